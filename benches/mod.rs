@@ -9,7 +9,7 @@ mod tests {
     use hbs_lms::{keygen, HssParameter, LmotsAlgorithm, LmsAlgorithm, Seed, Sha256_256};
     use hbs_lms::{
         signature::{SignerMut, Verifier},
-        Signature, SigningKey, VerifierSignature, VerifyingKey,
+        Signature, SigningKey, StatefulSigner, VerifierSignature, VerifyingKey,
     };
 
     const MESSAGE: [u8; 17] = [
@@ -190,6 +190,21 @@ mod tests {
         });
     }
 
+    #[bench]
+    fn sign_stateful_h5w2_h5w2(b: &mut Bencher) {
+        let hss_parameter = [
+            HssParameter::new(LmotsAlgorithm::LmotsW2, LmsAlgorithm::LmsH5),
+            HssParameter::new(LmotsAlgorithm::LmotsW2, LmsAlgorithm::LmsH5),
+        ];
+        let signing_key = generate_signing_key(&hss_parameter, None);
+        let mut signer = StatefulSigner::<Sha256_256>::new();
+
+        b.iter(|| {
+            let mut signing_key = signing_key.clone();
+            signer.try_sign(&MESSAGE, &mut signing_key, None).unwrap()
+        });
+    }
+
     #[bench]
     fn sign_with_aux_h5w2_h5w2(b: &mut Bencher) {
         let hss_parameter = [
@@ -126,34 +126,17 @@ fn sign(args: &ArgMatches) -> Result<(), std::io::Error> {
     let private_key_name = get_private_key_name(&keyname);
     let signature_name = get_signature_name(&message_name);
 
-    let private_key_data = read_file(&private_key_name);
+    let mut state_store = FileStateStore::new(private_key_name);
     let message_data = read_file(&message_name);
 
     let aux_data_name = get_aux_name(&keyname);
     let mut aux_data = read(aux_data_name).ok();
 
-    let mut private_key_update_function = |new_key: &[u8]| {
-        if write(&private_key_name, new_key).is_ok() {
-            return Ok(());
-        }
-        Err(())
-    };
-
     let result = if let Some(aux_data) = aux_data.as_mut() {
         let aux_slice = &mut &mut aux_data[..];
-        hbs_lms::sign::<Hasher>(
-            &message_data,
-            &private_key_data,
-            &mut private_key_update_function,
-            Some(aux_slice),
-        )
+        hbs_lms::sign::<Hasher>(&message_data, &mut state_store, Some(aux_slice))
     } else {
-        hbs_lms::sign::<Hasher>(
-            &message_data,
-            &private_key_data,
-            &mut private_key_update_function,
-            None,
-        )
+        hbs_lms::sign::<Hasher>(&message_data, &mut state_store, None)
     };
 
     if result.is_err() {
@@ -176,7 +159,7 @@ fn sign_mut(args: &ArgMatches) -> Result<(), std::io::Error> {
     let signature_name_mut = get_signature_mut_name(&message_name);
     let message_name_mut = get_message_mut_name(&message_name);
 
-    let private_key_data = read_file(&private_key_name);
+    let mut state_store = FileStateStore::new(private_key_name);
 
     let mut message_data = read_file(&message_name);
     message_data.extend_from_slice(&[0u8; 32]);
@@ -184,28 +167,11 @@ fn sign_mut(args: &ArgMatches) -> Result<(), std::io::Error> {
     let aux_data_name = get_aux_name(&keyname);
     let mut aux_data = read(aux_data_name).ok();
 
-    let mut private_key_update_function = |new_key: &[u8]| {
-        if write(&private_key_name, new_key).is_ok() {
-            return Ok(());
-        }
-        Err(())
-    };
-
     let signature_result = if let Some(aux_data) = aux_data.as_mut() {
         let aux_slice = &mut &mut aux_data[..];
-        hbs_lms::sign_mut::<Hasher>(
-            &mut message_data,
-            &private_key_data,
-            &mut private_key_update_function,
-            Some(aux_slice),
-        )
+        hbs_lms::sign_mut::<Hasher>(&mut message_data, &mut state_store, Some(aux_slice))
     } else {
-        hbs_lms::sign_mut::<Hasher>(
-            &mut message_data,
-            &private_key_data,
-            &mut private_key_update_function,
-            None,
-        )
+        hbs_lms::sign_mut::<Hasher>(&mut message_data, &mut state_store, None)
     };
 
     if signature_result.is_err() {
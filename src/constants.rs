@@ -48,6 +48,19 @@ pub const REF_IMPL_MAX_ALLOWED_HSS_LEVELS: usize = 8;
 pub const REF_IMPL_MAX_PRIVATE_KEY_SIZE: usize =
     HSS_COMPRESSED_USED_LEAFS_SIZE + REF_IMPL_MAX_ALLOWED_HSS_LEVELS + MAX_SEED_LEN;
 
+// Optional extensions appended to the private key of the reference implementation
+pub const PRIVATE_KEY_EXTENSION_HEADER_SIZE: usize = 2;
+pub const PRIVATE_KEY_LEAF_LIMIT_SIZE: usize = 8;
+pub const MAX_PRIVATE_KEY_EXTENSIONS_SIZE: usize = PRIVATE_KEY_EXTENSION_HEADER_SIZE
+    + PRIVATE_KEY_EXTENSION_HEADER_SIZE
+    + ILEN
+    + PRIVATE_KEY_EXTENSION_HEADER_SIZE
+    + PRIVATE_KEY_LEAF_LIMIT_SIZE
+    + PRIVATE_KEY_EXTENSION_HEADER_SIZE
+    + MAX_HASH_SIZE;
+pub const MAX_PRIVATE_KEY_SIZE: usize =
+    REF_IMPL_MAX_PRIVATE_KEY_SIZE + MAX_PRIVATE_KEY_EXTENSIONS_SIZE;
+
 pub const MAX_HASH_SIZE: usize = 32;
 pub const MAX_HASH_BLOCK_SIZE: usize = 64;
 
@@ -151,6 +164,10 @@ pub const MIN_SUBTREE: usize = 2; /* All subtrees (other than the root subtree)
 pub const DAUX_D: usize = 20;
 pub const DAUX_PREFIX_LEN: usize = 22; /* Not counting the seed value */
 pub const D_DAUX: u16 = 0xfdfd;
+pub const D_STATE_TAG: u16 = 0xfcfc;
+pub const D_CHECKPOINT: u16 = 0xfbfb;
+pub const D_WORK_UNIT: u16 = 0xfafa;
+pub const D_TREE_TRAVERSAL: u16 = 0xf9f9;
 
 pub mod winternitz_chain {
     use super::MAX_HASH_SIZE;
@@ -0,0 +1,131 @@
+use core::fmt;
+
+/**
+ * Errors of this crate.
+ *
+ * [`Error`] converts into [`signature::Error`], thus it can be used together with the traits of
+ * the [`signature`] crate. If the `std` feature is enabled, the [`Error`] is kept as source of the
+ * [`signature::Error`].
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The signature could not be parsed.
+    MalformedSignature,
+    /// The public key could not be parsed.
+    MalformedPublicKey,
+    /// The private key could not be parsed.
+    MalformedPrivateKey,
+    /// The LMS or LM-OTS typecode is unknown or not supported by this build.
+    UnsupportedTypecode(u32),
+    /// The parameters are invalid, e.g. too many HSS levels or an out of range leaf count.
+    InvalidParameters,
+    /// The HSS levels of the signature and the public key do not match.
+    LevelMismatch { signature: usize, public_key: usize },
+    /// The signature is invalid. `level` is the failing HSS level, starting with `0` at the top.
+    VerificationFailed { level: usize },
+    /// All one-time keys of the private key have been used.
+    KeyExhausted,
+    /// The HMAC of the auxiliary data does not match.
+    InvalidAuxData,
+    /// There is no auxiliary data, e.g. it was never generated or the buffer is too small.
+    MissingAuxData,
+    /// The private key state could not be locked, loaded or stored.
+    StateStore,
+    /// The state tag of the private key does not match, i.e. the private key was modified.
+    InvalidStateTag,
+    /// The private key state is older than the state recorded by the monotonic counter.
+    StateRollback,
+    /// The message does not fulfill the requirements of the signing function.
+    InvalidMessage,
+    /// The checkpoint of the key generation could not be parsed or its HMAC does not match.
+    InvalidCheckpoint,
+    /// The work unit could not be parsed, its HMAC does not match or it belongs to another key.
+    InvalidWorkUnit,
+    /// The work units do not cover all subtrees of the top-level tree.
+    IncompleteWorkUnits,
+    /// The tree traversal state could not be parsed or its HMAC does not match.
+    InvalidTreeTraversal,
+    /// The key generation was cancelled by its [`KeygenObserver`](crate::KeygenObserver).
+    Cancelled,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::MalformedSignature => write!(f, "malformed signature"),
+            Error::MalformedPublicKey => write!(f, "malformed public key"),
+            Error::MalformedPrivateKey => write!(f, "malformed private key"),
+            Error::UnsupportedTypecode(typecode) => {
+                write!(f, "unsupported typecode 0x{:08x}", typecode)
+            }
+            Error::InvalidParameters => write!(f, "invalid parameters"),
+            Error::LevelMismatch {
+                signature,
+                public_key,
+            } => write!(
+                f,
+                "signature with {} levels does not match public key with {} levels",
+                signature, public_key
+            ),
+            Error::VerificationFailed { level } => {
+                write!(f, "verification failed at HSS level {}", level)
+            }
+            Error::KeyExhausted => write!(f, "private key is exhausted"),
+            Error::InvalidAuxData => write!(f, "invalid auxiliary data"),
+            Error::MissingAuxData => write!(f, "missing auxiliary data"),
+            Error::StateStore => write!(f, "private key state could not be persisted"),
+            Error::InvalidStateTag => write!(f, "invalid private key state tag"),
+            Error::StateRollback => write!(f, "private key state was rolled back"),
+            Error::InvalidMessage => write!(f, "invalid message"),
+            Error::InvalidCheckpoint => write!(f, "invalid key generation checkpoint"),
+            Error::InvalidWorkUnit => write!(f, "invalid key generation work unit"),
+            Error::IncompleteWorkUnits => {
+                write!(
+                    f,
+                    "work units do not cover all subtrees of the top-level tree"
+                )
+            }
+            Error::InvalidTreeTraversal => write!(f, "invalid tree traversal state"),
+            Error::Cancelled => write!(f, "key generation was cancelled"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+impl From<Error> for signature::Error {
+    #[cfg(feature = "std")]
+    fn from(error: Error) -> Self {
+        signature::Error::from_source(error)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn from(_error: Error) -> Self {
+        signature::Error::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    #[test]
+    fn convert_into_signature_error() {
+        let error = Error::VerificationFailed { level: 1 };
+        let _signature_error: signature::Error = error.into();
+
+        #[cfg(feature = "std")]
+        {
+            use std::error::Error as _;
+
+            let source = _signature_error.source().unwrap();
+            assert_eq!(source.downcast_ref::<Error>(), Some(&error));
+            assert_eq!(
+                std::format!("{}", error),
+                "verification failed at HSS level 1"
+            );
+        }
+    }
+}
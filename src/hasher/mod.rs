@@ -38,12 +38,21 @@ impl DerefMut for HashChainData {
  * Requires PartialEq, to use compare within the tests.
  * This is required as long as this [issue](https://github.com/rust-lang/rust/issues/26925) is
  * open.
+ *
+ * Requires `Send` and `'static`, to pregenerate LMS trees on a background thread.
  * */
 pub trait HashChain:
-    Debug + Default + Clone + PartialEq + Send + Sync + FixedOutput + Update
+    Debug + Default + Clone + PartialEq + Send + Sync + 'static + FixedOutput + Update
 {
     const OUTPUT_SIZE: u16;
     const BLOCK_SIZE: u16;
+    /// Offset of the LMS typecodes of this hash function and output size to the SHA-256/M32
+    /// typecodes, according to NIST SP 800-208 and the IANA LMS registry. Output sizes without
+    /// registered typecodes use the private use range `0xdddddddd` to `0xffffffff` of RFC 8554.
+    const LMS_TYPECODE_OFFSET: u32 = 0;
+    /// Offset of the LM-OTS typecodes of this hash function and output size to the SHA-256/N32
+    /// typecodes, as for [`Self::LMS_TYPECODE_OFFSET`].
+    const LMOTS_TYPECODE_OFFSET: u32 = 0;
 
     fn finalize(self) -> ArrayVec<[u8; MAX_HASH_SIZE]>;
     fn finalize_reset(&mut self) -> ArrayVec<[u8; MAX_HASH_SIZE]>;
@@ -79,6 +88,16 @@ pub trait HashChain:
         ArrayVec::try_from(&hc_data[ITER_PREV..]).unwrap()
     }
 
+    /**
+     * Hashes `data` on a freshly initialized hasher, which is reset afterwards. Used for the short
+     * inputs of the LM-OTS private keys and the LMS tree nodes, which implementations may hash
+     * faster than with `update` and `finalize_reset`.
+     */
+    fn hash_short(&mut self, data: &[u8]) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+        self.update(data);
+        self.finalize_reset()
+    }
+
     fn do_actual_hash_chain(&mut self, hc_data: &mut HashChainData, from: usize, to: usize) {
         for j in from..to {
             hc_data[ITER_J] = j as u8;
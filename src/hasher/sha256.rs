@@ -2,16 +2,71 @@ use core::convert::TryFrom;
 use tinyvec::ArrayVec;
 
 use sha2::{
-    digest::{typenum::U32, FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Update},
+    compress256,
+    digest::{
+        generic_array::GenericArray,
+        typenum::{U32, U64},
+        FixedOutput, FixedOutputReset, Output, OutputSizeUser, Reset, Update,
+    },
     Sha256 as Hasher,
 };
 
-use crate::constants::MAX_HASH_SIZE;
+use crate::constants::{
+    winternitz_chain::{ITER_J, ITER_MAX_LEN, ITER_PREV},
+    MAX_HASH_SIZE,
+};
+
+use super::{HashChain, HashChainData};
+
+type Block = GenericArray<u8, U64>;
+
+const BLOCK_LEN: usize = 64;
+
+/// Length of the `0x80` byte and the message length, which pad the last block.
+const PADDING_LEN: usize = 9;
+
+/// Longest input [`hash_short`] hashes in two blocks.
+const MAX_SHORT_LEN: usize = 2 * BLOCK_LEN - PADDING_LEN;
+
+// The data of a hash chain step is compressed as a single block
+const _: () = assert!(ITER_MAX_LEN + PADDING_LEN <= BLOCK_LEN);
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Pads `block`, whose first `len` bytes are the whole message.
+fn pad(block: &mut Block, len: usize) {
+    block[len] = 0x80;
+    block[len + 1..BLOCK_LEN - 8].fill(0);
+    block[BLOCK_LEN - 8..].copy_from_slice(&(len as u64 * 8).to_be_bytes());
+}
+
+/// Compresses the already padded `blocks` from the initial state.
+fn compress(blocks: &[Block]) -> [u8; 32] {
+    let mut state = INITIAL_STATE;
+    compress256(&mut state, blocks);
+
+    let mut digest = [0u8; 32];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state.iter()) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
 
-use super::HashChain;
+/// SHA-256 of at most [`MAX_SHORT_LEN`] bytes, without the buffering of [`Hasher`].
+fn hash_short(data: &[u8]) -> [u8; 32] {
+    let mut blocks = [Block::default(); 2];
+    let count = (data.len() + PADDING_LEN + BLOCK_LEN - 1) / BLOCK_LEN;
+    for (index, byte) in data.iter().chain(&[0x80]).enumerate() {
+        blocks[index / BLOCK_LEN][index % BLOCK_LEN] = *byte;
+    }
+    blocks[count - 1][BLOCK_LEN - 8..].copy_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+    compress(&blocks[..count])
+}
 
 macro_rules! define_sha {
-    ($name:ident, $output_size:expr) => {
+    ($name:ident, $output_size:expr, $lms_typecode_offset:expr, $lmots_typecode_offset:expr) => {
         /**
          * Extension of [`sha2::Sha256`], which can be passed into the library, as it implements the [`HashChain`] trait.
          * */
@@ -23,6 +78,8 @@ macro_rules! define_sha {
         impl HashChain for $name {
             const OUTPUT_SIZE: u16 = $output_size;
             const BLOCK_SIZE: u16 = 64;
+            const LMS_TYPECODE_OFFSET: u32 = $lms_typecode_offset;
+            const LMOTS_TYPECODE_OFFSET: u32 = $lmots_typecode_offset;
 
             fn finalize(self) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
                 ArrayVec::try_from(&self.hasher.finalize_fixed()[..(Self::OUTPUT_SIZE as usize)])
@@ -35,6 +92,39 @@ macro_rules! define_sha {
                 )
                 .unwrap()
             }
+
+            fn hash_short(&mut self, data: &[u8]) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+                if data.len() > MAX_SHORT_LEN {
+                    self.update(data);
+                    return self.finalize_reset();
+                }
+                ArrayVec::try_from(&hash_short(data)[..(Self::OUTPUT_SIZE as usize)]).unwrap()
+            }
+
+            /**
+             * `hc_data` fits into a single block, which is padded once and compressed directly
+             * on every step of the chain.
+             */
+            fn do_actual_hash_chain(
+                &mut self,
+                hc_data: &mut HashChainData,
+                from: usize,
+                to: usize,
+            ) {
+                let len = hc_data.len();
+                let mut block = [Block::default()];
+                block[0][..len].copy_from_slice(hc_data);
+                pad(&mut block[0], len);
+
+                for j in from..to {
+                    block[0][ITER_J] = j as u8;
+                    let digest = compress(&block);
+                    block[0][ITER_PREV..len]
+                        .copy_from_slice(&digest[..(Self::OUTPUT_SIZE as usize)]);
+                }
+
+                hc_data.copy_from_slice(&block[0][..len]);
+            }
         }
 
         impl OutputSizeUser for $name {
@@ -73,8 +163,66 @@ macro_rules! define_sha {
     };
 }
 
-define_sha!(Sha256_256, 32);
+define_sha!(Sha256_256, 32, 0, 0);
+
+define_sha!(Sha256_192, 24, 5, 4);
+
+// No typecodes are registered for 128 bit outputs, typecodes of the private use range are used
+define_sha!(Sha256_128, 16, 0xf000_0100, 0xf000_0100);
 
-define_sha!(Sha256_192, 24);
+#[cfg(test)]
+mod tests {
+    use sha2::Digest;
 
-define_sha!(Sha256_128, 16);
+    use super::*;
+
+    fn reference_hash_chain<H: HashChain>(hc_data: &mut HashChainData, from: usize, to: usize) {
+        let mut hasher = H::default();
+        for j in from..to {
+            hc_data[ITER_J] = j as u8;
+            hasher.update(hc_data);
+            let temp_hash = hasher.finalize_reset();
+            hc_data[ITER_PREV..].copy_from_slice(temp_hash.as_slice());
+        }
+    }
+
+    fn hash_chain_matches_reference<H: HashChain>() {
+        let mut hc_data = H::prepare_hash_chain_data(&[7u8; 16], &[1, 2, 3, 4]);
+        for (index, byte) in hc_data[ITER_PREV..].iter_mut().enumerate() {
+            *byte = index as u8;
+        }
+        let mut expected = H::prepare_hash_chain_data(&[0u8; 16], &[0u8; 4]);
+        expected.copy_from_slice(&hc_data);
+
+        H::default().do_actual_hash_chain(&mut hc_data, 3, 255);
+        reference_hash_chain::<H>(&mut expected, 3, 255);
+        assert_eq!(hc_data.as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn hash_short_matches_sha256() {
+        let data: ArrayVec<[u8; 2 * BLOCK_LEN]> = (0..2 * BLOCK_LEN).map(|i| i as u8).collect();
+        for len in 0..=data.len() {
+            let expected = Hasher::digest(&data[..len]);
+            assert_eq!(
+                Sha256_256::default().hash_short(&data[..len]).as_slice(),
+                &expected[..]
+            );
+            assert_eq!(
+                Sha256_192::default().hash_short(&data[..len]).as_slice(),
+                &expected[..24]
+            );
+            assert_eq!(
+                Sha256_128::default().hash_short(&data[..len]).as_slice(),
+                &expected[..16]
+            );
+        }
+    }
+
+    #[test]
+    fn hash_chain_matches_sha256() {
+        hash_chain_matches_reference::<Sha256_256>();
+        hash_chain_matches_reference::<Sha256_192>();
+        hash_chain_matches_reference::<Sha256_128>();
+    }
+}
@@ -13,7 +13,7 @@ use crate::constants::MAX_HASH_SIZE;
 use super::HashChain;
 
 macro_rules! define_shake {
-    ($name:ident, $output_size:expr) => {
+    ($name:ident, $output_size:expr, $lms_typecode_offset:expr, $lmots_typecode_offset:expr) => {
         /**
          * Extension of [`sha3::Shake256`], which can be passed into the library, as it implements the [`HashChain`] trait.
          * */
@@ -25,6 +25,8 @@ macro_rules! define_shake {
         impl HashChain for $name {
             const OUTPUT_SIZE: u16 = $output_size;
             const BLOCK_SIZE: u16 = 64;
+            const LMS_TYPECODE_OFFSET: u32 = $lms_typecode_offset;
+            const LMOTS_TYPECODE_OFFSET: u32 = $lmots_typecode_offset;
 
             fn finalize(self) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
                 let mut digest = [0u8; MAX_HASH_SIZE];
@@ -75,8 +77,9 @@ macro_rules! define_shake {
     };
 }
 
-define_shake!(Shake256_256, 32);
+define_shake!(Shake256_256, 32, 10, 8);
 
-define_shake!(Shake256_192, 24);
+define_shake!(Shake256_192, 24, 15, 12);
 
-define_shake!(Shake256_128, 16);
+// No typecodes are registered for 128 bit outputs, typecodes of the private use range are used
+define_shake!(Shake256_128, 16, 0xf000_0200, 0xf000_0200);
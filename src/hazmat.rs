@@ -0,0 +1,357 @@
+//! Low-level building blocks of LMS: LM-OTS, Merkle tree nodes and seed derivation.
+//!
+//! # Hazardous material
+//!
+//! The functions of this module keep no state. Nothing prevents signing two different messages
+//! with the same LM-OTS key, i.e. with the same `lms_tree_identifier` and `lms_leaf_identifier`,
+//! which reveals enough of the private key to forge signatures. Use [`crate::sign`] or
+//! [`crate::lms::sign`] unless the state is tracked by the caller, e.g. for interoperability tests
+//! or for distributing the signature generation.
+//!
+//! All functions follow [RFC 8554](<https://datatracker.ietf.org/doc/html/rfc8554>) and expect
+//! the private key elements to be derived from `seed` as described in its Appendix A.
+
+use core::convert::TryFrom;
+use tinyvec::ArrayVec;
+
+use crate::{
+    constants::{MAX_LMOTS_SIGNATURE_LENGTH, MAX_TREE_HEIGHT},
+    hasher::HashChain,
+    hss::{parameter::HssParameter, seed_derive::SeedDerive},
+    lm_ots::{
+        self, parameters::LmotsParameter, signing::InMemoryLmotsSignature, signing::LmotsSignature,
+    },
+    lms::{
+        definitions::LmsPrivateKey,
+        helper::{get_authentication_path, get_tree_element},
+    },
+    Error, LmotsAlgorithm, Seed,
+};
+
+pub use crate::constants::{LmsTreeIdentifier, Node};
+
+/**
+ * Derives `H(I || u32str(q) || u16str(j) || u8str(0xff) || SEED)` as defined in
+ * [RFC 8554 Appendix A](<https://datatracker.ietf.org/doc/html/rfc8554#appendix-A>).
+ */
+pub fn seed_derive<H: HashChain>(
+    seed: &Seed<H>,
+    lms_tree_identifier: &LmsTreeIdentifier,
+    lms_leaf_identifier: u32,
+    j: u16,
+) -> Node {
+    let mut derive = SeedDerive::new(seed, lms_tree_identifier);
+    derive.set_lms_leaf_identifier(lms_leaf_identifier);
+    derive.set_child_seed(j);
+    derive.seed_derive(false)
+}
+
+/**
+ * Computes the LM-OTS public key `K` of leaf `lms_leaf_identifier`.
+ */
+pub fn lmots_public_key<H: HashChain>(
+    lmots_algorithm: LmotsAlgorithm,
+    seed: &Seed<H>,
+    lms_tree_identifier: &LmsTreeIdentifier,
+    lms_leaf_identifier: u32,
+) -> Result<Node, Error> {
+    let lmots_parameter = construct_lmots_parameter(lmots_algorithm)?;
+    let private_key = lm_ots::keygen::generate_private_key(
+        *lms_tree_identifier,
+        lms_leaf_identifier.to_be_bytes(),
+        seed.clone(),
+        lmots_parameter,
+    );
+
+    Ok(lm_ots::keygen::generate_public_key(&private_key).key)
+}
+
+/**
+ * Signs `message` with the LM-OTS key of leaf `lms_leaf_identifier`.
+ *
+ * Returns the serialized LM-OTS signature. `signature_randomizer` is the value `C` of the
+ * signature and must have the output size of the hash function.
+ */
+pub fn lmots_sign<H: HashChain>(
+    lmots_algorithm: LmotsAlgorithm,
+    seed: &Seed<H>,
+    lms_tree_identifier: &LmsTreeIdentifier,
+    lms_leaf_identifier: u32,
+    signature_randomizer: &[u8],
+    message: &[u8],
+) -> Result<ArrayVec<[u8; MAX_LMOTS_SIGNATURE_LENGTH]>, Error> {
+    let lmots_parameter = construct_lmots_parameter(lmots_algorithm)?;
+    if signature_randomizer.len() != H::OUTPUT_SIZE as usize {
+        return Err(Error::InvalidParameters);
+    }
+    let signature_randomizer = Node::try_from(signature_randomizer).unwrap();
+
+    let private_key = lm_ots::keygen::generate_private_key(
+        *lms_tree_identifier,
+        lms_leaf_identifier.to_be_bytes(),
+        seed.clone(),
+        lmots_parameter,
+    );
+
+    Ok(
+        LmotsSignature::sign(&private_key, &signature_randomizer, message)
+            .to_binary_representation(),
+    )
+}
+
+/**
+ * Computes the LM-OTS public key candidate `Kc` of a serialized LM-OTS signature, as defined in
+ * [RFC 8554 Algorithm 4b](<https://datatracker.ietf.org/doc/html/rfc8554#section-4.6>).
+ */
+pub fn lmots_public_key_candidate<H: HashChain>(
+    signature: &[u8],
+    lms_tree_identifier: &LmsTreeIdentifier,
+    lms_leaf_identifier: u32,
+    message: &[u8],
+) -> Result<Node, Error> {
+    let signature = InMemoryLmotsSignature::<H>::new(signature)?;
+
+    Ok(lm_ots::verify::generate_public_key_candidate(
+        &signature,
+        lms_tree_identifier,
+        lms_leaf_identifier,
+        message,
+    ))
+}
+
+/**
+ * Verifies a serialized LM-OTS signature against the LM-OTS public key `K`.
+ */
+pub fn lmots_verify<H: HashChain>(
+    signature: &[u8],
+    public_key: &[u8],
+    lms_tree_identifier: &LmsTreeIdentifier,
+    lms_leaf_identifier: u32,
+    message: &[u8],
+) -> Result<(), Error> {
+    let public_key_candidate = lmots_public_key_candidate::<H>(
+        signature,
+        lms_tree_identifier,
+        lms_leaf_identifier,
+        message,
+    )?;
+
+    if public_key_candidate.as_slice() == public_key {
+        Ok(())
+    } else {
+        Err(Error::VerificationFailed { level: 0 })
+    }
+}
+
+/**
+ * Computes the Merkle tree node `T[r]`.
+ *
+ * `r = 1` is the root of the tree, the leaves are at `2^h <= r < 2^(h+1)`. Computing an inner
+ * node requires the LM-OTS public keys of all leaves below it.
+ */
+pub fn tree_node<H: HashChain>(
+    parameter: &HssParameter<H>,
+    seed: &Seed<H>,
+    lms_tree_identifier: &LmsTreeIdentifier,
+    r: u32,
+) -> Result<Node, Error> {
+    let private_key = lms_private_key(parameter, seed, lms_tree_identifier);
+
+    if r == 0 || r as usize >= 2 * private_key.lms_parameter.number_of_lm_ots_keys() {
+        return Err(Error::InvalidParameters);
+    }
+
+    Ok(get_tree_element(r as usize, &private_key, &mut None))
+}
+
+/**
+ * Computes the authentication path of leaf `lms_leaf_identifier`, starting with the sibling of the
+ * leaf and ending with a child of the root.
+ */
+pub fn authentication_path<H: HashChain>(
+    parameter: &HssParameter<H>,
+    seed: &Seed<H>,
+    lms_tree_identifier: &LmsTreeIdentifier,
+    lms_leaf_identifier: u32,
+) -> Result<ArrayVec<[Node; MAX_TREE_HEIGHT]>, Error> {
+    let private_key = lms_private_key(parameter, seed, lms_tree_identifier);
+
+    if lms_leaf_identifier as usize >= private_key.lms_parameter.number_of_lm_ots_keys() {
+        return Err(Error::InvalidParameters);
+    }
+
+    Ok(get_authentication_path(
+        lms_leaf_identifier,
+        &private_key,
+        &mut None,
+    ))
+}
+
+fn construct_lmots_parameter<H: HashChain>(
+    lmots_algorithm: LmotsAlgorithm,
+) -> Result<LmotsParameter<H>, Error> {
+    lmots_algorithm
+        .construct_parameter()
+        .ok_or(Error::InvalidParameters)
+}
+
+fn lms_private_key<H: HashChain>(
+    parameter: &HssParameter<H>,
+    seed: &Seed<H>,
+    lms_tree_identifier: &LmsTreeIdentifier,
+) -> LmsPrivateKey<H> {
+    LmsPrivateKey::new(
+        seed.clone(),
+        *lms_tree_identifier,
+        0,
+        *parameter.get_lmots_parameter(),
+        *parameter.get_lms_parameter(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::D_LEAF, lms, util::helper::test_helper::gen_random_seed, LmsAlgorithm,
+        Sha256_256,
+    };
+    use digest::Update;
+
+    type H = Sha256_256;
+
+    const LMS_TREE_IDENTIFIER: LmsTreeIdentifier = [0x5a; 16];
+
+    #[test]
+    fn seed_derive_follows_rfc8554_appendix_a() {
+        let seed = gen_random_seed::<H>();
+
+        let mut buffer = [0u8; 23 + 32];
+        buffer[..16].copy_from_slice(&LMS_TREE_IDENTIFIER);
+        buffer[16..20].copy_from_slice(&7u32.to_be_bytes());
+        buffer[20..22].copy_from_slice(&3u16.to_be_bytes());
+        buffer[22] = 0xff;
+        buffer[23..].copy_from_slice(seed.as_slice());
+
+        assert_eq!(
+            seed_derive(&seed, &LMS_TREE_IDENTIFIER, 7, 3),
+            H::default().chain(buffer).finalize()
+        );
+    }
+
+    #[test]
+    fn lmots_sign_and_verify() {
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW4;
+        let randomizer = [0x42u8; 32];
+
+        let public_key = lmots_public_key(lmots, &seed, &LMS_TREE_IDENTIFIER, 3).unwrap();
+        let signature = lmots_sign(
+            lmots,
+            &seed,
+            &LMS_TREE_IDENTIFIER,
+            3,
+            &randomizer,
+            b"message",
+        )
+        .unwrap();
+
+        assert_eq!(
+            lmots_public_key_candidate::<H>(&signature, &LMS_TREE_IDENTIFIER, 3, b"message"),
+            Ok(public_key)
+        );
+        assert!(
+            lmots_verify::<H>(&signature, &public_key, &LMS_TREE_IDENTIFIER, 3, b"message").is_ok()
+        );
+        assert_eq!(
+            lmots_verify::<H>(&signature, &public_key, &LMS_TREE_IDENTIFIER, 3, b"massage"),
+            Err(Error::VerificationFailed { level: 0 })
+        );
+        assert!(
+            lmots_verify::<H>(&signature, &public_key, &LMS_TREE_IDENTIFIER, 4, b"message")
+                .is_err()
+        );
+
+        assert_eq!(
+            lmots_sign(
+                lmots,
+                &seed,
+                &LMS_TREE_IDENTIFIER,
+                3,
+                &randomizer[..16],
+                b"message"
+            ),
+            Err(Error::InvalidParameters)
+        );
+        assert_eq!(
+            lmots_public_key(
+                LmotsAlgorithm::LmotsReserved,
+                &seed,
+                &LMS_TREE_IDENTIFIER,
+                3
+            ),
+            Err(Error::InvalidParameters)
+        );
+    }
+
+    #[test]
+    fn tree_nodes_match_lms_key_and_signature() {
+        let seed = gen_random_seed::<H>();
+        let parameter = HssParameter::<H>::new(LmotsAlgorithm::LmotsW4, LmsAlgorithm::LmsH5);
+
+        let (mut signing_key, verifying_key) =
+            lms::keygen_with_lms_tree_identifier(parameter, &seed, &LMS_TREE_IDENTIFIER, None)
+                .unwrap();
+
+        let root = tree_node(&parameter, &seed, &LMS_TREE_IDENTIFIER, 1).unwrap();
+        assert_eq!(&verifying_key.as_slice()[24..], root.as_slice());
+
+        let lmots_public_key =
+            lmots_public_key(LmotsAlgorithm::LmotsW4, &seed, &LMS_TREE_IDENTIFIER, 0).unwrap();
+        let leaf = H::default()
+            .chain(LMS_TREE_IDENTIFIER)
+            .chain(32u32.to_be_bytes())
+            .chain(D_LEAF)
+            .chain(lmots_public_key.as_slice())
+            .finalize();
+        assert_eq!(
+            tree_node(&parameter, &seed, &LMS_TREE_IDENTIFIER, 32),
+            Ok(leaf)
+        );
+        assert_eq!(
+            tree_node(&parameter, &seed, &LMS_TREE_IDENTIFIER, 0),
+            Err(Error::InvalidParameters)
+        );
+        assert_eq!(
+            tree_node(&parameter, &seed, &LMS_TREE_IDENTIFIER, 64),
+            Err(Error::InvalidParameters)
+        );
+
+        let signature = lms::sign::<H>(b"message", &mut signing_key, None).unwrap();
+        let signature = signature.as_ref();
+        let lmots_signature_length = 4 + 32 + 32 * 67;
+
+        let lmots_signature = &signature[4..4 + lmots_signature_length];
+        assert!(lmots_verify::<H>(
+            lmots_signature,
+            &lmots_public_key,
+            &LMS_TREE_IDENTIFIER,
+            0,
+            b"message"
+        )
+        .is_ok());
+
+        let path = authentication_path(&parameter, &seed, &LMS_TREE_IDENTIFIER, 0).unwrap();
+        assert_eq!(path.len(), 5);
+        for (node, expected) in path
+            .iter()
+            .zip(signature[4 + lmots_signature_length + 4..].chunks(32))
+        {
+            assert_eq!(node.as_slice(), expected);
+        }
+        assert_eq!(
+            authentication_path(&parameter, &seed, &LMS_TREE_IDENTIFIER, 32),
+            Err(Error::InvalidParameters)
+        );
+    }
+}
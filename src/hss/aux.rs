@@ -8,12 +8,14 @@ use tinyvec::ArrayVec;
 
 use crate::{
     constants::{
-        DAUX_D, DAUX_PREFIX_LEN, D_DAUX, MAX_HASH_BLOCK_SIZE, MAX_HASH_SIZE, MAX_TREE_HEIGHT,
-        MIN_SUBTREE,
+        DAUX_D, DAUX_PREFIX_LEN, D_DAUX, ILEN, MAX_ALLOWED_HSS_LEVELS, MAX_HASH_BLOCK_SIZE,
+        MAX_HASH_SIZE, MAX_TREE_HEIGHT, MIN_SUBTREE,
     },
     hasher::HashChain,
+    hss::parameter::HssParameter,
     lms::parameters::LmsParameter,
-    util::helper::read_and_advance,
+    util::helper::try_read_and_advance,
+    Error,
 };
 
 type AuxLevel = u32;
@@ -30,8 +32,22 @@ pub struct MutableExpandedAuxData<'a> {
     pub data: [Option<&'a mut [u8]>; MAX_TREE_HEIGHT + 1],
     pub level: u32,
     pub hmac: &'a mut [u8],
+    /// Level and index of the subtree covered by `data`, if it does not cover the whole tree.
+    pub subtree_level: usize,
+    pub subtree_index: usize,
+    /// Identifier of the tree cached by the aux data of a lower-level tree.
+    pub lms_tree_identifier: Option<&'a mut [u8]>,
+    /// Aux data of the lower-level trees, which follows the aux data of the top-level tree.
+    pub lower: Option<&'a mut [u8]>,
+    /// Computed nodes are not saved, which keeps the HMAC of incomplete aux data valid.
+    pub read_only: bool,
+    /// Whether nodes were saved, such that the HMAC needs to be updated.
+    pub changed: bool,
 }
 
+/// Aux data of the lower-level trees, indexed by their level. The top level is always `None`.
+pub type LowerAuxData<'a> = ArrayVec<[Option<MutableExpandedAuxData<'a>>; MAX_ALLOWED_HSS_LEVELS]>;
+
 pub fn hss_optimal_aux_level<H: HashChain>(
     mut max_length: usize,
     lms_parameter: LmsParameter<H>,
@@ -70,61 +86,298 @@ pub fn hss_optimal_aux_level<H: HashChain>(
 pub fn hss_expand_aux_data<'a, H: HashChain>(
     aux_data: Option<&'a mut [u8]>,
     seed: Option<&'a [u8]>,
-) -> Option<MutableExpandedAuxData<'a>> {
+) -> Result<Option<MutableExpandedAuxData<'a>>, Error> {
     let mut index = 0;
 
     let mut expanded_aux_data: MutableExpandedAuxData = Default::default();
 
-    let mut aux_data = aux_data.unwrap();
+    let mut aux_data = match aux_data {
+        Some(aux_data) if !aux_data.is_empty() => aux_data,
+        _ => return Ok(None),
+    };
 
     if aux_data[AUX_DATA_MARKER] == NO_AUX_DATA {
-        return None;
+        return Ok(None);
     }
 
+    // Aux data that fails the check is not used, the trees are computed instead. It is reported
+    // by `AuxData::validate`.
+    let len_aux_data = match top_aux_data_len::<H>(aux_data, seed) {
+        Ok(len_aux_data) => len_aux_data,
+        Err(_) => return Ok(None),
+    };
+
     // REMARK: Reference implementation treats that as u64 and ANDs it with 0x7ffffffffL after its stored in expanded_aux_data
     // However in our opinion that should make no difference, because we only read 4 bytes.
     expanded_aux_data.level = u32::from_be_bytes(
-        read_and_advance(aux_data, 4, &mut index)
-            .try_into()
-            .unwrap(),
+        try_read_and_advance(
+            &aux_data[..len_aux_data],
+            4,
+            &mut index,
+            Error::InvalidAuxData,
+        )?
+        .try_into()
+        .unwrap(),
     );
 
-    const LEN_LAYER_SIZES: usize = 1 + MAX_TREE_HEIGHT;
-    let mut layer_sizes: ArrayVec<[usize; LEN_LAYER_SIZES]> =
-        ArrayVec::from([0usize; LEN_LAYER_SIZES]);
-    for index in 0..layer_sizes.capacity() {
-        if (expanded_aux_data.level >> index) & 1 == 0 {
-            continue;
-        }
-        layer_sizes[index] = (H::OUTPUT_SIZE as usize) << index;
+    aux_data = &mut aux_data[index..];
+    let lower = split_aux_layers::<H>(&mut expanded_aux_data, aux_data);
+    if !lower.is_empty() {
+        expanded_aux_data.lower = Some(lower);
+    }
+
+    Ok(Some(expanded_aux_data))
+}
+
+/**
+ * Returns the length of the aux data of the top-level tree without its HMAC. The HMAC is checked
+ * if `seed` is given.
+ */
+fn top_aux_data_len<H: HashChain>(aux_data: &[u8], seed: Option<&[u8]>) -> Result<usize, Error> {
+    if aux_data.len() < AUX_DATA_HASHES {
+        return Err(Error::InvalidAuxData);
+    }
+    let aux_level = u32::from_be_bytes(aux_data[..AUX_DATA_HASHES].try_into().unwrap());
+
+    let len_aux_data = AUX_DATA_HASHES + aux_layers_len::<H>(aux_level);
+    if aux_data.len() < len_aux_data + H::OUTPUT_SIZE as usize {
+        return Err(Error::InvalidAuxData);
     }
 
     // Check if data is valid
     if let Some(seed) = seed {
-        let len_aux_data = index + layer_sizes.iter().sum::<usize>();
         let (aux_data, aux_data_mac) = aux_data.split_at(len_aux_data);
+        let aux_data_mac = &aux_data_mac[..H::OUTPUT_SIZE as usize];
 
-        let key = compute_seed_derive::<H>(seed);
+        let key = compute_seed_derive::<H>(seed, D_DAUX);
         if !bool::from(compute_hmac::<H>(&key, aux_data).ct_eq(aux_data_mac)) {
-            return None;
+            return Err(Error::InvalidAuxData);
         }
     }
 
-    aux_data = &mut aux_data[index..];
+    Ok(len_aux_data)
+}
+
+/**
+ * Returns the length of the aux data of the next lower-level tree at the start of `aux_data`, or
+ * `None` if there is none.
+ */
+fn lower_aux_data_len<H: HashChain>(aux_data: &[u8]) -> Result<Option<usize>, Error> {
+    if aux_data.len() < AUX_DATA_HASHES || aux_data[AUX_DATA_MARKER] == NO_AUX_DATA {
+        return Ok(None);
+    }
+    let aux_level = u32::from_be_bytes(aux_data[..AUX_DATA_HASHES].try_into().unwrap());
+
+    let len_aux_data =
+        AUX_DATA_HASHES + ILEN + aux_layers_len::<H>(aux_level) + H::OUTPUT_SIZE as usize;
+    if aux_data.len() < len_aux_data {
+        return Err(Error::InvalidAuxData);
+    }
+
+    Ok(Some(len_aux_data))
+}
+
+/**
+ * Checks the aux data without modifying it. Fails with [`Error::InvalidAuxData`] if the HMAC of
+ * the top-level tree does not match or the aux data is truncated. The aux data of the lower-level
+ * trees is checked against their current tree when signing, see [`hss_is_lower_aux_data_valid`].
+ * Returns whether there is aux data at all.
+ */
+pub fn hss_check_aux_data<H: HashChain>(
+    aux_data: &[u8],
+    seed: &[u8],
+    levels: usize,
+) -> Result<bool, Error> {
+    if aux_data.is_empty() || !hss_is_aux_data_used(aux_data) {
+        return Ok(false);
+    }
+
+    let mut index = top_aux_data_len::<H>(aux_data, Some(seed))? + H::OUTPUT_SIZE as usize;
+    for _ in 1..levels {
+        match lower_aux_data_len::<H>(&aux_data[index..])? {
+            Some(len_aux_data) => index += len_aux_data,
+            None => break,
+        }
+    }
+
+    Ok(true)
+}
 
-    for (index, layer_size) in layer_sizes
+fn aux_layers_len<H: HashChain>(aux_level: AuxLevel) -> usize {
+    (0..=MAX_TREE_HEIGHT)
+        .filter(|index| (aux_level >> index) & 1 != 0)
+        .map(|index| (H::OUTPUT_SIZE as usize) << index)
+        .sum()
+}
+
+/**
+ * Assigns the layers selected by `data.level` and the following HMAC to `data`. The length of
+ * `aux_data` has to be checked beforehand. Returns the remaining aux data.
+ */
+fn split_aux_layers<'a, H: HashChain>(
+    data: &mut MutableExpandedAuxData<'a>,
+    mut aux_data: &'a mut [u8],
+) -> &'a mut [u8] {
+    for index in 0..=MAX_TREE_HEIGHT {
+        if (data.level >> index) & 1 == 0 {
+            continue;
+        }
+        let (layer, rest) = aux_data.split_at_mut((H::OUTPUT_SIZE as usize) << index);
+
+        data.data[index] = Some(layer);
+        aux_data = rest;
+    }
+    let (hmac, rest) = aux_data.split_at_mut(H::OUTPUT_SIZE as usize);
+    data.hmac = hmac;
+
+    rest
+}
+
+/**
+ * Lays out the aux data of the lower-level trees with `parameters` in `aux_data`, which is the
+ * space left behind the aux data of the top-level tree. Every level gets the optimal aux level
+ * for the remaining space, starting from the level below the top-level tree. The aux data is
+ * only filled once the trees are computed while signing. Returns the length of the layout.
+ */
+pub fn hss_store_lower_aux_markers<H: HashChain>(
+    aux_data: &mut [u8],
+    parameters: &[HssParameter<H>],
+) -> usize {
+    let mut len = 0;
+
+    for (aux_level, len_aux_data) in lower_aux_layout(aux_data.len(), parameters) {
+        // The zeroed tree identifier doesn't match any tree, which resets the aux data on first use
+        let aux_data = &mut aux_data[len..len + len_aux_data];
+        aux_data.fill(0);
+        hss_store_aux_marker(aux_data, aux_level);
+        len += len_aux_data;
+    }
+
+    len
+}
+
+/**
+ * Returns the length of the aux data of the lower-level trees with `parameters` within
+ * `max_length` bytes, see [`hss_store_lower_aux_markers`].
+ */
+pub fn hss_get_lower_aux_data_len<H: HashChain>(
+    max_length: usize,
+    parameters: &[HssParameter<H>],
+) -> usize {
+    lower_aux_layout(max_length, parameters)
         .iter()
-        .enumerate()
-        .filter(|(_, size)| **size != 0)
-    {
-        let (data, data_rest) = aux_data.split_at_mut(*layer_size);
+        .map(|(_, len_aux_data)| len_aux_data)
+        .sum()
+}
+
+fn lower_aux_layout<H: HashChain>(
+    mut max_length: usize,
+    parameters: &[HssParameter<H>],
+) -> ArrayVec<[(AuxLevel, usize); MAX_ALLOWED_HSS_LEVELS]> {
+    let mut layout = ArrayVec::new();
+
+    for parameter in parameters {
+        if max_length < ILEN {
+            break;
+        }
+
+        let mut len_aux_data = 0;
+        let aux_level = hss_optimal_aux_level(
+            max_length - ILEN,
+            *parameter.get_lms_parameter(),
+            Some(&mut len_aux_data),
+        );
+        if aux_level == 0 {
+            break;
+        }
+
+        layout.push((aux_level, ILEN + len_aux_data));
+        max_length -= ILEN + len_aux_data;
+    }
+
+    layout
+}
+
+/**
+ * Splits the aux data of the lower-level trees, which follows the aux data of the top-level tree
+ * in `aux_data`, into one [`MutableExpandedAuxData`] per level. Every level consists of the aux
+ * level, the identifier of the cached tree, the layers and a HMAC over all of them.
+ *
+ * The aux data of a level isn't checked here, as it changes with the tree of that level. See
+ * [`hss_is_lower_aux_data_valid`].
+ */
+pub fn hss_expand_lower_aux_data<'a, H: HashChain>(
+    aux_data: &mut Option<MutableExpandedAuxData<'a>>,
+    levels: usize,
+) -> Result<LowerAuxData<'a>, Error> {
+    let mut lower_aux_data = LowerAuxData::new();
+    lower_aux_data.push(None);
+
+    let mut lower = aux_data
+        .as_mut()
+        .and_then(|aux_data| aux_data.lower.take())
+        .unwrap_or_default();
+
+    while lower_aux_data.len() < levels {
+        // Truncated aux data is not used, like the aux data of the top-level tree
+        if !matches!(lower_aux_data_len::<H>(lower), Ok(Some(_))) {
+            break;
+        }
+
+        let mut expanded_aux_data = MutableExpandedAuxData {
+            level: u32::from_be_bytes(lower[..AUX_DATA_HASHES].try_into().unwrap()),
+            ..Default::default()
+        };
+
+        let (lms_tree_identifier, rest) =
+            core::mem::take(&mut lower)[AUX_DATA_HASHES..].split_at_mut(ILEN);
+        expanded_aux_data.lms_tree_identifier = Some(lms_tree_identifier);
+        lower = split_aux_layers::<H>(&mut expanded_aux_data, rest);
+
+        lower_aux_data.push(Some(expanded_aux_data));
+    }
+
+    lower_aux_data.resize_with(levels.max(1), || None);
+
+    Ok(lower_aux_data)
+}
+
+/**
+ * Checks whether the aux data of a lower-level tree caches the tree `lms_tree_identifier` and
+ * was not modified.
+ */
+pub fn hss_is_lower_aux_data_valid<H: HashChain>(
+    data: &MutableExpandedAuxData,
+    lms_tree_identifier: &[u8],
+    seed: &[u8],
+) -> bool {
+    match data.lms_tree_identifier.as_deref() {
+        Some(cached_lms_tree_identifier) if cached_lms_tree_identifier == lms_tree_identifier => {
+            bool::from(compute_aux_data_hmac::<H>(data, seed).ct_eq(&data.hmac[..]))
+        }
+        _ => false,
+    }
+}
 
-        expanded_aux_data.data[index] = Some(data);
-        aux_data = data_rest;
+/**
+ * Clears the aux data of a lower-level tree, such that it caches the tree `lms_tree_identifier`
+ * once it is computed. The HMAC is updated with [`hss_finalize_aux_data`] afterwards.
+ */
+pub fn hss_reset_lower_aux_data(data: &mut MutableExpandedAuxData, lms_tree_identifier: &[u8]) {
+    hss_clear_aux_data(data);
+    if let Some(cached_lms_tree_identifier) = data.lms_tree_identifier.as_mut() {
+        cached_lms_tree_identifier.copy_from_slice(lms_tree_identifier);
     }
-    expanded_aux_data.hmac = aux_data;
+}
 
-    Some(expanded_aux_data)
+/**
+ * Clears all nodes of the aux data, which are filled in again as they are computed.
+ */
+pub fn hss_clear_aux_data(data: &mut MutableExpandedAuxData) {
+    for layer in data.data.iter_mut().flatten() {
+        layer.fill(0);
+    }
+    data.changed = true;
 }
 
 pub fn hss_get_aux_data_len<H: HashChain>(
@@ -160,31 +413,44 @@ pub fn hss_save_aux_data<H: HashChain>(
 ) {
     // We need to calculate the level of the tree and the offset from the beginning
     let level = core::mem::size_of::<usize>() * 8 - index.leading_zeros() as usize - 1;
-    if data.data[level].is_none() {
+    if data.data[level].is_none() || data.read_only {
         return;
     }
 
-    let lms_leaf_identifier: usize = index - 2u32.pow(level as u32) as usize;
-    let start_index = lms_leaf_identifier * H::OUTPUT_SIZE as usize;
+    let start_index = aux_data_offset(data, index, level) * H::OUTPUT_SIZE as usize;
     let end_index = start_index + H::OUTPUT_SIZE as usize;
 
-    let dest = data.data[level].as_mut().unwrap();
-    dest[start_index..end_index].copy_from_slice(cur_val);
+    let dest = &mut data.data[level].as_mut().unwrap()[start_index..end_index];
+    if dest != cur_val {
+        dest.copy_from_slice(cur_val);
+        data.changed = true;
+    }
 }
 
 pub fn hss_finalize_aux_data<H: HashChain>(data: &mut MutableExpandedAuxData, seed: &[u8]) {
-    let aux_seed = compute_seed_derive::<H>(seed);
+    let hmac = compute_aux_data_hmac::<H>(data, seed);
+    data.hmac.copy_from_slice(hmac.as_slice());
+}
+
+fn compute_aux_data_hmac<H: HashChain>(
+    data: &MutableExpandedAuxData,
+    seed: &[u8],
+) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+    let aux_seed = compute_seed_derive::<H>(seed, D_DAUX);
 
     let mut hasher = compute_hmac_ipad::<H>(&aux_seed).chain(data.level.to_be_bytes());
 
+    if let Some(lms_tree_identifier) = data.lms_tree_identifier.as_deref() {
+        hasher.update(lms_tree_identifier);
+    }
+
     for i in 0..MAX_TREE_HEIGHT {
-        if let Some(x) = data.data[i].as_mut() {
+        if let Some(x) = data.data[i].as_deref() {
             hasher.update(x);
         }
     }
 
-    data.hmac
-        .copy_from_slice(compute_hmac_opad::<H>(&mut hasher, &aux_seed).as_slice());
+    compute_hmac_opad::<H>(&mut hasher, &aux_seed)
 }
 
 pub fn hss_extract_aux_data<H: HashChain>(
@@ -193,15 +459,11 @@ pub fn hss_extract_aux_data<H: HashChain>(
 ) -> Option<ArrayVec<[u8; MAX_HASH_SIZE]>> {
     // We need to calculate the level of the tree and the offset from the beginning
     let level = core::mem::size_of::<usize>() * 8 - index.leading_zeros() as usize - 1;
-    let lms_leaf_identifier: u32 = index as u32 - 2u32.pow(level as u32);
-
-    aux.data[level].as_ref()?;
-
-    let src = aux.data[level].as_ref().unwrap();
+    let src = aux.data[level].as_ref()?;
 
     let hash_size = H::OUTPUT_SIZE as usize;
 
-    let start_index = lms_leaf_identifier as usize * hash_size;
+    let start_index = aux_data_offset(aux, index, level) * hash_size;
     let end_index = start_index + hash_size;
 
     if src[start_index..end_index].iter().all(|&b| b == 0u8) {
@@ -215,16 +477,53 @@ pub fn hss_extract_aux_data<H: HashChain>(
     Some(result)
 }
 
-fn compute_seed_derive<H: HashChain>(seed: &[u8]) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+fn aux_data_offset(data: &MutableExpandedAuxData, index: usize, level: usize) -> usize {
+    index - (1 << level) - (data.subtree_index << (level - data.subtree_level))
+}
+
+/**
+ * Splits the levels from `subtree_level` downwards into one [`MutableExpandedAuxData`] per subtree
+ * rooted at `subtree_level`, such that the subtrees can be computed concurrently. The levels above
+ * are left to `data`.
+ */
+#[cfg(feature = "std")]
+pub fn hss_split_aux_data<'a, H: HashChain>(
+    data: &'a mut MutableExpandedAuxData,
+    subtree_level: usize,
+) -> Vec<MutableExpandedAuxData<'a>> {
+    let mut subtrees: Vec<MutableExpandedAuxData> = (0..1usize << subtree_level)
+        .map(|subtree_index| MutableExpandedAuxData {
+            level: data.level,
+            subtree_level,
+            subtree_index,
+            ..Default::default()
+        })
+        .collect();
+
+    for (level, level_data) in data.data.iter_mut().enumerate().skip(subtree_level) {
+        if let Some(level_data) = level_data.as_deref_mut() {
+            let subtree_size = (H::OUTPUT_SIZE as usize) << (level - subtree_level);
+            for (subtree, subtree_data) in
+                subtrees.iter_mut().zip(level_data.chunks_mut(subtree_size))
+            {
+                subtree.data[level] = Some(subtree_data);
+            }
+        }
+    }
+
+    subtrees
+}
+
+pub fn compute_seed_derive<H: HashChain>(seed: &[u8], d: u16) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
     let mut prefix = [0u8; DAUX_PREFIX_LEN];
 
-    prefix[DAUX_D] = (D_DAUX >> 8) as u8;
-    prefix[DAUX_D + 1] = (D_DAUX & 0xff) as u8;
+    prefix[DAUX_D] = (d >> 8) as u8;
+    prefix[DAUX_D + 1] = (d & 0xff) as u8;
 
     H::default().chain(&prefix[..]).chain(seed).finalize()
 }
 
-fn compute_hmac_ipad<H: HashChain>(key: &[u8]) -> H {
+pub fn compute_hmac_ipad<H: HashChain>(key: &[u8]) -> H {
     const IPAD_ARRAY: [u8; MAX_HASH_BLOCK_SIZE] = [IPAD; MAX_HASH_BLOCK_SIZE];
 
     let key = key
@@ -237,7 +536,10 @@ fn compute_hmac_ipad<H: HashChain>(key: &[u8]) -> H {
         .chain(&IPAD_ARRAY[H::OUTPUT_SIZE.into()..H::BLOCK_SIZE.into()])
 }
 
-fn compute_hmac_opad<H: HashChain>(hasher: &mut H, key: &[u8]) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+pub fn compute_hmac_opad<H: HashChain>(
+    hasher: &mut H,
+    key: &[u8],
+) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
     const OPAD_ARRAY: [u8; MAX_HASH_BLOCK_SIZE] = [OPAD; MAX_HASH_BLOCK_SIZE];
 
     let buffer = hasher.finalize_reset();
@@ -254,7 +556,7 @@ fn compute_hmac_opad<H: HashChain>(hasher: &mut H, key: &[u8]) -> ArrayVec<[u8;
         .finalize_reset()
 }
 
-fn compute_hmac<H: HashChain>(key: &[u8], data: &[u8]) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+pub fn compute_hmac<H: HashChain>(key: &[u8], data: &[u8]) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
     let mut hasher = compute_hmac_ipad::<H>(key).chain(data);
     compute_hmac_opad::<H>(&mut hasher, key)
 }
@@ -265,12 +567,14 @@ mod tests {
     use crate::util::helper::test_helper::gen_random_seed;
     use crate::{
         constants::MAX_HASH_SIZE,
-        hss::{aux::hss_expand_aux_data, hss_keygen},
-        HssParameter, LmotsAlgorithm, LmsAlgorithm,
+        hss::{
+            aux::{hss_check_aux_data, hss_expand_aux_data, hss_expand_lower_aux_data},
+            hss_keygen, hss_sign, hss_verify,
+        },
+        Error, HssParameter, LmotsAlgorithm, LmsAlgorithm,
     };
 
     #[test]
-    #[should_panic(expected = "expand_aux_data should return None!")]
     fn expand_aux_data_with_forged_aux_data() {
         type H = Sha256_256;
         let seed = gen_random_seed::<H>();
@@ -287,7 +591,105 @@ mod tests {
 
         aux_slice[2 * MAX_HASH_SIZE - 1] ^= 1;
 
-        hss_expand_aux_data::<H>(Some(aux_slice), Some(seed.as_slice()))
-            .expect("expand_aux_data should return None!");
+        assert!(
+            hss_expand_aux_data::<H>(Some(aux_slice), Some(seed.as_slice()))
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            hss_check_aux_data::<H>(aux_slice, seed.as_slice(), parameters.len()),
+            Err(Error::InvalidAuxData)
+        );
+    }
+
+    #[test]
+    fn expand_truncated_aux_data() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let mut aux_data = [0u8; 1_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+
+        let _ =
+            hss_keygen::<H>(&parameters, &seed, Some(aux_slice)).expect("Should generate HSS keys");
+
+        // The aux data of the lower-level tree comes last
+        let aux_len = aux_slice.len();
+        assert_eq!(
+            hss_check_aux_data::<H>(&aux_slice[..aux_len - 1], seed.as_slice(), parameters.len()),
+            Err(Error::InvalidAuxData)
+        );
+        let mut expanded_aux_data =
+            hss_expand_aux_data::<H>(Some(&mut aux_slice[..aux_len - 1]), Some(seed.as_slice()))
+                .unwrap();
+        assert!(expanded_aux_data.is_some());
+        let lower_aux_data =
+            hss_expand_lower_aux_data::<H>(&mut expanded_aux_data, parameters.len()).unwrap();
+        assert!(lower_aux_data.iter().all(Option::is_none));
+
+        assert_eq!(
+            hss_check_aux_data::<H>(&aux_slice[..2], seed.as_slice(), parameters.len()),
+            Err(Error::InvalidAuxData)
+        );
+        assert!(
+            hss_expand_aux_data::<H>(Some(&mut aux_slice[..2]), Some(seed.as_slice()))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn sign_with_lower_aux_data() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let parameters = [
+            HssParameter::new(lmots, LmsAlgorithm::LmsH5),
+            HssParameter::new(lmots, LmsAlgorithm::LmsH5),
+            HssParameter::new(lmots, LmsAlgorithm::LmsH2),
+        ];
+
+        let mut aux_data = [0u8; 5_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+
+        let (mut signing_key, verifying_key) =
+            hss_keygen::<H>(&parameters, &seed, Some(aux_slice)).expect("Should generate HSS keys");
+        let mut reference_signing_key = signing_key.clone();
+
+        for i in 0..6 {
+            // Modified aux data of a lower-level tree is not used, but computed again
+            if i == 3 {
+                let aux_len = aux_slice.len();
+                aux_slice[aux_len - 1] ^= 1;
+            }
+
+            let signature = hss_sign::<H>(b"message", &mut signing_key, Some(aux_slice)).unwrap();
+            let reference_signature =
+                hss_sign::<H>(b"message", &mut reference_signing_key, None).unwrap();
+
+            assert_eq!(signature.as_ref(), reference_signature.as_ref());
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+        }
+
+        // Both lower levels cache their current tree
+        let mut expanded_aux_data =
+            hss_expand_aux_data::<H>(Some(aux_slice), Some(seed.as_slice())).unwrap();
+        let lower_aux_data =
+            hss_expand_lower_aux_data::<H>(&mut expanded_aux_data, parameters.len()).unwrap();
+        assert!(lower_aux_data[1..].iter().all(|level_aux_data| {
+            level_aux_data
+                .as_ref()
+                .and_then(|level_aux_data| level_aux_data.lms_tree_identifier.as_deref())
+                .is_some_and(|lms_tree_identifier| {
+                    lms_tree_identifier.iter().any(|&byte| byte != 0)
+                })
+        }));
     }
 }
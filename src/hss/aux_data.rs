@@ -0,0 +1,362 @@
+use core::{convert::TryInto, marker::PhantomData};
+
+use crate::{
+    hss::{
+        aux::{
+            hss_check_aux_data, hss_get_aux_data_len, hss_get_lower_aux_data_len,
+            hss_optimal_aux_level, hss_store_aux_marker,
+        },
+        definitions::HssPublicKey,
+        reference_impl_private_key::ReferenceImplPrivateKey,
+        SigningKey,
+    },
+    Error, HashChain, HssParameter,
+};
+
+const AUX_DATA_VERSION: u32 = 1;
+const AUX_DATA_VERSION_LEN: usize = 4;
+
+/**
+ * State of [`AuxData`] with respect to a [`SigningKey`].
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuxDataStatus {
+    /// There is no aux data, signing computes the trees without it.
+    Missing,
+    /// The aux data was modified, truncated or belongs to another key. Signing does not use it
+    /// and computes the trees instead, until it is regenerated.
+    Invalid,
+    /// The aux data is valid and used for signing.
+    Used,
+}
+
+/**
+ * Auxiliary data of a [`SigningKey`] in a buffer provided by the caller.
+ *
+ * The aux data caches internal nodes of the top-level tree and, with enough space, of the current
+ * lower-level trees. It is filled by the key generation and used by every signature. The buffer
+ * is shrunk to the length that is actually used, which is the length to persist.
+ *
+ * # Example
+ * ```
+ * use hbs_lms::{keygen, AuxData, AuxDataStatus, HssParameter, LmotsAlgorithm, LmsAlgorithm,
+ *     Sha256_256, Seed,
+ * };
+ *
+ * let parameters = [HssParameter::new(LmotsAlgorithm::LmotsW2, LmsAlgorithm::LmsH5)];
+ * let mut buffer = vec![0u8; AuxData::<Sha256_256>::len_for(&parameters, 10_000)];
+ * let mut aux_data = AuxData::<Sha256_256>::new(&mut buffer);
+ *
+ * let (mut signing_key, _) =
+ *     keygen::<Sha256_256>(&parameters, &Seed::default(), Some(aux_data.as_mut_slice())).unwrap();
+ * assert_eq!(aux_data.status(&signing_key), AuxDataStatus::Used);
+ *
+ * let signature = signing_key.try_sign_with_aux(b"message", Some(aux_data.as_mut_slice()));
+ * ```
+ */
+pub struct AuxData<'a, H: HashChain> {
+    data: &'a mut [u8],
+    pub(crate) populate: bool,
+    pub(crate) changed: bool,
+    phantom_data: PhantomData<H>,
+}
+
+impl<'a, H: HashChain> AuxData<'a, H> {
+    /**
+     * Returns the length of the aux data of a key with `parameters` within a budget of
+     * `max_length` bytes.
+     */
+    pub fn len_for(parameters: &[HssParameter<H>], max_length: usize) -> usize {
+        let top_lms_parameter = match parameters.first() {
+            Some(parameter) if max_length > 0 => *parameter.get_lms_parameter(),
+            _ => return 0,
+        };
+
+        let len = hss_get_aux_data_len(max_length, top_lms_parameter);
+        if hss_optimal_aux_level(len, top_lms_parameter, None) == 0 {
+            return len;
+        }
+
+        len + hss_get_lower_aux_data_len(max_length - len, &parameters[1..])
+    }
+
+    /**
+     * Wraps `buffer`, which either holds aux data or is filled by the key generation or
+     * [`AuxData::regenerate`]. An empty or zeroed buffer holds no aux data.
+     */
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            data: buffer,
+            populate: false,
+            changed: false,
+            phantom_data: PhantomData,
+        }
+    }
+
+    /**
+     * Lets the signatures save every node they compute into the empty slots of the aux data and
+     * update its HMAC. Missing aux data is laid out in the buffer first. Thus, small or
+     * incomplete aux data fills itself in over time, see [`AuxData::is_changed`].
+     */
+    pub fn with_lazy_population(mut self) -> Self {
+        self.populate = true;
+        self
+    }
+
+    /**
+     * Returns whether a signature changed the aux data since the last
+     * [`AuxData::clear_changed`], i.e. whether it needs to be persisted. Besides the lazy
+     * population, this happens whenever the aux data of a lower-level tree is refilled for the
+     * next tree.
+     */
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    pub fn clear_changed(&mut self) {
+        self.changed = false;
+    }
+
+    /**
+     * Returns the aux data in the form expected by the key generation and signing functions.
+     */
+    pub fn as_mut_slice(&mut self) -> &mut &'a mut [u8] {
+        &mut self.data
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /**
+     * Checks the aux data against `signing_key`. Fails with [`Error::MissingAuxData`] if there
+     * is no aux data and with [`Error::InvalidAuxData`] if its HMAC does not match.
+     */
+    pub fn validate(&self, signing_key: &SigningKey<H>) -> Result<(), Error> {
+        let private_key =
+            ReferenceImplPrivateKey::<H>::from_binary_representation(signing_key.as_slice())?;
+        let levels = private_key.compressed_parameter.to::<H>()?.len();
+
+        if hss_check_aux_data::<H>(self.data, private_key.seed.as_slice(), levels)? {
+            Ok(())
+        } else {
+            Err(Error::MissingAuxData)
+        }
+    }
+
+    /**
+     * Returns whether the aux data is missing, invalid or used for `signing_key`.
+     */
+    pub fn status(&self, signing_key: &SigningKey<H>) -> AuxDataStatus {
+        match self.validate(signing_key) {
+            Ok(()) => AuxDataStatus::Used,
+            Err(Error::MissingAuxData) => AuxDataStatus::Missing,
+            Err(_) => AuxDataStatus::Invalid,
+        }
+    }
+
+    /**
+     * Computes the aux data of `signing_key` again, e.g. after it was lost or found to be
+     * invalid. This computes the whole top-level tree. The layout is derived from the length of
+     * the buffer, so the aux data of a key generation is regenerated with the same layout.
+     */
+    pub fn regenerate(&mut self, signing_key: &SigningKey<H>) -> Result<(), Error> {
+        let private_key =
+            ReferenceImplPrivateKey::<H>::from_binary_representation(signing_key.as_slice())?;
+
+        if self.data.is_empty() {
+            return Err(Error::MissingAuxData);
+        }
+        hss_store_aux_marker(self.data, 0);
+
+        HssPublicKey::from(&private_key, Some(&mut self.data))?;
+
+        Ok(())
+    }
+
+    /**
+     * Returns the length of the aux data serialized by [`AuxData::serialize`].
+     */
+    pub fn serialized_len(&self) -> usize {
+        AUX_DATA_VERSION_LEN + self.data.len()
+    }
+
+    /**
+     * Writes the aux data with a version header into `data` and returns its length. Fails with
+     * [`Error::InvalidParameters`] if `data` is too small.
+     */
+    pub fn serialize(&self, data: &mut [u8]) -> Result<usize, Error> {
+        let len = self.serialized_len();
+        if data.len() < len {
+            return Err(Error::InvalidParameters);
+        }
+
+        data[..AUX_DATA_VERSION_LEN].copy_from_slice(&AUX_DATA_VERSION.to_be_bytes());
+        data[AUX_DATA_VERSION_LEN..len].copy_from_slice(self.data);
+
+        Ok(len)
+    }
+
+    /**
+     * Reads aux data written by [`AuxData::serialize`] into `buffer`. Fails with
+     * [`Error::InvalidAuxData`] if the version is unknown and with [`Error::InvalidParameters`]
+     * if `buffer` is too small. The aux data itself is checked by [`AuxData::validate`].
+     */
+    pub fn deserialize(data: &[u8], buffer: &'a mut [u8]) -> Result<Self, Error> {
+        if data.len() < AUX_DATA_VERSION_LEN {
+            return Err(Error::InvalidAuxData);
+        }
+        let (version, data) = data.split_at(AUX_DATA_VERSION_LEN);
+        if u32::from_be_bytes(version.try_into().unwrap()) != AUX_DATA_VERSION {
+            return Err(Error::InvalidAuxData);
+        }
+
+        if buffer.len() < data.len() {
+            return Err(Error::InvalidParameters);
+        }
+        let buffer = &mut buffer[..data.len()];
+        buffer.copy_from_slice(data);
+
+        Ok(Self::new(buffer))
+    }
+}
+
+/**
+ * Passes the raw aux data of the signing functions as [`AuxData`] to `f`.
+ */
+pub(crate) fn with_aux_slice<H: HashChain, T>(
+    aux_data: Option<&mut &mut [u8]>,
+    f: impl FnOnce(Option<&mut AuxData<H>>) -> T,
+) -> T {
+    match aux_data {
+        Some(aux_slice) => {
+            let mut aux_data = AuxData::new(core::mem::take(aux_slice));
+            let result = f(Some(&mut aux_data));
+            *aux_slice = aux_data.data;
+            result
+        }
+        None => f(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AuxData, AuxDataStatus};
+    use crate::{
+        hss::{hss_keygen, hss_sign, hss_verify},
+        util::helper::test_helper::gen_random_seed,
+        Error, HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+    };
+
+    type H = Sha256_256;
+
+    #[test]
+    fn validate_regenerate_and_serialize() {
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let parameters = [
+            HssParameter::new(lmots, LmsAlgorithm::LmsH5),
+            HssParameter::new(lmots, LmsAlgorithm::LmsH2),
+        ];
+
+        let len = AuxData::<H>::len_for(&parameters, 2_000);
+        let mut buffer = [0u8; 2_000];
+        let mut aux_data = AuxData::<H>::new(&mut buffer[..len]);
+
+        let (mut signing_key, verifying_key) =
+            hss_keygen::<H>(&parameters, &seed, Some(aux_data.as_mut_slice())).unwrap();
+        assert_eq!(aux_data.len(), len);
+        assert_eq!(aux_data.status(&signing_key), AuxDataStatus::Used);
+        let mut generated = [0u8; 2_000];
+        generated[..len].copy_from_slice(aux_data.as_slice());
+
+        let mut serialized = [0u8; 2_004];
+        let serialized_len = aux_data.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized_len, aux_data.serialized_len());
+
+        // Lost aux data is missing, modified aux data is invalid
+        aux_data.as_mut_slice().fill(0);
+        assert_eq!(aux_data.status(&signing_key), AuxDataStatus::Missing);
+        assert_eq!(aux_data.validate(&signing_key), Err(Error::MissingAuxData));
+
+        aux_data.regenerate(&signing_key).unwrap();
+        assert_eq!(aux_data.as_slice(), &generated[..len]);
+
+        aux_data.as_mut_slice()[8] ^= 1;
+        assert_eq!(aux_data.status(&signing_key), AuxDataStatus::Invalid);
+        assert_eq!(aux_data.validate(&signing_key), Err(Error::InvalidAuxData));
+
+        let mut deserialize_buffer = [0u8; 2_000];
+        let mut aux_data =
+            AuxData::<H>::deserialize(&serialized[..serialized_len], &mut deserialize_buffer)
+                .unwrap();
+        assert_eq!(aux_data.status(&signing_key), AuxDataStatus::Used);
+
+        let signature =
+            hss_sign::<H>(b"message", &mut signing_key, Some(aux_data.as_mut_slice())).unwrap();
+        assert!(hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok());
+
+        serialized[3] ^= 1;
+        assert!(matches!(
+            AuxData::<H>::deserialize(&serialized[..serialized_len], &mut deserialize_buffer),
+            Err(Error::InvalidAuxData)
+        ));
+    }
+
+    #[test]
+    fn populate_lazily() {
+        let seed = gen_random_seed::<H>();
+        let parameters = [HssParameter::new(
+            LmotsAlgorithm::LmotsW2,
+            LmsAlgorithm::LmsH5,
+        )];
+
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let mut reference_signing_key = signing_key.clone();
+
+        let len = AuxData::<H>::len_for(&parameters, 2_000);
+        let mut buffer = [0u8; 2_000];
+
+        // Missing aux data is left alone without lazy population
+        let mut aux_data = AuxData::<H>::new(&mut buffer[..len]);
+        let signature = signing_key
+            .try_sign_with_aux_data(b"message", &mut aux_data)
+            .unwrap();
+        let reference_signature =
+            hss_sign::<H>(b"message", &mut reference_signing_key, None).unwrap();
+        assert_eq!(signature.as_ref(), reference_signature.as_ref());
+        assert_eq!(aux_data.status(&signing_key), AuxDataStatus::Missing);
+        assert!(!aux_data.is_changed());
+
+        // The signatures fill in the nodes they compute, until the aux data is complete
+        let mut aux_data = aux_data.with_lazy_population();
+        for leaf in 1..17 {
+            let signature = signing_key
+                .try_sign_with_aux_data(b"message", &mut aux_data)
+                .unwrap();
+            let reference_signature =
+                hss_sign::<H>(b"message", &mut reference_signing_key, None).unwrap();
+
+            assert_eq!(signature.as_ref(), reference_signature.as_ref());
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+            assert_eq!(aux_data.status(&signing_key), AuxDataStatus::Used);
+            assert_eq!(aux_data.is_changed(), [1, 2, 4, 16].contains(&leaf));
+            aux_data.clear_changed();
+        }
+
+        let mut regenerated_buffer = [0u8; 2_000];
+        let mut regenerated_aux_data = AuxData::<H>::new(&mut regenerated_buffer[..len]);
+        regenerated_aux_data.regenerate(&signing_key).unwrap();
+        assert_eq!(aux_data.as_slice(), regenerated_aux_data.as_slice());
+    }
+}
@@ -0,0 +1,335 @@
+use core::convert::TryInto;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    constants::{Node, D_CHECKPOINT},
+    hasher::HashChain,
+    hss::{
+        aux::{compute_hmac_ipad, compute_hmac_opad, compute_seed_derive},
+        progress::NodeCache,
+    },
+    lms::definitions::LmsPrivateKey,
+    Error, Seed,
+};
+
+const CHECKPOINT_LEVEL: usize = 0;
+const CHECKPOINT_COMPLETED: usize = 4;
+const CHECKPOINT_NODES: usize = 8;
+
+/**
+ * Persists the checkpoints written during key generation, see
+ * [`crate::keygen_with_checkpoint`].
+ */
+pub trait CheckpointStore {
+    /**
+     * Stores `checkpoint`, which replaces the previously stored checkpoint.
+     *
+     * Returning an error aborts the key generation with that error.
+     */
+    fn store(&mut self, checkpoint: &[u8]) -> Result<(), Error>;
+}
+
+impl<F: FnMut(&[u8]) -> Result<(), Error>> CheckpointStore for F {
+    fn store(&mut self, checkpoint: &[u8]) -> Result<(), Error> {
+        self(checkpoint)
+    }
+}
+
+/**
+ * Returns the length of a checkpoint storing the roots of all subtrees at `checkpoint_level`
+ * of the top-level tree.
+ *
+ * A checkpoint is written whenever one of the `2^checkpoint_level` subtrees is completed.
+ */
+pub fn hss_checkpoint_len<H: HashChain>(checkpoint_level: u8) -> usize {
+    CHECKPOINT_NODES + ((1usize << checkpoint_level) + 1) * H::OUTPUT_SIZE as usize
+}
+
+/**
+ * Checkpoint of the key generation, storing the roots of the subtrees at `level` that have been
+ * completed so far.
+ *
+ * The subtrees are completed from left to right, thus only their number needs to be stored. The
+ * data is authenticated with an HMAC keyed from the seed, like the aux data.
+ *
+ * Layout: `u32str(level) || u32str(completed) || T[2^level] || .. || T[2^(level+1) - 1] || HMAC`
+ */
+pub(crate) struct Checkpoint<'a, H: HashChain> {
+    data: &'a mut [u8],
+    level: usize,
+    completed: usize,
+    key: Node,
+    hasher: H,
+    checkpoint_store: &'a mut dyn CheckpointStore,
+}
+
+impl<'a, H: HashChain> Checkpoint<'a, H> {
+    /**
+     * Parses the checkpoint in `data`, or starts a new one if `data` is zeroed.
+     *
+     * The deepest level fitting into `data` is used, which must not be above `min_level`.
+     */
+    pub fn new(
+        data: &'a mut [u8],
+        seed: &Seed<H>,
+        private_key: &LmsPrivateKey<H>,
+        min_level: usize,
+        checkpoint_store: &'a mut dyn CheckpointStore,
+    ) -> Result<Self, Error> {
+        let tree_height = private_key.lms_parameter.get_tree_height();
+        let level = (1..=tree_height)
+            .rev()
+            .find(|&level| hss_checkpoint_len::<H>(level) <= data.len())
+            .ok_or(Error::InvalidParameters)? as usize;
+        if level < min_level {
+            return Err(Error::InvalidParameters);
+        }
+
+        let data = &mut data[..hss_checkpoint_len::<H>(level as u8)];
+        let key = compute_seed_derive::<H>(seed.as_slice(), D_CHECKPOINT);
+        let hasher = compute_hmac_ipad::<H>(&key)
+            .chain(private_key.lms_parameter.get_type_id().to_be_bytes())
+            .chain(private_key.lmots_parameter.get_type_id().to_be_bytes())
+            .chain(private_key.lms_tree_identifier)
+            .chain((level as u32).to_be_bytes());
+
+        let mut checkpoint = Self {
+            data,
+            level,
+            completed: 0,
+            key,
+            hasher,
+            checkpoint_store,
+        };
+
+        if checkpoint.read_u32(CHECKPOINT_LEVEL) == 0 {
+            checkpoint.data.fill(0);
+            checkpoint.data[CHECKPOINT_LEVEL..CHECKPOINT_COMPLETED]
+                .copy_from_slice(&(level as u32).to_be_bytes());
+            return Ok(checkpoint);
+        }
+
+        let completed = checkpoint.read_u32(CHECKPOINT_COMPLETED) as usize;
+        if checkpoint.read_u32(CHECKPOINT_LEVEL) as usize != level || completed > 1 << level {
+            return Err(Error::InvalidCheckpoint);
+        }
+
+        let size_hash = H::OUTPUT_SIZE as usize;
+        checkpoint.completed = completed;
+        checkpoint
+            .hasher
+            .update(&checkpoint.data[CHECKPOINT_NODES..CHECKPOINT_NODES + completed * size_hash]);
+
+        let hmac = &checkpoint.data[checkpoint.data.len() - size_hash..];
+        if !bool::from(checkpoint.compute_hmac().ct_eq(hmac)) {
+            return Err(Error::InvalidCheckpoint);
+        }
+
+        Ok(checkpoint)
+    }
+
+    fn get_offset(&self, index: usize) -> Option<usize> {
+        let level = core::mem::size_of::<usize>() * 8 - index.leading_zeros() as usize - 1;
+        if level == self.level {
+            Some(index - (1 << level))
+        } else {
+            None
+        }
+    }
+
+    fn read_u32(&self, start: usize) -> u32 {
+        u32::from_be_bytes(self.data[start..start + 4].try_into().unwrap())
+    }
+
+    fn compute_hmac(&self) -> Node {
+        let mut hasher = self
+            .hasher
+            .clone()
+            .chain((self.completed as u32).to_be_bytes());
+        compute_hmac_opad::<H>(&mut hasher, &self.key)
+    }
+}
+
+impl<'a, H: HashChain> NodeCache for Checkpoint<'a, H> {
+    /**
+     * Returns the root of the subtree at `index`, if it has already been completed.
+     */
+    fn get_node(&self, index: usize) -> Option<Node> {
+        let offset = self.get_offset(index)?;
+        if offset >= self.completed {
+            return None;
+        }
+
+        let size_hash = H::OUTPUT_SIZE as usize;
+        let start = CHECKPOINT_NODES + offset * size_hash;
+        Some(
+            self.data[start..start + size_hash]
+                .iter()
+                .copied()
+                .collect(),
+        )
+    }
+
+    /**
+     * Records the root of the subtree at `index` and stores the updated checkpoint.
+     */
+    fn complete_node(&mut self, index: usize, node: &[u8]) -> Result<(), Error> {
+        if self.get_offset(index) != Some(self.completed) {
+            return Ok(());
+        }
+
+        let size_hash = H::OUTPUT_SIZE as usize;
+        let start = CHECKPOINT_NODES + self.completed * size_hash;
+        self.data[start..start + size_hash].copy_from_slice(node);
+        self.hasher.update(node);
+
+        self.completed += 1;
+        self.data[CHECKPOINT_COMPLETED..CHECKPOINT_NODES]
+            .copy_from_slice(&(self.completed as u32).to_be_bytes());
+
+        let hmac = self.compute_hmac();
+        let hmac_start = self.data.len() - size_hash;
+        self.data[hmac_start..].copy_from_slice(hmac.as_slice());
+
+        self.checkpoint_store.store(self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hss::{hss_keygen, hss_keygen_with_checkpoint},
+        util::helper::test_helper::gen_random_seed,
+        HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+    };
+
+    type H = Sha256_256;
+
+    const CHECKPOINT_LEN: usize = 8 + (8 + 1) * 32;
+
+    #[test]
+    fn resume_interrupted_keygen() {
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+        assert_eq!(hss_checkpoint_len::<H>(3), CHECKPOINT_LEN);
+
+        let mut aux_data = [0u8; 1_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+        let keys = hss_keygen::<H>(&parameters, &seed, Some(aux_slice)).unwrap();
+
+        // Interrupt the key generation after three of eight subtrees
+        let mut stored_checkpoint = [0u8; CHECKPOINT_LEN];
+        let mut stores = 0;
+        let mut checkpoint_store = |checkpoint: &[u8]| {
+            if stores == 3 {
+                return Err(Error::StateStore);
+            }
+            stores += 1;
+            stored_checkpoint.copy_from_slice(checkpoint);
+            Ok(())
+        };
+        let mut checkpoint = [0u8; CHECKPOINT_LEN];
+        let mut interrupted_aux_data = [0u8; 1_000];
+        let interrupted_aux_slice: &mut &mut [u8] = &mut &mut interrupted_aux_data[..];
+        assert_eq!(
+            hss_keygen_with_checkpoint::<H>(
+                &parameters,
+                &seed,
+                Some(interrupted_aux_slice),
+                &mut checkpoint,
+                &mut checkpoint_store,
+            ),
+            Err(Error::StateStore)
+        );
+        assert_eq!(&stored_checkpoint[4..8], &3u32.to_be_bytes());
+
+        let mut stores = 0;
+        let mut checkpoint_store = |_: &[u8]| {
+            stores += 1;
+            Ok(())
+        };
+        let mut checkpoint = stored_checkpoint;
+        let mut resumed_aux_data = [0u8; 1_000];
+        let resumed_aux_slice: &mut &mut [u8] = &mut &mut resumed_aux_data[..];
+        assert_eq!(
+            hss_keygen_with_checkpoint::<H>(
+                &parameters,
+                &seed,
+                Some(resumed_aux_slice),
+                &mut checkpoint,
+                &mut checkpoint_store,
+            ),
+            Ok(keys)
+        );
+        assert_eq!(stores, 5);
+        assert_eq!(resumed_aux_slice, aux_slice);
+    }
+
+    #[test]
+    fn reject_invalid_checkpoint() {
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+        let parameters = [HssParameter::new(lmots, lms)];
+
+        let mut stored_checkpoint = [0u8; CHECKPOINT_LEN];
+        let mut checkpoint_store = |checkpoint: &[u8]| {
+            stored_checkpoint.copy_from_slice(checkpoint);
+            Ok(())
+        };
+        let mut checkpoint = [0u8; CHECKPOINT_LEN];
+        hss_keygen_with_checkpoint::<H>(
+            &parameters,
+            &seed,
+            None,
+            &mut checkpoint,
+            &mut checkpoint_store,
+        )
+        .unwrap();
+
+        let mut checkpoint_store = |_: &[u8]| Ok(());
+
+        let mut checkpoint = stored_checkpoint;
+        checkpoint[CHECKPOINT_NODES] ^= 1;
+        assert_eq!(
+            hss_keygen_with_checkpoint::<H>(
+                &parameters,
+                &seed,
+                None,
+                &mut checkpoint,
+                &mut checkpoint_store,
+            ),
+            Err(Error::InvalidCheckpoint)
+        );
+
+        let mut checkpoint = stored_checkpoint;
+        assert_eq!(
+            hss_keygen_with_checkpoint::<H>(
+                &parameters,
+                &gen_random_seed::<H>(),
+                None,
+                &mut checkpoint,
+                &mut checkpoint_store,
+            ),
+            Err(Error::InvalidCheckpoint)
+        );
+
+        // The checkpoint does not cover the deepest aux data level
+        let mut aux_data = [0u8; 1_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+        let mut checkpoint = [0u8; CHECKPOINT_LEN - 1];
+        assert_eq!(
+            hss_keygen_with_checkpoint::<H>(
+                &parameters,
+                &seed,
+                Some(aux_slice),
+                &mut checkpoint,
+                &mut checkpoint_store,
+            ),
+            Err(Error::InvalidParameters)
+        );
+    }
+}
@@ -6,7 +6,9 @@ use crate::{
     constants::{MAX_ALLOWED_HSS_LEVELS, MAX_HSS_PUBLIC_KEY_LENGTH},
     hasher::HashChain,
     hss::aux::{
-        hss_expand_aux_data, hss_finalize_aux_data, hss_optimal_aux_level, hss_store_aux_marker,
+        hss_expand_aux_data, hss_finalize_aux_data, hss_is_lower_aux_data_valid,
+        hss_optimal_aux_level, hss_reset_lower_aux_data, hss_store_aux_marker,
+        hss_store_lower_aux_markers, LowerAuxData,
     },
     lms::{
         self,
@@ -14,16 +16,21 @@ use crate::{
         generate_key_pair,
         parameters::LmsParameter,
     },
-    util::helper::read_and_advance,
+    util::helper::try_read_and_advance,
+    Error,
 };
 use crate::{hss::aux::hss_get_aux_data_len, lms::signing::LmsSignature};
 
 use super::{
     aux::{hss_is_aux_data_used, MutableExpandedAuxData},
+    checkpoint::{Checkpoint, CheckpointStore},
+    pregeneration::NextBottomTree,
+    progress::{KeygenObserver, KeygenProgressTracker},
     reference_impl_private_key::{
-        generate_child_seed_and_lms_tree_identifier, generate_signature_randomizer,
-        ReferenceImplPrivateKey,
+        generate_child_seed_and_lms_tree_identifier, generate_child_signature_randomizer,
+        ReferenceImplPrivateKey, SeedAndLmsTreeIdentifier,
     },
+    work_unit::WorkUnitNodes,
 };
 
 #[derive(Debug, Default, PartialEq)]
@@ -31,6 +38,7 @@ pub struct HssPrivateKey<H: HashChain> {
     pub private_key: ArrayVec<[LmsPrivateKey<H>; MAX_ALLOWED_HSS_LEVELS]>,
     pub public_key: ArrayVec<[LmsPublicKey<H>; MAX_ALLOWED_HSS_LEVELS - 1]>,
     pub signatures: ArrayVec<[LmsSignature<H>; MAX_ALLOWED_HSS_LEVELS - 1]>, // Only L - 1 signatures needed
+    pub rfc_derivation: bool,
 }
 
 impl<H: HashChain> HssPrivateKey<H> {
@@ -38,52 +46,235 @@ impl<H: HashChain> HssPrivateKey<H> {
         self.private_key.len()
     }
 
+    #[cfg(test)]
     pub fn from(
         private_key: &ReferenceImplPrivateKey<H>,
         aux_data: &mut Option<MutableExpandedAuxData>,
-    ) -> Result<Self, ()> {
+    ) -> Result<Self, Error> {
         let mut hss_private_key: HssPrivateKey<H> = Default::default();
+        hss_private_key.expand(private_key, 0, aux_data, &mut LowerAuxData::new(), None)?;
+        Ok(hss_private_key)
+    }
 
-        let mut current_seed = private_key.generate_root_seed_and_lms_tree_identifier();
+    /**
+     * Updates the expanded key to the state of `private_key`. Only the levels below a tree that
+     * moved on to another leaf are regenerated. `aux_data` is only used, if the whole key needs to
+     * be regenerated. The bottom tree is taken from `next_bottom_tree`, if it was pregenerated.
+     * Returns the first regenerated level.
+     *
+     * The aux data in `lower_aux_data` is refilled for every regenerated level. Afterwards, it
+     * only keeps the valid aux data of the bottom tree and its parent, which is used for the next
+     * signatures. Updating the HMAC of changed aux data is left to the caller.
+     */
+    pub fn refresh<'a>(
+        &mut self,
+        private_key: &ReferenceImplPrivateKey<H>,
+        aux_data: &mut Option<MutableExpandedAuxData<'a>>,
+        lower_aux_data: &mut LowerAuxData<'a>,
+        next_bottom_tree: Option<&mut NextBottomTree<H>>,
+    ) -> Result<usize, Error> {
         let parameters = private_key.compressed_parameter.to::<H>()?;
         let used_leafs_indexes = private_key.compressed_used_leafs_indexes.to(&parameters);
+        let levels = parameters.len();
 
-        let lms_private_key = LmsPrivateKey {
-            seed: current_seed.seed.clone(),
-            lms_tree_identifier: current_seed.lms_tree_identifier,
-            lmots_parameter: *parameters[0].get_lmots_parameter(),
-            lms_parameter: *parameters[0].get_lms_parameter(),
-            used_leafs_index: used_leafs_indexes[0],
+        let is_same_key = self.private_key.len() == levels
+            && self.rfc_derivation == private_key.rfc_derivation
+            && self.signatures.len() >= levels - 1
+            && self.private_key.iter().zip(parameters.iter()).all(
+                |(lms_private_key, parameter)| {
+                    lms_private_key.lmots_parameter == *parameter.get_lmots_parameter()
+                        && lms_private_key.lms_parameter == *parameter.get_lms_parameter()
+                },
+            )
+            && {
+                let root_seed = private_key.generate_root_seed_and_lms_tree_identifier();
+                self.private_key[0].seed == root_seed.seed
+                    && self.private_key[0].lms_tree_identifier == root_seed.lms_tree_identifier
+            };
+
+        let first_level = if is_same_key {
+            (1..levels)
+                .find(|&level| {
+                    self.signatures[level - 1].lms_leaf_identifier
+                        != used_leafs_indexes[level - 1].to_be_bytes()
+                })
+                .unwrap_or(levels)
+        } else {
+            0
         };
-        hss_private_key.private_key.push(lms_private_key);
 
-        for (i, parameter) in parameters.iter().enumerate().skip(1) {
-            let parent_used_leafs_index: u32 = hss_private_key.private_key[i - 1].used_leafs_index;
+        for level in 1..first_level.min(levels) {
+            if level + 2 < levels {
+                if let Some(level_aux_data) = lower_aux_data.get_mut(level) {
+                    *level_aux_data = None;
+                }
+            } else {
+                let lms_tree_identifier = self.private_key[level].lms_tree_identifier;
+                Self::select_lower_aux_data(
+                    lower_aux_data,
+                    level,
+                    &lms_tree_identifier,
+                    private_key.seed.as_slice(),
+                    false,
+                );
+            }
+        }
+
+        if first_level < levels {
+            self.expand(
+                private_key,
+                first_level,
+                aux_data,
+                lower_aux_data,
+                next_bottom_tree,
+            )?;
+        } else {
+            self.signatures.truncate(levels - 1);
+            self.private_key[levels - 1].used_leafs_index = used_leafs_indexes[levels - 1];
+        }
+
+        Ok(first_level)
+    }
 
-            current_seed = generate_child_seed_and_lms_tree_identifier::<H>(
+    fn expand<'a>(
+        &mut self,
+        private_key: &ReferenceImplPrivateKey<H>,
+        first_level: usize,
+        aux_data: &mut Option<MutableExpandedAuxData<'a>>,
+        lower_aux_data: &mut LowerAuxData<'a>,
+        mut next_bottom_tree: Option<&mut NextBottomTree<H>>,
+    ) -> Result<(), Error> {
+        let parameters = private_key.compressed_parameter.to::<H>()?;
+        let used_leafs_indexes = private_key.compressed_used_leafs_indexes.to(&parameters);
+
+        self.rfc_derivation = private_key.rfc_derivation;
+        self.private_key.truncate(first_level);
+        self.public_key.truncate(first_level.saturating_sub(1));
+        self.signatures.truncate(first_level.saturating_sub(1));
+
+        let mut current_seed = if first_level == 0 {
+            let current_seed = private_key.generate_root_seed_and_lms_tree_identifier();
+
+            let lms_private_key = LmsPrivateKey {
+                seed: current_seed.seed.clone(),
+                lms_tree_identifier: current_seed.lms_tree_identifier,
+                lmots_parameter: *parameters[0].get_lmots_parameter(),
+                lms_parameter: *parameters[0].get_lms_parameter(),
+                used_leafs_index: used_leafs_indexes[0],
+            };
+            self.private_key.push(lms_private_key);
+
+            current_seed
+        } else {
+            let parent = &mut self.private_key[first_level - 1];
+            parent.used_leafs_index = used_leafs_indexes[first_level - 1];
+
+            SeedAndLmsTreeIdentifier::new(&parent.seed, &parent.lms_tree_identifier)
+        };
+
+        for (i, parameter) in parameters.iter().enumerate().skip(first_level.max(1)) {
+            let parent_used_leafs_index: u32 = self.private_key[i - 1].used_leafs_index;
+
+            // The rollover of the bottom tree only swaps in the pregenerated tree
+            if i == parameters.len() - 1 && used_leafs_indexes[i] == 0 {
+                if let Some(next_bottom_tree) = next_bottom_tree.as_deref_mut() {
+                    if let Some(tree) = next_bottom_tree.take(&self.private_key[i - 1])? {
+                        // The aux data still caches the previous tree, it is refilled on its
+                        // next regeneration
+                        Self::select_lower_aux_data(
+                            lower_aux_data,
+                            i,
+                            &tree.private_key.lms_tree_identifier,
+                            private_key.seed.as_slice(),
+                            false,
+                        );
+                        self.private_key[i - 1].used_leafs_index += 1;
+                        self.private_key.push(tree.private_key);
+                        self.public_key.push(tree.public_key);
+                        self.signatures.push(tree.signature);
+                        continue;
+                    }
+                }
+            }
+
+            let child_seed = generate_child_seed_and_lms_tree_identifier::<H>(
                 &current_seed,
                 &parent_used_leafs_index,
             );
-            let signature_randomizer =
-                generate_signature_randomizer::<H>(&current_seed, &parent_used_leafs_index);
+            let signature_randomizer = generate_child_signature_randomizer::<H>(
+                &current_seed,
+                &child_seed,
+                &parent_used_leafs_index,
+                private_key.rfc_derivation,
+            );
+            current_seed = child_seed;
+
+            Self::select_lower_aux_data(
+                lower_aux_data,
+                i,
+                &current_seed.lms_tree_identifier,
+                private_key.seed.as_slice(),
+                true,
+            );
+            let mut no_aux_data = None;
+            let level_aux_data = lower_aux_data.get_mut(i).unwrap_or(&mut no_aux_data);
 
-            let lms_keypair =
-                generate_key_pair(&current_seed, parameter, &used_leafs_indexes[i], &mut None);
+            let lms_keypair = generate_key_pair(
+                &current_seed,
+                parameter,
+                &used_leafs_indexes[i],
+                level_aux_data,
+            );
+
+            let mut no_aux_data = None;
+            let parent_aux_data = if i == 1 {
+                &mut *aux_data
+            } else {
+                lower_aux_data.get_mut(i - 1).unwrap_or(&mut no_aux_data)
+            };
 
             let signature = lms::signing::LmsSignature::sign(
-                &mut hss_private_key.private_key[i - 1],
+                &mut self.private_key[i - 1],
                 lms_keypair.public_key.to_binary_representation().as_slice(),
                 &signature_randomizer,
-                aux_data,
+                parent_aux_data,
             )?;
-            *aux_data = None;
 
-            hss_private_key.private_key.push(lms_keypair.private_key);
-            hss_private_key.public_key.push(lms_keypair.public_key);
-            hss_private_key.signatures.push(signature);
+            self.private_key.push(lms_keypair.private_key);
+            self.public_key.push(lms_keypair.public_key);
+            self.signatures.push(signature);
         }
 
-        Ok(hss_private_key)
+        Ok(())
+    }
+
+    /**
+     * Keeps the aux data of `level` only, if it caches the tree `lms_tree_identifier`. With
+     * `reset`, the aux data of another tree is cleared for the tree instead.
+     */
+    fn select_lower_aux_data(
+        lower_aux_data: &mut LowerAuxData,
+        level: usize,
+        lms_tree_identifier: &[u8],
+        seed: &[u8],
+        reset: bool,
+    ) {
+        let level_aux_data = match lower_aux_data.get_mut(level) {
+            Some(level_aux_data) => level_aux_data,
+            None => return,
+        };
+
+        if let Some(data) = level_aux_data.as_mut() {
+            if hss_is_lower_aux_data_valid::<H>(data, lms_tree_identifier, seed) {
+                return;
+            }
+            if reset {
+                hss_reset_lower_aux_data(data, lms_tree_identifier);
+                return;
+            }
+        }
+
+        *level_aux_data = None;
     }
 
     pub fn get_expanded_aux_data<'a>(
@@ -91,8 +282,11 @@ impl<H: HashChain> HssPrivateKey<H> {
         private_key: &'a ReferenceImplPrivateKey<H>,
         top_lms_parameter: &LmsParameter<H>,
         is_aux_data_used: bool,
-    ) -> Option<MutableExpandedAuxData<'a>> {
-        let aux_data = aux_data?;
+    ) -> Result<Option<MutableExpandedAuxData<'a>>, Error> {
+        let aux_data = match aux_data {
+            Some(aux_data) => aux_data,
+            None => return Ok(None),
+        };
 
         if is_aux_data_used {
             return hss_expand_aux_data::<H>(Some(aux_data), Some(private_key.seed.as_slice()));
@@ -100,15 +294,25 @@ impl<H: HashChain> HssPrivateKey<H> {
 
         // Shrink input slice
         let aux_len = hss_get_aux_data_len(aux_data.len(), *top_lms_parameter);
+        let aux_level = hss_optimal_aux_level(aux_len, *top_lms_parameter, None);
+
+        // The space the top-level tree can't make use of is left to the lower-level trees
+        let lower_aux_len = if aux_level != 0 {
+            let parameters = private_key.compressed_parameter.to::<H>()?;
+            hss_store_lower_aux_markers(&mut aux_data[aux_len..], &parameters[1..])
+        } else {
+            0
+        };
+
         let moved = core::mem::take(aux_data);
-        *aux_data = &mut moved[..aux_len];
+        *aux_data = &mut moved[..aux_len + lower_aux_len];
 
-        let aux_level = hss_optimal_aux_level(aux_len, *top_lms_parameter, None);
         hss_store_aux_marker(aux_data, aux_level);
 
         hss_expand_aux_data::<H>(Some(aux_data), None)
     }
 
+    #[cfg(test)]
     pub fn get_lifetime(&self) -> u64 {
         let mut lifetime: u64 = 0;
         let mut trees_total_lmots_keys: ArrayVec<[u64; MAX_ALLOWED_HSS_LEVELS]> = ArrayVec::new();
@@ -154,7 +358,123 @@ impl<H: HashChain> HssPublicKey<H> {
     pub fn from(
         private_key: &ReferenceImplPrivateKey<H>,
         aux_data: Option<&mut &mut [u8]>,
-    ) -> Result<Self, ()> {
+    ) -> Result<Self, Error> {
+        Self::generate(private_key, aux_data, |lms_private_key, aux_data| {
+            Ok(LmsPublicKey::new(lms_private_key, aux_data))
+        })
+    }
+
+    /**
+     * Like [`HssPublicKey::from`], but computes the top-level tree on `threads` threads.
+     */
+    #[cfg(feature = "std")]
+    pub fn from_parallel(
+        private_key: &ReferenceImplPrivateKey<H>,
+        aux_data: Option<&mut &mut [u8]>,
+        threads: usize,
+    ) -> Result<Self, Error> {
+        Self::generate(private_key, aux_data, |lms_private_key, aux_data| {
+            Ok(LmsPublicKey::new_parallel(
+                lms_private_key,
+                aux_data,
+                threads,
+            ))
+        })
+    }
+
+    /**
+     * Like [`HssPublicKey::from`], but reports the progress of the top-level tree to `observer`,
+     * which may cancel the computation.
+     */
+    pub fn from_with_observer(
+        private_key: &ReferenceImplPrivateKey<H>,
+        aux_data: Option<&mut &mut [u8]>,
+        observer: &mut dyn KeygenObserver,
+    ) -> Result<Self, Error> {
+        Self::generate(private_key, aux_data, |lms_private_key, aux_data| {
+            let leafs_total = lms_private_key.lms_parameter.number_of_lm_ots_keys() as u64;
+            let mut progress_tracker = Some(KeygenProgressTracker::new(
+                Some(observer),
+                None,
+                0,
+                leafs_total,
+            ));
+            LmsPublicKey::new_tracked(lms_private_key, aux_data, &mut progress_tracker)
+        })
+    }
+
+    /**
+     * Like [`HssPublicKey::from`], but resumes the top-level tree from `checkpoint` and passes
+     * every updated checkpoint to `checkpoint_store`.
+     */
+    pub fn from_with_checkpoint(
+        private_key: &ReferenceImplPrivateKey<H>,
+        aux_data: Option<&mut &mut [u8]>,
+        checkpoint: &mut [u8],
+        checkpoint_store: &mut dyn CheckpointStore,
+    ) -> Result<Self, Error> {
+        Self::generate(private_key, aux_data, |lms_private_key, aux_data| {
+            // The checkpoint has to cover the deepest aux data level, as the nodes below the
+            // completed subtrees are not computed again
+            let deepest_aux_level = aux_data
+                .as_ref()
+                .and_then(|aux_data| aux_data.data.iter().rposition(Option::is_some))
+                .unwrap_or(0);
+            let mut checkpoint = Checkpoint::new(
+                checkpoint,
+                &private_key.seed,
+                lms_private_key,
+                deepest_aux_level,
+                checkpoint_store,
+            )?;
+
+            let leafs_total = lms_private_key.lms_parameter.number_of_lm_ots_keys() as u64;
+            let mut progress_tracker = Some(KeygenProgressTracker::new(
+                None,
+                Some(&mut checkpoint),
+                0,
+                leafs_total,
+            ));
+            LmsPublicKey::new_tracked(lms_private_key, aux_data, &mut progress_tracker)
+        })
+    }
+
+    /**
+     * Like [`HssPublicKey::from`], but takes the subtree roots and their aux data from
+     * `work_units` and only computes the levels above them.
+     */
+    pub fn from_work_units(
+        private_key: &ReferenceImplPrivateKey<H>,
+        aux_data: Option<&mut &mut [u8]>,
+        work_units: &[&[u8]],
+    ) -> Result<Self, Error> {
+        Self::generate(private_key, aux_data, |lms_private_key, aux_data| {
+            let aux_level = aux_data.as_ref().map_or(0, |aux_data| aux_data.level);
+            let mut work_unit_nodes =
+                WorkUnitNodes::new(work_units, &private_key.seed, lms_private_key, aux_level)?;
+            if let Some(aux_data) = aux_data.as_mut() {
+                work_unit_nodes.copy_aux_data(aux_data);
+            }
+
+            let leafs_total = lms_private_key.lms_parameter.number_of_lm_ots_keys() as u64;
+            let mut progress_tracker = Some(KeygenProgressTracker::new(
+                None,
+                Some(&mut work_unit_nodes),
+                0,
+                leafs_total,
+            ));
+            LmsPublicKey::new_tracked(lms_private_key, aux_data, &mut progress_tracker)
+        })
+    }
+
+    fn generate(
+        private_key: &ReferenceImplPrivateKey<H>,
+        mut aux_data: Option<&mut &mut [u8]>,
+        generate_public_key: impl FnOnce(
+            &LmsPrivateKey<H>,
+            &mut Option<MutableExpandedAuxData>,
+        ) -> Result<LmsPublicKey<H>, Error>,
+    ) -> Result<Self, Error> {
         let parameters = private_key.compressed_parameter.to::<H>()?;
         let levels = parameters.len();
         let used_leafs_indexes = private_key.compressed_used_leafs_indexes.to(&parameters);
@@ -167,21 +487,34 @@ impl<H: HashChain> HssPublicKey<H> {
             false
         };
 
+        // Invalid aux data is not rewritten, the public key is computed without it instead
         let mut expanded_aux_data = HssPrivateKey::get_expanded_aux_data(
-            aux_data,
+            aux_data.as_deref_mut(),
             private_key,
             top_lms_parameter,
             is_aux_data_used,
-        );
+        )
+        .unwrap_or(None);
 
         let current_seed = private_key.generate_root_seed_and_lms_tree_identifier();
 
-        let lms_keypair = generate_key_pair(
-            &current_seed,
-            &parameters[0],
-            &used_leafs_indexes[0],
-            &mut expanded_aux_data,
+        let lms_private_key = LmsPrivateKey::new(
+            current_seed.seed.clone(),
+            current_seed.lms_tree_identifier,
+            used_leafs_indexes[0],
+            *parameters[0].get_lmots_parameter(),
+            *top_lms_parameter,
         );
+        let public_key = match generate_public_key(&lms_private_key, &mut expanded_aux_data) {
+            Ok(public_key) => public_key,
+            Err(error) => {
+                // Don't leave partially computed aux data behind
+                if let (Some(aux_data), false) = (aux_data, is_aux_data_used) {
+                    hss_store_aux_marker(aux_data, 0);
+                }
+                return Err(error);
+            }
+        };
 
         if let Some(expanded_aux_data) = expanded_aux_data.as_mut() {
             if !is_aux_data_used {
@@ -190,7 +523,7 @@ impl<H: HashChain> HssPublicKey<H> {
         }
 
         Ok(Self {
-            public_key: lms_keypair.public_key,
+            public_key,
             level: levels,
         })
     }
@@ -205,17 +538,18 @@ impl<H: HashChain> HssPublicKey<H> {
 }
 
 impl<'a, H: HashChain> InMemoryHssPublicKey<'a, H> {
-    pub fn new(data: &'a [u8]) -> Option<Self> {
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
         let mut index = 0;
 
-        let level = u32::from_be_bytes(read_and_advance(data, 4, &mut index).try_into().unwrap());
+        let level = u32::from_be_bytes(
+            try_read_and_advance(data, 4, &mut index, Error::MalformedPublicKey)?
+                .try_into()
+                .unwrap(),
+        ) as usize;
 
         let public_key = InMemoryLmsPublicKey::new(&data[index..])?;
 
-        Some(Self {
-            public_key,
-            level: level as usize,
-        })
+        Ok(Self { public_key, level })
     }
 }
 
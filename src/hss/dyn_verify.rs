@@ -0,0 +1,217 @@
+use core::convert::{TryFrom, TryInto};
+use tinyvec::ArrayVec;
+
+use crate::{
+    constants::MAX_HSS_PUBLIC_KEY_LENGTH, hss::hss_verify, signature::Verifier,
+    util::helper::try_read_and_advance, Error, HashChain, LmsAlgorithm, Sha256_192, Sha256_256,
+    Shake256_192, Shake256_256, Signature, VerifierSignature,
+};
+
+/**
+ * Hash function and output size, as identified by the LMS typecode of a public key.
+ *
+ * The variants with 128 bit outputs only have typecodes of the private use range and are not
+ * supported.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HashFunction {
+    Sha256_256,
+    Sha256_192,
+    Shake256_256,
+    Shake256_192,
+}
+
+impl HashFunction {
+    fn from_public_key(public_key: &[u8]) -> Result<Self, Error> {
+        let mut index = 4;
+        let lms_type = u32::from_be_bytes(
+            try_read_and_advance(public_key, 4, &mut index, Error::MalformedPublicKey)?
+                .try_into()
+                .unwrap(),
+        );
+
+        if is_lms_type_of::<Sha256_256>(lms_type) {
+            Ok(HashFunction::Sha256_256)
+        } else if is_lms_type_of::<Sha256_192>(lms_type) {
+            Ok(HashFunction::Sha256_192)
+        } else if is_lms_type_of::<Shake256_256>(lms_type) {
+            Ok(HashFunction::Shake256_256)
+        } else if is_lms_type_of::<Shake256_192>(lms_type) {
+            Ok(HashFunction::Shake256_192)
+        } else {
+            Err(Error::UnsupportedTypecode(lms_type))
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8], public_key: &[u8]) -> Result<(), Error> {
+        match self {
+            HashFunction::Sha256_256 => hss_verify::<Sha256_256>(message, signature, public_key),
+            HashFunction::Sha256_192 => hss_verify::<Sha256_192>(message, signature, public_key),
+            HashFunction::Shake256_256 => {
+                hss_verify::<Shake256_256>(message, signature, public_key)
+            }
+            HashFunction::Shake256_192 => {
+                hss_verify::<Shake256_192>(message, signature, public_key)
+            }
+        }
+    }
+}
+
+fn is_lms_type_of<H: HashChain>(lms_type: u32) -> bool {
+    LmsAlgorithm::get_from_type::<H>(lms_type).is_some()
+}
+
+/**
+ * Verifying key of any hash function with assigned typecodes, selected at runtime.
+ *
+ * In contrast to [`VerifyingKey`](crate::VerifyingKey), the hash function is not a generic
+ * parameter, but determined from the LMS typecode of the public key. Signatures using typecodes
+ * of another hash function are rejected with [`Error::UnsupportedTypecode`].
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DynVerifyingKey {
+    bytes: ArrayVec<[u8; MAX_HSS_PUBLIC_KEY_LENGTH]>,
+    hash_function: HashFunction,
+}
+
+impl DynVerifyingKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let hash_function = HashFunction::from_public_key(bytes)?;
+        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::MalformedPublicKey)?;
+
+        Ok(Self {
+            bytes,
+            hash_function,
+        })
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+}
+
+impl Verifier<Signature> for DynVerifyingKey {
+    fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), signature::Error> {
+        Ok(self
+            .hash_function
+            .verify(msg, signature.as_ref(), &self.bytes)?)
+    }
+}
+
+impl<'a> Verifier<VerifierSignature<'a>> for DynVerifyingKey {
+    fn verify(&self, msg: &[u8], signature: &VerifierSignature) -> Result<(), signature::Error> {
+        Ok(self
+            .hash_function
+            .verify(msg, signature.as_ref(), &self.bytes)?)
+    }
+}
+
+/**
+ * Verify a signature without knowing the hash function at compile time.
+ *
+ * The hash function is selected by the LMS typecode of `public_key`, see [`DynVerifyingKey`].
+ *
+ * # Arguments
+ * * `message` - The message that should be verified.
+ * * `signature` - The signature that should be used for verification.
+ * * `public_key` - The public key that should be used for verification.
+ */
+pub fn verify_any(message: &[u8], signature: &[u8], public_key: &[u8]) -> Result<(), Error> {
+    HashFunction::from_public_key(public_key)?.verify(message, signature, public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hss::{hss_keygen, hss_sign},
+        util::helper::test_helper::gen_random_seed,
+        HssParameter, LmotsAlgorithm, Sha256_128,
+    };
+
+    macro_rules! generate_test {
+        ($name:ident, $hash_chain:ty, $hash_function:expr) => {
+            #[test]
+            fn $name() {
+                type H = $hash_chain;
+                let seed = gen_random_seed::<H>();
+                let parameters = [HssParameter::new(
+                    LmotsAlgorithm::LmotsW2,
+                    LmsAlgorithm::LmsH5,
+                )];
+
+                let (mut signing_key, verifying_key) =
+                    hss_keygen::<H>(&parameters, &seed, None).unwrap();
+                let signature = hss_sign::<H>(b"message", &mut signing_key, None).unwrap();
+
+                assert!(
+                    verify_any(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+                );
+                assert!(
+                    verify_any(b"massage", signature.as_ref(), verifying_key.as_slice()).is_err()
+                );
+
+                let dyn_verifying_key =
+                    DynVerifyingKey::from_bytes(verifying_key.as_slice()).unwrap();
+                assert_eq!(dyn_verifying_key.hash_function, $hash_function);
+                assert!(dyn_verifying_key.verify(b"message", &signature).is_ok());
+            }
+        };
+    }
+
+    generate_test!(verify_any_sha256_256, Sha256_256, HashFunction::Sha256_256);
+
+    generate_test!(verify_any_sha256_192, Sha256_192, HashFunction::Sha256_192);
+
+    generate_test!(
+        verify_any_shake256_256,
+        Shake256_256,
+        HashFunction::Shake256_256
+    );
+
+    generate_test!(
+        verify_any_shake256_192,
+        Shake256_192,
+        HashFunction::Shake256_192
+    );
+
+    #[test]
+    fn reject_mismatching_typecodes() {
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+
+        let parameters = [HssParameter::new(lmots, lms)];
+        let seed = gen_random_seed::<Sha256_256>();
+        let (mut signing_key, _) = hss_keygen::<Sha256_256>(&parameters, &seed, None).unwrap();
+        let signature = hss_sign::<Sha256_256>(b"message", &mut signing_key, None).unwrap();
+
+        let parameters = [HssParameter::new(lmots, lms)];
+        let seed = gen_random_seed::<Shake256_256>();
+        let (_, verifying_key) = hss_keygen::<Shake256_256>(&parameters, &seed, None).unwrap();
+
+        assert!(matches!(
+            verify_any(b"message", signature.as_ref(), verifying_key.as_slice()),
+            Err(Error::UnsupportedTypecode(_))
+        ));
+
+        let mut public_key = [0u8; 60];
+        public_key[..verifying_key.as_slice().len()].copy_from_slice(verifying_key.as_slice());
+        public_key[7] = 0x7f;
+        assert_eq!(
+            DynVerifyingKey::from_bytes(&public_key),
+            Err(Error::UnsupportedTypecode(0x7f))
+        );
+        assert_eq!(
+            DynVerifyingKey::from_bytes(&public_key[..6]),
+            Err(Error::MalformedPublicKey)
+        );
+
+        // Keys with 128 bit outputs use private typecodes, which are not supported
+        let parameters = [HssParameter::new(lmots, lms)];
+        let seed = gen_random_seed::<Sha256_128>();
+        let (mut signing_key, verifying_key) =
+            hss_keygen::<Sha256_128>(&parameters, &seed, None).unwrap();
+        let signature = hss_sign::<Sha256_128>(b"message", &mut signing_key, None).unwrap();
+        assert!(verify_any(b"message", signature.as_ref(), verifying_key.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use tinyvec::ArrayVec;
+
+use crate::{
+    constants::MAX_ALLOWED_HSS_LEVELS, hss::reference_impl_private_key::ReferenceImplPrivateKey,
+    Error, HashChain,
+};
+
+/**
+ * Snapshot of the consumed and remaining one-time keys of a private key.
+ *
+ * The private key of the reference implementation is wiped once its last one-time key was used,
+ * afterwards no [`KeyUsage`] can be determined anymore and [`Error::KeyExhausted`] is returned.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyUsage {
+    /// Index of the next leaf of the whole HSS hierarchy, i.e. the number of consumed leafs.
+    /// For keys created by [`crate::SigningKey::split_off`] this includes the leafs in front of
+    /// the range of the key.
+    pub used: u64,
+    /// Number of signatures that can still be generated.
+    pub remaining: u64,
+    /// Leaf used by the next signature in every HSS level, starting with the top level.
+    pub leaf_indexes: ArrayVec<[u32; MAX_ALLOWED_HSS_LEVELS]>,
+    /// Number of signatures left until the bottom tree is replaced by the next one.
+    pub bottom_tree_remaining: u32,
+}
+
+impl KeyUsage {
+    pub(crate) fn from_private_key<H: HashChain>(
+        private_key: &ReferenceImplPrivateKey<H>,
+    ) -> Result<Self, Error> {
+        let parameters = private_key.compressed_parameter.to::<H>()?;
+        let used = private_key.compressed_used_leafs_indexes.count();
+        let remaining = (private_key.get_max_used_leafs_index()? - used).saturating_add(1);
+
+        let leaf_indexes = private_key
+            .compressed_used_leafs_indexes
+            .to(&parameters)
+            .iter()
+            .take(parameters.len())
+            .copied()
+            .collect::<ArrayVec<[u32; MAX_ALLOWED_HSS_LEVELS]>>();
+
+        let bottom_lms_parameter = parameters[parameters.len() - 1].get_lms_parameter();
+        let bottom_tree_leafs = bottom_lms_parameter.number_of_lm_ots_keys() as u64;
+        let bottom_tree_remaining =
+            (bottom_tree_leafs - leaf_indexes[leaf_indexes.len() - 1] as u64).min(remaining);
+
+        Ok(Self {
+            used,
+            remaining,
+            leaf_indexes,
+            bottom_tree_remaining: bottom_tree_remaining as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyUsage;
+    use crate::{
+        hss::{hss_keygen, hss_sign},
+        util::helper::test_helper::gen_random_seed,
+        Error, HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+    };
+
+    #[test]
+    fn track_usage_until_exhaustion() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let (mut signing_key, _) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+
+        for used in 0..16u64 {
+            let usage = signing_key.usage().unwrap();
+            assert_eq!(
+                usage,
+                KeyUsage {
+                    used,
+                    remaining: 16 - used,
+                    leaf_indexes: [(used / 4) as u32, (used % 4) as u32]
+                        .iter()
+                        .copied()
+                        .collect(),
+                    bottom_tree_remaining: 4 - (used % 4) as u32,
+                }
+            );
+            assert_eq!(signing_key.get_lifetime().unwrap(), usage.remaining);
+
+            hss_sign::<H>(b"message", &mut signing_key, None).unwrap();
+        }
+
+        assert_eq!(signing_key.usage(), Err(Error::KeyExhausted));
+    }
+
+    #[test]
+    fn usage_of_split_off_key() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let (mut signing_key, _) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let split_off_key = signing_key.split_off(10).unwrap();
+
+        let usage = signing_key.usage().unwrap();
+        assert_eq!((usage.used, usage.remaining), (0, 6));
+        assert_eq!(usage.bottom_tree_remaining, 4);
+
+        let usage = split_off_key.usage().unwrap();
+        assert_eq!((usage.used, usage.remaining), (6, 10));
+        assert_eq!(usage.leaf_indexes.as_slice(), &[1, 2]);
+        assert_eq!(usage.bottom_tree_remaining, 2);
+
+        for _ in 0..5 {
+            hss_sign::<H>(b"message", &mut signing_key, None).unwrap();
+        }
+        let usage = signing_key.usage().unwrap();
+        assert_eq!((usage.remaining, usage.bottom_tree_remaining), (1, 1));
+    }
+}
@@ -1,26 +1,50 @@
 pub mod aux;
+pub mod aux_data;
+pub mod checkpoint;
 pub mod definitions;
+pub mod dyn_verify;
+pub mod key_usage;
 pub mod parameter;
+pub(crate) mod pregeneration;
+pub mod progress;
 pub mod reference_impl_private_key;
-mod seed_derive;
+pub mod rollback;
+pub(crate) mod seed_derive;
 pub mod signing;
+pub mod state_store;
+pub mod stateful_signer;
 pub mod verify;
+pub mod work_unit;
 
 use core::{convert::TryFrom, marker::PhantomData};
 use tinyvec::ArrayVec;
 
 use crate::{
-    constants::{MAX_HSS_PUBLIC_KEY_LENGTH, REF_IMPL_MAX_PRIVATE_KEY_SIZE},
-    hss::{aux::hss_is_aux_data_used, reference_impl_private_key::Seed},
-    signature::{Error, SignerMut, Verifier},
-    HashChain, Signature, VerifierSignature,
+    constants::{LmsTreeIdentifier, MAX_HSS_PUBLIC_KEY_LENGTH, MAX_PRIVATE_KEY_SIZE},
+    hss::{
+        aux::{
+            hss_clear_aux_data, hss_expand_lower_aux_data, hss_finalize_aux_data,
+            hss_is_aux_data_used,
+        },
+        aux_data::{with_aux_slice, AuxData},
+        reference_impl_private_key::Seed,
+    },
+    lms::traversal::MerkleTraversal,
+    signature::{SignerMut, Verifier},
+    Error, HashChain, Signature, VerifierSignature,
 };
 
 use self::{
+    checkpoint::CheckpointStore,
     definitions::{HssPrivateKey, HssPublicKey, InMemoryHssPublicKey},
+    key_usage::KeyUsage,
     parameter::HssParameter,
-    reference_impl_private_key::ReferenceImplPrivateKey,
+    pregeneration::NextBottomTree,
+    progress::KeygenObserver,
+    reference_impl_private_key::{CompressedUsedLeafsIndexes, ReferenceImplPrivateKey},
     signing::{HssSignature, InMemoryHssSignature},
+    state_store::StateStore,
+    verify::VerificationCache,
 };
 
 /**
@@ -28,13 +52,13 @@ use self::{
  */
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SigningKey<H: HashChain> {
-    pub bytes: ArrayVec<[u8; REF_IMPL_MAX_PRIVATE_KEY_SIZE]>,
+    pub bytes: ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>,
     phantom_data: PhantomData<H>,
 }
 
 impl<H: HashChain> SigningKey<H> {
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::new())?;
+        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::MalformedPrivateKey)?;
 
         Ok(Self {
             bytes,
@@ -51,12 +75,94 @@ impl<H: HashChain> SigningKey<H> {
     }
 
     pub fn get_lifetime(&self) -> Result<u64, Error> {
-        let rfc_sk = ReferenceImplPrivateKey::from_binary_representation(self.bytes.as_slice())
-            .map_err(|_| Error::new())?;
+        Ok(self.usage()?.remaining)
+    }
+
+    /**
+     * Returns the consumed and remaining one-time keys of this key. Fails with
+     * [`Error::KeyExhausted`] once the key was wiped after its last signature.
+     */
+    pub fn usage(&self) -> Result<KeyUsage, Error> {
+        let rfc_sk = ReferenceImplPrivateKey::<H>::from_binary_representation(&self.bytes)?;
+
+        KeyUsage::from_private_key(&rfc_sk)
+    }
+
+    /**
+     * Appends a state tag to this key, i.e. an HMAC keyed from the seed over the private key.
+     * Keys with a state tag are refused with [`Error::InvalidStateTag`] if the leaf counter or
+     * any other part of the key was modified. Together with a
+     * [`RollbackProtectedStateStore`](crate::RollbackProtectedStateStore) this protects against
+     * restoring old states. The tag is kept on every update of the key, but such keys are no
+     * longer compatible with the reference implementation.
+     */
+    pub fn enable_state_tag(&mut self) -> Result<(), Error> {
+        let mut rfc_sk = ReferenceImplPrivateKey::<H>::from_binary_representation(&self.bytes)?;
+        rfc_sk.state_tag = true;
+        self.bytes = rfc_sk.to_binary_representation();
+
+        Ok(())
+    }
+
+    /**
+     * Splits off the last `leafs` unused leafs of this key into a new [`SigningKey`].
+     *
+     * Both keys cover disjoint ranges of leafs and verify with the same [`VerifyingKey`]. Each
+     * key encodes its upper bound and refuses to sign past it. `self` keeps at least one leaf.
+     * The updated `self` must be persisted before the returned key is used.
+     */
+    pub fn split_off(&mut self, leafs: u64) -> Result<Self, Error> {
+        let mut rfc_sk = ReferenceImplPrivateKey::<H>::from_binary_representation(&self.bytes)?;
+
+        let count = rfc_sk.compressed_used_leafs_indexes.count();
+        let max_used_leafs_index = rfc_sk.get_max_used_leafs_index()?;
+
+        if leafs == 0 || leafs > max_used_leafs_index - count {
+            return Err(Error::InvalidParameters);
+        }
+        let split = max_used_leafs_index - (leafs - 1);
+
+        let mut split_sk = rfc_sk.clone();
+        split_sk.compressed_used_leafs_indexes = CompressedUsedLeafsIndexes::with_limit(
+            split,
+            rfc_sk.compressed_used_leafs_indexes.limit(),
+        );
+        rfc_sk.set_leaf_limit(split)?;
 
-        let parsed_sk = HssPrivateKey::<H>::from(&rfc_sk, &mut None).map_err(|_| Error::new())?;
+        let split_off = Self::from_bytes(&split_sk.to_binary_representation())?;
+        self.bytes = rfc_sk.to_binary_representation();
 
-        Ok(parsed_sk.get_lifetime())
+        Ok(split_off)
+    }
+
+    /**
+     * Partitions the unused leafs of this key into `parts` [`SigningKey`]s with disjoint ranges
+     * of leafs of (nearly) equal size. All keys verify with the same [`VerifyingKey`].
+     */
+    #[cfg(feature = "std")]
+    pub fn partition(mut self, parts: u64) -> Result<Vec<Self>, Error> {
+        let rfc_sk = ReferenceImplPrivateKey::<H>::from_binary_representation(&self.bytes)?;
+
+        let count = rfc_sk.compressed_used_leafs_indexes.count();
+        let max_used_leafs_index = rfc_sk.get_max_used_leafs_index()?;
+        let remaining_leafs = (max_used_leafs_index - count) as u128 + 1;
+
+        if parts == 0 || parts as u128 > remaining_leafs {
+            return Err(Error::InvalidParameters);
+        }
+
+        let leafs_per_part = remaining_leafs / parts as u128;
+        let additional_leafs = remaining_leafs % parts as u128;
+
+        let mut result = Vec::with_capacity(parts as usize);
+        for part in (1..parts).rev() {
+            let leafs = leafs_per_part + u128::from((part as u128) < additional_leafs);
+            result.push(self.split_off(leafs as u64)?);
+        }
+        result.push(self);
+        result.reverse();
+
+        Ok(result)
     }
 
     pub fn try_sign_with_aux(
@@ -64,24 +170,33 @@ impl<H: HashChain> SigningKey<H> {
         msg: &[u8],
         aux_data: Option<&mut &mut [u8]>,
     ) -> Result<Signature, Error> {
-        let private_key = self.bytes;
-        let mut private_key_update_function = |new_key: &[u8]| {
-            self.bytes.as_mut_slice().copy_from_slice(new_key);
-            Ok(())
-        };
+        hss_sign::<H>(msg, self, aux_data)
+    }
 
-        hss_sign::<H>(
-            msg,
-            private_key.as_slice(),
-            &mut private_key_update_function,
-            aux_data,
+    /**
+     * Like [`SigningKey::try_sign_with_aux`], but takes the aux data as [`AuxData`], which may
+     * be populated lazily.
+     */
+    pub fn try_sign_with_aux_data(
+        &mut self,
+        msg: &[u8],
+        aux_data: &mut AuxData<H>,
+    ) -> Result<Signature, Error> {
+        hss_sign_core::<H>(
+            Some(msg),
+            None,
+            self,
+            Some(aux_data),
+            &mut HssPrivateKey::default(),
+            None,
+            None,
         )
     }
 }
 
 impl<H: HashChain> SignerMut<Signature> for SigningKey<H> {
-    fn try_sign(&mut self, msg: &[u8]) -> Result<Signature, Error> {
-        self.try_sign_with_aux(msg, None)
+    fn try_sign(&mut self, msg: &[u8]) -> Result<Signature, signature::Error> {
+        Ok(self.try_sign_with_aux(msg, None)?)
     }
 }
 
@@ -96,7 +211,7 @@ pub struct VerifyingKey<H: HashChain> {
 
 impl<H: HashChain> VerifyingKey<H> {
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::new())?;
+        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::MalformedPublicKey)?;
 
         Ok(Self {
             bytes,
@@ -107,17 +222,30 @@ impl<H: HashChain> VerifyingKey<H> {
     pub fn as_slice(&self) -> &[u8] {
         self.bytes.as_slice()
     }
+
+    /**
+     * Verify `signature` of `msg` and skip the signed public keys `cache` has verified before.
+     * See [`VerificationCache`].
+     */
+    pub fn verify_with_cache<const N: usize>(
+        &self,
+        msg: &[u8],
+        signature: &[u8],
+        cache: &mut VerificationCache<H, N>,
+    ) -> Result<(), Error> {
+        cache.verify(msg, signature, &self.bytes)
+    }
 }
 
 impl<H: HashChain> Verifier<Signature> for VerifyingKey<H> {
-    fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), Error> {
-        hss_verify::<H>(msg, signature.as_ref(), &self.bytes)
+    fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), signature::Error> {
+        Ok(hss_verify::<H>(msg, signature.as_ref(), &self.bytes)?)
     }
 }
 
 impl<'a, H: HashChain> Verifier<VerifierSignature<'a>> for VerifyingKey<H> {
-    fn verify(&self, msg: &[u8], signature: &VerifierSignature) -> Result<(), Error> {
-        hss_verify::<H>(msg, signature.as_ref(), &self.bytes)
+    fn verify(&self, msg: &[u8], signature: &VerifierSignature) -> Result<(), signature::Error> {
+        Ok(hss_verify::<H>(msg, signature.as_ref(), &self.bytes)?)
     }
 }
 
@@ -135,10 +263,53 @@ pub fn hss_verify<H: HashChain>(
     signature: &[u8],
     public_key: &[u8],
 ) -> Result<(), Error> {
-    let signature = InMemoryHssSignature::<H>::new(signature).ok_or_else(Error::new)?;
-    let public_key = InMemoryHssPublicKey::<H>::new(public_key).ok_or_else(Error::new)?;
+    let signature = InMemoryHssSignature::<H>::new(signature)?;
+    let public_key = InMemoryHssPublicKey::<H>::new(public_key)?;
+
+    crate::hss::verify::verify(&signature, &public_key, message)
+}
+
+/**
+ * Verify many signatures under the same public key.
+ *
+ * The result of every `(message, signature)` of `items` is stored in the corresponding element of
+ * `results`, like [`hss_verify`] would return it. Signatures of the same lower-level trees share
+ * their signed public keys, which are only verified once.
+ *
+ * Fails with [`Error::InvalidParameters`] if `results` and `items` differ in length, and with
+ * [`Error::MalformedPublicKey`] if the public key can not be parsed.
+ */
+pub fn hss_verify_batch<H: HashChain>(
+    public_key: &[u8],
+    items: &[(&[u8], &[u8])],
+    results: &mut [Result<(), Error>],
+) -> Result<(), Error> {
+    if items.len() != results.len() {
+        return Err(Error::InvalidParameters);
+    }
+    let public_key = InMemoryHssPublicKey::<H>::new(public_key)?;
+
+    crate::hss::verify::verify_batch(&public_key, items, results);
+    Ok(())
+}
+
+/**
+ * Like [`hss_verify_batch`], but verifies the bottom level of the signatures on `threads` threads.
+ */
+#[cfg(feature = "std")]
+pub fn hss_verify_batch_parallel<H: HashChain>(
+    public_key: &[u8],
+    items: &[(&[u8], &[u8])],
+    results: &mut [Result<(), Error>],
+    threads: usize,
+) -> Result<(), Error> {
+    if items.len() != results.len() {
+        return Err(Error::InvalidParameters);
+    }
+    let public_key = InMemoryHssPublicKey::<H>::new(public_key)?;
 
-    crate::hss::verify::verify(&signature, &public_key, message).map_err(|_| Error::new())
+    crate::hss::verify::verify_batch_parallel(&public_key, items, results, threads);
+    Ok(())
 }
 
 /**
@@ -147,92 +318,199 @@ pub fn hss_verify<H: HashChain>(
  * # Arguments
  * * `HashChain` - The hasher implementation that should be used. ```Sha256``` is a standard software implementation.
  * * `message` - The message that should be signed.
- * * `private_key` - The private key that should be used.
- * * `private_key_update_function` - The update function that is called with the new private key. This function should save the new private key.
- * * `aux_data` - Auxiliary data to speedup signature generation if available
+ * * `state_store` - The [`StateStore`] that holds the private key. The signature is only returned if the advanced private key was stored successfully.
+ * * `aux_data` - Auxiliary data to speedup signature generation if available. Modified aux data is not used, see [`AuxData::validate`](crate::AuxData::validate).
  */
 pub fn hss_sign<H: HashChain>(
     message: &[u8],
-    private_key: &[u8],
-    private_key_update_function: &mut dyn FnMut(&[u8]) -> Result<(), ()>,
+    state_store: &mut dyn StateStore,
     aux_data: Option<&mut &mut [u8]>,
 ) -> Result<Signature, Error> {
-    hss_sign_core::<H>(
-        Some(message),
-        None,
-        private_key,
-        private_key_update_function,
-        aux_data,
-    )
+    with_aux_slice(aux_data, |aux_data| {
+        hss_sign_core::<H>(
+            Some(message),
+            None,
+            state_store,
+            aux_data,
+            &mut HssPrivateKey::default(),
+            None,
+            None,
+        )
+    })
 }
 
 #[cfg(feature = "fast_verify")]
 pub fn hss_sign_mut<H: HashChain>(
     message_mut: &mut [u8],
-    private_key: &[u8],
-    private_key_update_function: &mut dyn FnMut(&[u8]) -> Result<(), ()>,
+    state_store: &mut dyn StateStore,
     aux_data: Option<&mut &mut [u8]>,
 ) -> Result<Signature, Error> {
+    check_message_mut::<H>(message_mut)?;
+
+    with_aux_slice(aux_data, |aux_data| {
+        hss_sign_core::<H>(
+            None,
+            Some(message_mut),
+            state_store,
+            aux_data,
+            &mut HssPrivateKey::default(),
+            None,
+            None,
+        )
+    })
+}
+
+#[cfg(feature = "fast_verify")]
+fn check_message_mut<H: HashChain>(message_mut: &[u8]) -> Result<(), Error> {
     if message_mut.len() <= H::OUTPUT_SIZE.into() {
-        return Err(Error::new());
+        return Err(Error::InvalidMessage);
     }
 
     let (_, message_randomizer) = message_mut.split_at(message_mut.len() - H::OUTPUT_SIZE as usize);
     if !message_randomizer.iter().all(|&byte| byte == 0u8) {
-        return Err(Error::new());
+        return Err(Error::InvalidMessage);
     }
 
-    hss_sign_core::<H>(
-        None,
-        Some(message_mut),
-        private_key,
-        private_key_update_function,
-        aux_data,
-    )
+    Ok(())
 }
 
 fn hss_sign_core<H: HashChain>(
     message: Option<&[u8]>,
     message_mut: Option<&mut [u8]>,
-    private_key: &[u8],
-    private_key_update_function: &mut dyn FnMut(&[u8]) -> Result<(), ()>,
-    aux_data: Option<&mut &mut [u8]>,
+    state_store: &mut dyn StateStore,
+    aux_data: Option<&mut AuxData<H>>,
+    hss_private_key: &mut HssPrivateKey<H>,
+    next_bottom_tree: Option<&mut NextBottomTree<H>>,
+    tree_traversal: Option<&mut Option<MerkleTraversal<H>>>,
 ) -> Result<Signature, Error> {
-    let mut rfc_private_key = ReferenceImplPrivateKey::from_binary_representation(private_key)
-        .map_err(|_| Error::new())?;
+    state_store.lock()?;
+    let result = hss_sign_locked::<H>(
+        message,
+        message_mut,
+        state_store,
+        aux_data,
+        hss_private_key,
+        next_bottom_tree,
+        tree_traversal,
+    );
+    let unlock_result = state_store.unlock();
+
+    let signature = result?;
+    unlock_result?;
+    Ok(signature)
+}
+
+fn hss_sign_locked<H: HashChain>(
+    message: Option<&[u8]>,
+    message_mut: Option<&mut [u8]>,
+    state_store: &mut dyn StateStore,
+    mut aux_data: Option<&mut AuxData<H>>,
+    hss_private_key: &mut HssPrivateKey<H>,
+    mut next_bottom_tree: Option<&mut NextBottomTree<H>>,
+    tree_traversal: Option<&mut Option<MerkleTraversal<H>>>,
+) -> Result<Signature, Error> {
+    let private_key = state_store.load()?;
+    let mut rfc_private_key =
+        ReferenceImplPrivateKey::from_binary_representation(private_key.as_slice())?;
 
     let is_aux_data_used = if let Some(ref aux_data) = aux_data {
-        hss_is_aux_data_used(aux_data)
+        hss_is_aux_data_used(aux_data.as_slice())
     } else {
         false
     };
+    let populate_aux_data = aux_data.as_ref().is_some_and(|aux_data| aux_data.populate);
+
+    // Missing aux data is only laid out if the signatures fill it in
+    let aux_slice = match aux_data.as_deref_mut() {
+        Some(aux_data) if is_aux_data_used || populate_aux_data => Some(aux_data.as_mut_slice()),
+        _ => None,
+    };
 
-    let parameters = rfc_private_key
-        .compressed_parameter
-        .to::<H>()
-        .map_err(|_| Error::new())?;
+    let parameters = rfc_private_key.compressed_parameter.to::<H>()?;
     let mut expanded_aux_data = HssPrivateKey::get_expanded_aux_data(
-        aux_data,
+        aux_slice,
         &rfc_private_key,
         parameters[0].get_lms_parameter(),
         is_aux_data_used,
-    );
+    )?;
+    if let Some(expanded_aux_data) = expanded_aux_data.as_mut() {
+        expanded_aux_data.read_only = !populate_aux_data;
+        if !is_aux_data_used {
+            hss_clear_aux_data(expanded_aux_data);
+        }
+    }
+
+    let mut lower_aux_data =
+        hss_expand_lower_aux_data::<H>(&mut expanded_aux_data, parameters.len())?;
+
+    let hss_signature = (|| {
+        // Only the levels which moved on to another leaf are regenerated
+        hss_private_key.refresh(
+            &rfc_private_key,
+            &mut expanded_aux_data,
+            &mut lower_aux_data,
+            next_bottom_tree.as_deref_mut(),
+        )?;
+        let tree_traversal = match tree_traversal {
+            Some(tree_traversal) => {
+                if let Some(pregenerated) = next_bottom_tree
+                    .as_deref_mut()
+                    .and_then(|next_bottom_tree| next_bottom_tree.take_tree_traversal())
+                {
+                    *tree_traversal = Some(pregenerated);
+                }
+                let bottom = &hss_private_key.private_key[hss_private_key.get_length() - 1];
+                Some(MerkleTraversal::prepare(tree_traversal, bottom)?)
+            }
+            None => None,
+        };
+        let levels = hss_private_key.get_length();
+        if let Some(next_bottom_tree) = next_bottom_tree {
+            let parent_aux_data = if levels > 2 {
+                &mut lower_aux_data[levels - 2]
+            } else {
+                &mut expanded_aux_data
+            };
+            next_bottom_tree.advance(hss_private_key, parent_aux_data, tree_traversal.is_some())?;
+        }
+        let bottom_aux_data = if levels > 1 {
+            &mut lower_aux_data[levels - 1]
+        } else {
+            &mut expanded_aux_data
+        };
 
-    let mut private_key = HssPrivateKey::<H>::from(&rfc_private_key, &mut expanded_aux_data)
-        .map_err(|_| Error::new())?;
+        HssSignature::sign(
+            hss_private_key,
+            message,
+            message_mut,
+            bottom_aux_data,
+            tree_traversal,
+        )
+    })();
+
+    // Saved nodes are covered by the HMAC before the aux data is handed back
+    let mut is_aux_data_changed = false;
+    for expanded_aux_data in core::iter::once(&mut expanded_aux_data)
+        .chain(lower_aux_data.iter_mut())
+        .filter_map(Option::as_mut)
+        .filter(|expanded_aux_data| expanded_aux_data.changed)
+    {
+        hss_finalize_aux_data::<H>(expanded_aux_data, rfc_private_key.seed.as_slice());
+        is_aux_data_changed = true;
+    }
+    if let Some(aux_data) = aux_data {
+        aux_data.changed |= is_aux_data_changed;
+    }
 
-    let hss_signature = HssSignature::sign(
-        &mut private_key,
-        message,
-        message_mut,
-        &mut expanded_aux_data,
-    )
-    .map_err(|_| Error::new())?;
+    // The signature of the bottom level is never reused
+    hss_private_key
+        .signatures
+        .truncate(hss_private_key.get_length() - 1);
+    let hss_signature = hss_signature?;
 
-    // Advance private key
-    rfc_private_key.increment(&private_key);
-    private_key_update_function(&rfc_private_key.to_binary_representation())
-        .map_err(|_| Error::new())?;
+    // Advance private key and release the signature only if the new state is committed
+    rfc_private_key.increment(hss_private_key);
+    state_store.store(&rfc_private_key.to_binary_representation())?;
 
     let hash_iterations = {
         let mut hash_iterations: u32 = 0;
@@ -252,7 +530,7 @@ fn hss_sign_core<H: HashChain>(
  * * `HashChain` - The hasher implementation that should be used. ```Sha256``` is a standard software implementation.
  * * `parameters` - An array which specifies the Winternitz parameter and tree height of each individual HSS level. The first element describes Level 1, the second element Level 2 and so on.
  * * `seed` - An optional seed which will be used to generate the private key. It must be only used for testing purposes and not for production used key pairs.
- * * `aux_data` - The reference to a slice to auxiliary data. This can be used to speedup signature generation.
+ * * `aux_data` - The reference to a slice to auxiliary data. This can be used to speedup signature generation. Space the top-level tree can't make use of caches the trees of the lower levels.
  *
  * # Example
  * ```
@@ -278,11 +556,198 @@ pub fn hss_keygen<H: HashChain>(
     seed: &Seed<H>,
     aux_data: Option<&mut &mut [u8]>,
 ) -> Result<(SigningKey<H>, VerifyingKey<H>), Error> {
-    let private_key =
-        ReferenceImplPrivateKey::generate(parameters, seed).map_err(|_| Error::new())?;
+    let private_key = ReferenceImplPrivateKey::generate(parameters, seed)?;
+
+    keygen_from_private_key(&private_key, aux_data)
+}
+
+/**
+ * Generate [`SigningKey`] and [`VerifyingKey`] from an explicit top-level `I` and SEED.
+ *
+ * In contrast to [`hss_keygen`], `seed` is used as SEED of the top-level LMS tree as is, instead of
+ * deriving SEED and `I` from it. This allows to rebuild the keys of the RFC 8554 test cases or
+ * to import keys of other implementations. The `I` and SEED of the lower levels as well as the
+ * signature randomizers are derived as in RFC 8554 Appendix A, thus the signatures match those
+ * of the RFC test cases. The [`SigningKey`] records the top-level `I` and the derivation, such
+ * that signing stays consistent with the [`VerifyingKey`].
+ *
+ * # Arguments
+ *
+ * * `parameters` - Winternitz parameter and tree height of each HSS level, as for [`hss_keygen`].
+ * * `seed` - SEED of the top-level LMS tree.
+ * * `lms_tree_identifier` - `I` of the top-level LMS tree.
+ * * `aux_data` - The reference to a slice to auxiliary data.
+ */
+pub fn hss_keygen_with_lms_tree_identifier<H: HashChain>(
+    parameters: &[HssParameter<H>],
+    seed: &Seed<H>,
+    lms_tree_identifier: &LmsTreeIdentifier,
+    aux_data: Option<&mut &mut [u8]>,
+) -> Result<(SigningKey<H>, VerifyingKey<H>), Error> {
+    let private_key = ReferenceImplPrivateKey::generate_with_lms_tree_identifier(
+        parameters,
+        seed,
+        lms_tree_identifier,
+    )?;
+
+    keygen_from_private_key(&private_key, aux_data)
+}
+
+/**
+ * Generate [`SigningKey`] and [`VerifyingKey`] like [`hss_keygen`], but report the progress to
+ * `observer`.
+ *
+ * The observer is notified after every leaf of the top-level tree and may cancel the key
+ * generation, which then fails with [`Error::Cancelled`]. No keys are returned in that case and
+ * newly generated auxiliary data is marked as unused.
+ *
+ * # Arguments
+ *
+ * * `parameters` - Winternitz parameter and tree height of each HSS level, as for [`hss_keygen`].
+ * * `seed` - The seed which will be used to generate the private key.
+ * * `aux_data` - The reference to a slice to auxiliary data.
+ * * `observer` - The [`KeygenObserver`](crate::KeygenObserver) notified about the progress.
+ *
+ * # Example
+ * ```
+ * use core::ops::ControlFlow;
+ * use hbs_lms::{
+ *     keygen_with_observer, HssParameter, KeygenProgress, LmotsAlgorithm, LmsAlgorithm, Seed,
+ *     Sha256_256,
+ * };
+ *
+ * let parameters = [HssParameter::new(LmotsAlgorithm::LmotsW4, LmsAlgorithm::LmsH5)];
+ * let seed = Seed::default();
+ *
+ * let mut observer = |progress: &KeygenProgress| {
+ *     println!("{}/{}", progress.leafs_processed, progress.leafs_total);
+ *     ControlFlow::Continue(())
+ * };
+ * let (signing_key, verifying_key) =
+ *     keygen_with_observer::<Sha256_256>(&parameters, &seed, None, &mut observer).unwrap();
+ * ```
+ */
+pub fn hss_keygen_with_observer<H: HashChain>(
+    parameters: &[HssParameter<H>],
+    seed: &Seed<H>,
+    aux_data: Option<&mut &mut [u8]>,
+    observer: &mut dyn KeygenObserver,
+) -> Result<(SigningKey<H>, VerifyingKey<H>), Error> {
+    let private_key = ReferenceImplPrivateKey::generate(parameters, seed)?;
+    let hss_public_key = HssPublicKey::from_with_observer(&private_key, aux_data, observer)?;
+
+    keys_from_private_and_public_key(&private_key, &hss_public_key)
+}
+
+/**
+ * Generate [`SigningKey`] and [`VerifyingKey`] like [`hss_keygen`], but resume from and write
+ * checkpoints.
+ *
+ * The top-level tree is split into subtrees, whose roots are recorded in `checkpoint` as soon as
+ * they are completed. After every completed subtree, `checkpoint` is passed to `checkpoint_store`.
+ * An interrupted key generation is continued by passing the last stored checkpoint together with
+ * the same `parameters` and `seed`, which results in the same keys and aux data. A zeroed
+ * `checkpoint` starts a new key generation.
+ *
+ * The checkpoint is authenticated with an HMAC keyed from `seed`, a modified checkpoint or one of
+ * another key is rejected with [`Error::InvalidCheckpoint`].
+ *
+ * # Arguments
+ *
+ * * `parameters` - Winternitz parameter and tree height of each HSS level, as for [`hss_keygen`].
+ * * `seed` - The seed which will be used to generate the private key.
+ * * `aux_data` - The reference to a slice to auxiliary data.
+ * * `checkpoint` - The checkpoint to resume from. Its length determines the level of the subtrees,
+ *   see [`hss_checkpoint_len`](crate::keygen_checkpoint_len). The level must not be above the
+ *   deepest level stored in the aux data, otherwise [`Error::InvalidParameters`] is returned.
+ * * `checkpoint_store` - The [`CheckpointStore`](crate::CheckpointStore) persisting `checkpoint`.
+ */
+pub fn hss_keygen_with_checkpoint<H: HashChain>(
+    parameters: &[HssParameter<H>],
+    seed: &Seed<H>,
+    aux_data: Option<&mut &mut [u8]>,
+    checkpoint: &mut [u8],
+    checkpoint_store: &mut dyn CheckpointStore,
+) -> Result<(SigningKey<H>, VerifyingKey<H>), Error> {
+    let private_key = ReferenceImplPrivateKey::generate(parameters, seed)?;
+    let hss_public_key =
+        HssPublicKey::from_with_checkpoint(&private_key, aux_data, checkpoint, checkpoint_store)?;
+
+    keys_from_private_and_public_key(&private_key, &hss_public_key)
+}
 
-    let hss_public_key = HssPublicKey::from(&private_key, aux_data).map_err(|_| Error::new())?;
+/**
+ * Generate [`SigningKey`] and [`VerifyingKey`] from the work units of several machines.
+ *
+ * Every machine computes the roots of a range of subtrees of the top-level tree with
+ * [`hss_keygen_work_unit`](crate::keygen_work_unit). This function merges the subtree roots into
+ * the root of the top-level tree. The keys and the aux data are identical to the ones of
+ * [`hss_keygen`].
+ *
+ * Fails with [`Error::IncompleteWorkUnits`] if `work_units` does not cover all subtrees and with
+ * [`Error::InvalidWorkUnit`] if a work unit is malformed, overlaps another one, belongs to another
+ * key or was computed for another length of the aux data.
+ *
+ * # Arguments
+ *
+ * * `parameters` - Winternitz parameter and tree height of each HSS level, as for [`hss_keygen`].
+ * * `seed` - The seed which will be used to generate the private key.
+ * * `work_units` - The serialized work units of all subtrees.
+ * * `aux_data` - The reference to a slice to auxiliary data.
+ */
+pub fn hss_keygen_combine<H: HashChain>(
+    parameters: &[HssParameter<H>],
+    seed: &Seed<H>,
+    work_units: &[&[u8]],
+    aux_data: Option<&mut &mut [u8]>,
+) -> Result<(SigningKey<H>, VerifyingKey<H>), Error> {
+    let private_key = ReferenceImplPrivateKey::generate(parameters, seed)?;
+    let hss_public_key = HssPublicKey::from_work_units(&private_key, aux_data, work_units)?;
+
+    keys_from_private_and_public_key(&private_key, &hss_public_key)
+}
+
+/**
+ * Generate [`SigningKey`] and [`VerifyingKey`] like [`hss_keygen`], but compute the top-level tree
+ * on multiple threads.
+ *
+ * The top-level tree is split into at least `threads` subtrees, whose roots are computed
+ * concurrently. Every thread fills the auxiliary data of its subtrees. The keys and the auxiliary
+ * data are identical to the ones of [`hss_keygen`].
+ *
+ * # Arguments
+ *
+ * * `parameters` - Winternitz parameter and tree height of each HSS level, as for [`hss_keygen`].
+ * * `seed` - The seed which will be used to generate the private key.
+ * * `aux_data` - The reference to a slice to auxiliary data.
+ * * `threads` - The number of threads used for the top-level tree.
+ */
+#[cfg(feature = "std")]
+pub fn hss_keygen_parallel<H: HashChain>(
+    parameters: &[HssParameter<H>],
+    seed: &Seed<H>,
+    aux_data: Option<&mut &mut [u8]>,
+    threads: usize,
+) -> Result<(SigningKey<H>, VerifyingKey<H>), Error> {
+    let private_key = ReferenceImplPrivateKey::generate(parameters, seed)?;
+    let hss_public_key = HssPublicKey::from_parallel(&private_key, aux_data, threads)?;
+
+    keys_from_private_and_public_key(&private_key, &hss_public_key)
+}
+
+fn keygen_from_private_key<H: HashChain>(
+    private_key: &ReferenceImplPrivateKey<H>,
+    aux_data: Option<&mut &mut [u8]>,
+) -> Result<(SigningKey<H>, VerifyingKey<H>), Error> {
+    let hss_public_key = HssPublicKey::from(private_key, aux_data)?;
 
+    keys_from_private_and_public_key(private_key, &hss_public_key)
+}
+
+fn keys_from_private_and_public_key<H: HashChain>(
+    private_key: &ReferenceImplPrivateKey<H>,
+    hss_public_key: &HssPublicKey<H>,
+) -> Result<(SigningKey<H>, VerifyingKey<H>), Error> {
     let signing_key = SigningKey::from_bytes(&private_key.to_binary_representation())?;
     let verifying_key = VerifyingKey::from_bytes(&hss_public_key.to_binary_representation())?;
     Ok((signing_key, verifying_key))
@@ -292,7 +757,10 @@ pub fn hss_keygen<H: HashChain>(
 mod tests {
     use crate::util::helper::test_helper::gen_random_seed;
     use crate::{
-        constants::{HSS_COMPRESSED_USED_LEAFS_SIZE, MAX_HASH_SIZE},
+        constants::{
+            HSS_COMPRESSED_USED_LEAFS_SIZE, MAX_HASH_SIZE, MAX_HSS_SIGNATURE_LENGTH,
+            REF_IMPL_MAX_PRIVATE_KEY_SIZE,
+        },
         hasher::{
             sha256::{Sha256_128, Sha256_192, Sha256_256},
             shake256::{Shake256_128, Shake256_192, Shake256_256},
@@ -302,6 +770,8 @@ mod tests {
     };
 
     use super::*;
+    use crate::KeygenProgress;
+    use core::ops::ControlFlow;
 
     #[test]
     fn update_keypair() {
@@ -320,18 +790,8 @@ mod tests {
 
         let signing_key_const = signing_key.clone();
 
-        let mut update_private_key = |new_key: &[u8]| {
-            signing_key.as_mut_slice().copy_from_slice(new_key);
-            Ok(())
-        };
-
-        let signature = hss_sign::<H>(
-            &message,
-            signing_key_const.as_slice(),
-            &mut update_private_key,
-            None,
-        )
-        .expect("Signing should complete without error.");
+        let signature = hss_sign::<H>(&message, &mut signing_key, None)
+            .expect("Signing should complete without error.");
 
         assert!(hss_verify::<H>(&message, signature.as_ref(), verifying_key.as_slice()).is_ok());
 
@@ -373,20 +833,8 @@ mod tests {
                 index
             );
 
-            let signing_key_const = signing_key.clone();
-
-            let mut update_private_key = |new_key: &[u8]| {
-                signing_key.as_mut_slice().copy_from_slice(new_key);
-                Ok(())
-            };
-
-            let signature = hss_sign::<H>(
-                &message,
-                signing_key_const.as_slice(),
-                &mut update_private_key,
-                None,
-            )
-            .expect("Signing should complete without error.");
+            let signature = hss_sign::<H>(&message, &mut signing_key, None)
+                .expect("Signing should complete without error.");
 
             assert!(
                 hss_verify::<H>(&message, signature.as_ref(), verifying_key.as_slice()).is_ok()
@@ -416,27 +864,16 @@ mod tests {
         let keypair_lifetime = signing_key.get_lifetime().unwrap();
 
         for index in 0..(1u64 + keypair_lifetime) {
-            let signing_key_const = signing_key.clone();
-
-            let mut update_private_key = |new_key: &[u8]| {
-                signing_key.as_mut_slice().copy_from_slice(new_key);
-                Ok(())
-            };
-
-            let signature = hss_sign::<H>(
-                &message,
-                signing_key_const.as_slice(),
-                &mut update_private_key,
-                None,
-            )
-            .unwrap_or_else(|_| {
-                if index < keypair_lifetime {
-                    panic!("Signing should complete without error.");
-                } else {
-                    assert!(signing_key.get_lifetime().is_err());
-                    panic!("Signing should panic!");
-                }
-            });
+            let signature =
+                hss_sign::<H>(&message, &mut signing_key, None).unwrap_or_else(|error| {
+                    if index < keypair_lifetime {
+                        panic!("Signing should complete without error.");
+                    } else {
+                        assert_eq!(error, Error::KeyExhausted);
+                        assert_eq!(signing_key.get_lifetime(), Err(Error::KeyExhausted));
+                        panic!("Signing should panic!");
+                    }
+                });
 
             assert!(
                 hss_verify::<H>(&message, signature.as_ref(), verifying_key.as_slice()).is_ok()
@@ -444,6 +881,150 @@ mod tests {
         }
     }
 
+    #[test]
+    fn verification_errors() {
+        let message = [
+            32u8, 48, 2, 1, 48, 58, 20, 57, 9, 83, 99, 255, 0, 34, 2, 1, 0,
+        ];
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let (mut signing_key, verifying_key) =
+            hss_keygen::<H>(&parameters, &seed, None).expect("Should generate HSS keys");
+        let (_, single_level_verifying_key) =
+            hss_keygen::<H>(&parameters[..1], &seed, None).expect("Should generate HSS keys");
+
+        let signature = hss_sign::<H>(&message, &mut signing_key, None).unwrap();
+        let signature = signature.as_ref();
+        let public_key = verifying_key.as_slice();
+
+        assert_eq!(
+            hss_verify::<H>(&message, &signature[..signature.len() - 1], public_key),
+            Err(Error::MalformedSignature)
+        );
+        assert_eq!(
+            hss_verify::<H>(&message, &[], public_key),
+            Err(Error::MalformedSignature)
+        );
+        assert_eq!(
+            hss_verify::<H>(&message, signature, single_level_verifying_key.as_slice()),
+            Err(Error::LevelMismatch {
+                signature: 2,
+                public_key: 1
+            })
+        );
+
+        // Signature randomizer of the top level signature
+        let mut tampered_signature = ArrayVec::<[u8; MAX_HSS_SIGNATURE_LENGTH]>::new();
+        tampered_signature.extend_from_slice(signature);
+        tampered_signature[12] ^= 1;
+        assert_eq!(
+            hss_verify::<H>(&message, &tampered_signature, public_key),
+            Err(Error::VerificationFailed { level: 0 })
+        );
+
+        // Authentication path of the bottom level signature
+        tampered_signature.copy_from_slice(signature);
+        tampered_signature[signature.len() - 1] ^= 1;
+        assert_eq!(
+            hss_verify::<H>(&message, &tampered_signature, public_key),
+            Err(Error::VerificationFailed { level: 1 })
+        );
+
+        let mut tampered_public_key = verifying_key.clone();
+        tampered_public_key.bytes[4..8].copy_from_slice(&0xffu32.to_be_bytes());
+        assert_eq!(
+            hss_verify::<H>(&message, signature, tampered_public_key.as_slice()),
+            Err(Error::UnsupportedTypecode(0xff))
+        );
+    }
+
+    #[test]
+    fn split_off_keypair() {
+        let message = [
+            32u8, 48, 2, 1, 48, 58, 20, 57, 9, 83, 99, 255, 0, 34, 2, 1, 0,
+        ];
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let (mut signing_key, verifying_key) =
+            hss_keygen::<H>(&parameters, &seed, None).expect("Should generate HSS keys");
+
+        assert!(signing_key.split_off(0).is_err());
+        assert!(signing_key.split_off(16).is_err());
+
+        let mut split_off_key = signing_key.split_off(6).unwrap();
+        assert_eq!(signing_key.get_lifetime().unwrap(), 10);
+        assert_eq!(split_off_key.get_lifetime().unwrap(), 6);
+        assert_eq!(
+            split_off_key.as_slice()[..HSS_COMPRESSED_USED_LEAFS_SIZE],
+            10u64.to_be_bytes()
+        );
+
+        for (key, lifetime) in [(&mut signing_key, 10), (&mut split_off_key, 6)] {
+            for _ in 0..lifetime {
+                let signature = hss_sign::<H>(&message, key, None)
+                    .expect("Signing should complete without error.");
+                assert!(
+                    hss_verify::<H>(&message, signature.as_ref(), verifying_key.as_slice()).is_ok()
+                );
+            }
+            assert!(hss_sign::<H>(&message, key, None).is_err());
+            assert!(key.get_lifetime().is_err());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn partition_keypair() {
+        let message = [
+            32u8, 48, 2, 1, 48, 58, 20, 57, 9, 83, 99, 255, 0, 34, 2, 1, 0,
+        ];
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let (signing_key, verifying_key) =
+            hss_keygen::<H>(&parameters, &seed, None).expect("Should generate HSS keys");
+
+        assert!(signing_key.clone().partition(0).is_err());
+        assert!(signing_key.clone().partition(17).is_err());
+
+        let mut signing_keys = signing_key.partition(3).unwrap();
+        let lifetimes: Vec<u64> = signing_keys
+            .iter()
+            .map(|key| key.get_lifetime().unwrap())
+            .collect();
+        assert_eq!(lifetimes, [6, 5, 5]);
+
+        let mut used_leafs = Vec::new();
+        for key in signing_keys.iter_mut() {
+            while key.get_lifetime().is_ok() {
+                used_leafs.push(key.as_slice()[..HSS_COMPRESSED_USED_LEAFS_SIZE].to_vec());
+
+                let signature = hss_sign::<H>(&message, key, None)
+                    .expect("Signing should complete without error.");
+                assert!(
+                    hss_verify::<H>(&message, signature.as_ref(), verifying_key.as_slice()).is_ok()
+                );
+            }
+        }
+
+        let expected_leafs: Vec<Vec<u8>> = (0..16u64).map(|i| i.to_be_bytes().to_vec()).collect();
+        assert_eq!(used_leafs, expected_leafs);
+    }
+
     #[test]
     fn keygen_with_forged_aux_data() {
         type H = Sha256_256;
@@ -468,6 +1049,141 @@ mod tests {
         assert_eq!(vk1, vk2);
     }
 
+    #[test]
+    fn sign_with_forged_aux_data() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let mut aux_data = [0u8; 1_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+
+        let (mut signing_key, verifying_key) =
+            hss_keygen::<H>(&parameters, &seed, Some(aux_slice)).expect("Should generate HSS keys");
+
+        aux_slice[2 * MAX_HASH_SIZE - 1] ^= 0x1;
+        let mut forged_aux_data = [0u8; 1_000];
+        forged_aux_data[..aux_slice.len()].copy_from_slice(aux_slice);
+
+        // The forged aux data is not used, the tree is computed instead
+        let signature = hss_sign::<H>(b"message", &mut signing_key, Some(aux_slice)).unwrap();
+        assert!(hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok());
+        assert_eq!(&aux_slice[..], &forged_aux_data[..aux_slice.len()]);
+    }
+
+    #[test]
+    fn keygen_with_progress() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let mut aux_data = [0u8; 1_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+
+        let mut reports = 0;
+        let mut last_progress = None;
+        let mut observer = |progress: &KeygenProgress| {
+            reports += 1;
+            last_progress = Some(*progress);
+            ControlFlow::Continue(())
+        };
+        let (signing_key, verifying_key) =
+            hss_keygen_with_observer::<H>(&parameters, &seed, Some(aux_slice), &mut observer)
+                .unwrap();
+
+        assert_eq!(reports, 32);
+        assert_eq!(
+            last_progress,
+            Some(KeygenProgress {
+                level: 0,
+                leafs_processed: 32,
+                leafs_total: 32,
+            })
+        );
+        assert_eq!(
+            hss_keygen::<H>(&parameters, &seed, None),
+            Ok((signing_key.clone(), verifying_key.clone()))
+        );
+
+        // Nodes taken from the aux data count for all leafs below them
+        let mut leafs_processed = 0;
+        let mut observer = |progress: &KeygenProgress| {
+            leafs_processed = progress.leafs_processed;
+            ControlFlow::Continue(())
+        };
+        assert_eq!(
+            hss_keygen_with_observer::<H>(&parameters, &seed, Some(aux_slice), &mut observer),
+            Ok((signing_key, verifying_key))
+        );
+        assert_eq!(leafs_processed, 32);
+    }
+
+    #[test]
+    fn cancel_keygen() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+        let parameters = [HssParameter::new(lmots, lms)];
+
+        let mut aux_data = [0u8; 1_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+
+        let mut observer = |progress: &KeygenProgress| {
+            if progress.leafs_processed < 10 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        };
+        assert_eq!(
+            hss_keygen_with_observer::<H>(&parameters, &seed, Some(aux_slice), &mut observer),
+            Err(Error::Cancelled)
+        );
+        assert!(!aux::hss_is_aux_data_used(aux_slice));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn keygen_parallel() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let mut aux_data = [0u8; 10_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+        let (signing_key, verifying_key) =
+            hss_keygen::<H>(&parameters, &seed, Some(aux_slice)).expect("Should generate HSS keys");
+        let aux_data = aux_slice.to_vec();
+
+        for threads in [0, 1, 3, 4, 64] {
+            let mut parallel_aux_data = [0u8; 10_000];
+            let parallel_aux_slice: &mut &mut [u8] = &mut &mut parallel_aux_data[..];
+            let (parallel_signing_key, parallel_verifying_key) =
+                hss_keygen_parallel::<H>(&parameters, &seed, Some(parallel_aux_slice), threads)
+                    .expect("Should generate HSS keys");
+
+            assert_eq!(parallel_signing_key, signing_key);
+            assert_eq!(parallel_verifying_key, verifying_key);
+            assert_eq!(parallel_aux_slice.to_vec(), aux_data);
+
+            let (_, parallel_verifying_key) =
+                hss_keygen_parallel::<H>(&parameters, &seed, None, threads)
+                    .expect("Should generate HSS keys");
+            assert_eq!(parallel_verifying_key, verifying_key);
+        }
+    }
+
     #[test]
     fn test_signing_sha256_128() {
         test_signing_core_sha_x::<Sha256_128>();
@@ -523,20 +1239,8 @@ mod tests {
         let mut message = [0u8; 64];
         message[..message_values.len()].copy_from_slice(&message_values);
 
-        let signing_key_const = signing_key.clone();
-
-        let mut update_private_key = |new_key: &[u8]| {
-            signing_key.as_mut_slice().copy_from_slice(new_key);
-            Ok(())
-        };
-
-        let signature = hss_sign::<H>(
-            &message,
-            signing_key_const.as_slice(),
-            &mut update_private_key,
-            aux_data.as_mut(),
-        )
-        .expect("Signing should complete without error.");
+        let signature = hss_sign::<H>(&message, &mut signing_key, aux_data.as_mut())
+            .expect("Signing should complete without error.");
 
         assert!(hss_verify::<H>(&message, signature.as_ref(), verifying_key.as_slice(),).is_ok());
 
@@ -568,20 +1272,8 @@ mod tests {
         let mut message = [0u8; 64];
         message[..message_values.len()].copy_from_slice(&message_values);
 
-        let signing_key_const = signing_key.clone();
-
-        let mut update_private_key = |new_key: &[u8]| {
-            signing_key.as_mut_slice().copy_from_slice(new_key);
-            Ok(())
-        };
-
-        let signature = hss_sign_mut::<H>(
-            &mut message,
-            signing_key_const.as_slice(),
-            &mut update_private_key,
-            None,
-        )
-        .expect("Signing should complete without error.");
+        let signature = hss_sign_mut::<H>(&mut message, &mut signing_key, None)
+            .expect("Signing should complete without error.");
 
         assert!(H::OUTPUT_SIZE == MAX_HASH_SIZE as u16);
         assert_ne!(
@@ -0,0 +1,407 @@
+use tinyvec::ArrayVec;
+
+use crate::{
+    constants::{Node, MAX_TREE_HEIGHT},
+    hasher::HashChain,
+    hss::{
+        aux::MutableExpandedAuxData,
+        definitions::HssPrivateKey,
+        reference_impl_private_key::{
+            generate_child_seed_and_lms_tree_identifier, generate_child_signature_randomizer,
+            SeedAndLmsTreeIdentifier,
+        },
+    },
+    lm_ots::signing::LmotsSignature,
+    lms::{
+        definitions::{LmsPrivateKey, LmsPublicKey},
+        helper::get_authentication_path,
+        signing::LmsSignature,
+        traversal::{MerkleTraversal, TreeHash},
+    },
+    Error,
+};
+
+/**
+ * Pregenerated child tree with the signature of its public key by the parent tree.
+ */
+pub(crate) struct PregeneratedTree<H: HashChain> {
+    pub private_key: LmsPrivateKey<H>,
+    pub public_key: LmsPublicKey<H>,
+    pub signature: LmsSignature<H>,
+    pub tree_traversal: Option<MerkleTraversal<H>>,
+}
+
+/**
+ * Computation of the child tree of the next leaf of `parent`. The authentication path of that
+ * leaf is either computed alongside, or taken from the aux data once the child tree is done.
+ */
+struct Job<H: HashChain> {
+    parent: LmsPrivateKey<H>,
+    child_seed: SeedAndLmsTreeIdentifier<H>,
+    rfc_derivation: bool,
+    child_private_key: LmsPrivateKey<H>,
+    child_tree: TreeHash,
+    child_tree_traversal: Option<MerkleTraversal<H>>,
+    parent_tree: Option<TreeHash>,
+    parent_authentication_path: ArrayVec<[Node; MAX_TREE_HEIGHT]>,
+}
+
+impl<H: HashChain> Job<H> {
+    fn new(
+        parent: &LmsPrivateKey<H>,
+        bottom: &LmsPrivateKey<H>,
+        rfc_derivation: bool,
+        use_aux_data: bool,
+        with_tree_traversal: bool,
+    ) -> Self {
+        let parent = parent.clone();
+        let child_seed = generate_child_seed_and_lms_tree_identifier::<H>(
+            &SeedAndLmsTreeIdentifier::new(&parent.seed, &parent.lms_tree_identifier),
+            &parent.used_leafs_index,
+        );
+        // The child tree uses the parameters of the current bottom tree
+        let child_private_key = LmsPrivateKey::new(
+            child_seed.seed.clone(),
+            child_seed.lms_tree_identifier,
+            0,
+            bottom.lmots_parameter,
+            bottom.lms_parameter,
+        );
+        let child_tree = TreeHash::new(bottom.lms_parameter.get_tree_height() as usize, 0);
+        let child_tree_traversal = if with_tree_traversal {
+            Some(MerkleTraversal::empty(&child_private_key))
+        } else {
+            None
+        };
+
+        let parent_tree_height = parent.lms_parameter.get_tree_height() as usize;
+        let parent_tree = if use_aux_data {
+            None
+        } else {
+            Some(TreeHash::new(parent_tree_height, 0))
+        };
+        let mut parent_authentication_path = ArrayVec::new();
+        parent_authentication_path.resize(parent_tree_height, Node::default());
+
+        Self {
+            parent,
+            child_seed,
+            rfc_derivation,
+            child_private_key,
+            child_tree,
+            child_tree_traversal,
+            parent_tree,
+            parent_authentication_path,
+        }
+    }
+
+    fn remaining_leafs(&self) -> usize {
+        self.child_tree.remaining_leafs()
+            + self
+                .parent_tree
+                .as_ref()
+                .map_or(0, |parent_tree| parent_tree.remaining_leafs())
+    }
+
+    fn step(&mut self) {
+        if self.child_tree.remaining_leafs() > 0 {
+            let child_tree_traversal = &mut self.child_tree_traversal;
+            self.child_tree
+                .step(&self.child_private_key, &mut |height, index, node| {
+                    if let Some(child_tree_traversal) = child_tree_traversal.as_mut() {
+                        child_tree_traversal.setup_node(height, index, node);
+                    }
+                });
+        } else if let Some(parent_tree) = self.parent_tree.as_mut() {
+            let leaf_index = self.parent.lms_parameter.number_of_lm_ots_keys()
+                + self.parent.used_leafs_index as usize;
+            let authentication_path = &mut self.parent_authentication_path;
+            parent_tree.step(&self.parent, &mut |height, index, node| {
+                if height < authentication_path.len() && (leaf_index >> height) ^ 1 == index {
+                    authentication_path[height] = *node;
+                }
+            });
+        }
+    }
+
+    fn run(&mut self) {
+        while self.remaining_leafs() > 0 {
+            self.step();
+        }
+    }
+
+    fn finish(
+        mut self,
+        aux_data: &mut Option<MutableExpandedAuxData>,
+    ) -> Result<PregeneratedTree<H>, Error> {
+        self.run();
+
+        let private_key = core::mem::take(&mut self.child_private_key);
+        let public_key = LmsPublicKey {
+            key: self.child_tree.node().unwrap(),
+            lms_tree_identifier: private_key.lms_tree_identifier,
+            lmots_parameter: private_key.lmots_parameter,
+            lms_parameter: private_key.lms_parameter,
+        };
+
+        let parent_leaf = self.parent.used_leafs_index;
+        let authentication_path = match self.parent_tree {
+            Some(_) => self.parent_authentication_path,
+            None => get_authentication_path(parent_leaf, &self.parent, aux_data),
+        };
+
+        let lm_ots_private_key = self.parent.use_lmots_private_key()?;
+        let signature_randomizer = generate_child_signature_randomizer::<H>(
+            &SeedAndLmsTreeIdentifier::new(&self.parent.seed, &self.parent.lms_tree_identifier),
+            &self.child_seed,
+            &parent_leaf,
+            self.rfc_derivation,
+        );
+        let signature = LmsSignature {
+            lms_leaf_identifier: lm_ots_private_key.lms_leaf_identifier,
+            lmots_signature: LmotsSignature::sign(
+                &lm_ots_private_key,
+                &signature_randomizer,
+                public_key.to_binary_representation().as_slice(),
+            ),
+            authentication_path,
+            lms_parameter: self.parent.lms_parameter,
+        };
+
+        Ok(PregeneratedTree {
+            private_key,
+            public_key,
+            signature,
+            tree_traversal: self.child_tree_traversal,
+        })
+    }
+}
+
+// Without `std`, the tree can not be boxed
+#[allow(clippy::large_enum_variant)]
+enum State<H: HashChain> {
+    Idle,
+    #[cfg(not(feature = "std"))]
+    Running(Job<H>),
+    #[cfg(feature = "std")]
+    Background(std::thread::JoinHandle<Job<H>>),
+    Ready(PregeneratedTree<H>),
+}
+
+/**
+ * Pregeneration of the bottom tree that follows the current one.
+ *
+ * Once the bottom tree of a multi-level key is exhausted, the signature that rolls it over has to
+ * generate the next bottom tree and sign its public key with the parent tree. [`NextBottomTree`]
+ * does this work ahead of time, so that the rollover only swaps in the finished tree.
+ *
+ * With `std`, the tree is computed on a background thread. Without `std`, every signature
+ * computes a share of the leafs, such that the tree is complete before the current bottom tree is
+ * exhausted. The pregenerated tree is identical to the one generated at the rollover, thus the
+ * signatures do not change.
+ */
+pub(crate) struct NextBottomTree<H: HashChain> {
+    /// Parent tree, whose current leaf signs the pregenerated tree.
+    parent: LmsPrivateKey<H>,
+    state: State<H>,
+    /// Traversal of the last taken tree.
+    tree_traversal: Option<MerkleTraversal<H>>,
+}
+
+impl<H: HashChain> Default for NextBottomTree<H> {
+    fn default() -> Self {
+        Self {
+            parent: LmsPrivateKey::default(),
+            state: State::Idle,
+            tree_traversal: None,
+        }
+    }
+}
+
+impl<H: HashChain> NextBottomTree<H> {
+    /**
+     * Takes the pregenerated child tree of the current leaf of `parent`. Completes the
+     * pregeneration first, if necessary. Returns `None` if another tree was pregenerated.
+     */
+    pub fn take(
+        &mut self,
+        parent: &LmsPrivateKey<H>,
+    ) -> Result<Option<PregeneratedTree<H>>, Error> {
+        let is_next_of_parent = self.is_next_of(parent);
+        let mut tree = match core::mem::replace(&mut self.state, State::Idle) {
+            _ if !is_next_of_parent => return Ok(None),
+            State::Idle => return Ok(None),
+            #[cfg(not(feature = "std"))]
+            State::Running(job) => job.finish(&mut None)?,
+            #[cfg(feature = "std")]
+            State::Background(handle) => join(handle).finish(&mut None)?,
+            State::Ready(tree) => tree,
+        };
+        self.tree_traversal = tree.tree_traversal.take();
+
+        Ok(Some(tree))
+    }
+
+    /**
+     * Takes the traversal of the last taken tree, if it was pregenerated.
+     */
+    pub fn take_tree_traversal(&mut self) -> Option<MerkleTraversal<H>> {
+        self.tree_traversal.take()
+    }
+
+    fn is_next_of(&self, parent: &LmsPrivateKey<H>) -> bool {
+        !matches!(self.state, State::Idle)
+            && self.parent.seed == parent.seed
+            && self.parent.lms_tree_identifier == parent.lms_tree_identifier
+            && self.parent.used_leafs_index == parent.used_leafs_index
+    }
+
+    /**
+     * Continues the pregeneration of the tree that follows the bottom tree of `private_key`.
+     * Restarts the pregeneration if the key moved on to another tree in the meantime.
+     *
+     * `aux_data` of the parent tree is used to compute the authentication path.
+     * With `with_tree_traversal`, the traversal of the pregenerated tree is set up as well.
+     */
+    pub fn advance(
+        &mut self,
+        private_key: &HssPrivateKey<H>,
+        aux_data: &mut Option<MutableExpandedAuxData>,
+        with_tree_traversal: bool,
+    ) -> Result<(), Error> {
+        let levels = private_key.get_length();
+        if levels < 2 {
+            self.state = State::Idle;
+            return Ok(());
+        }
+
+        let parent = &private_key.private_key[levels - 2];
+        let bottom = &private_key.private_key[levels - 1];
+        // The parent tree is exhausted as well, which is left to the regular rollover
+        if parent.used_leafs_index as usize >= parent.lms_parameter.number_of_lm_ots_keys() {
+            self.state = State::Idle;
+            return Ok(());
+        }
+
+        if !self.is_next_of(parent) {
+            self.parent = parent.clone();
+            let job = Job::new(
+                parent,
+                bottom,
+                private_key.rfc_derivation,
+                aux_data.is_some(),
+                with_tree_traversal,
+            );
+            #[cfg(feature = "std")]
+            let state = State::Background(std::thread::spawn(move || {
+                let mut job = job;
+                job.run();
+                job
+            }));
+            #[cfg(not(feature = "std"))]
+            let state = State::Running(job);
+            self.state = state;
+        }
+
+        self.state = match core::mem::replace(&mut self.state, State::Idle) {
+            #[cfg(not(feature = "std"))]
+            State::Running(mut job) => {
+                // Remaining signatures of the bottom tree, including the current one
+                let signatures =
+                    bottom.lms_parameter.number_of_lm_ots_keys() - bottom.used_leafs_index as usize;
+                let signatures = signatures.max(1);
+                let steps = (job.remaining_leafs() + signatures - 1) / signatures;
+                for _ in 0..steps {
+                    job.step();
+                }
+
+                if job.remaining_leafs() == 0 {
+                    State::Ready(job.finish(aux_data)?)
+                } else {
+                    State::Running(job)
+                }
+            }
+            #[cfg(feature = "std")]
+            State::Background(handle) if handle.is_finished() => {
+                State::Ready(join(handle).finish(aux_data)?)
+            }
+            state => state,
+        };
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+fn join<H: HashChain>(handle: std::thread::JoinHandle<Job<H>>) -> Job<H> {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hss::{aux_data::AuxData, hss_keygen, hss_sign, hss_sign_core, hss_verify},
+        util::helper::test_helper::gen_random_seed,
+        HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+    };
+
+    type H = Sha256_256;
+
+    #[test]
+    fn rollover_uses_pregenerated_tree() {
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let parameters = [
+            HssParameter::new(lmots, LmsAlgorithm::LmsH5),
+            HssParameter::new(lmots, LmsAlgorithm::LmsH2),
+        ];
+
+        let mut aux_data = [0u8; 1_000];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+        let (mut signing_key, verifying_key) =
+            hss_keygen::<H>(&parameters, &seed, Some(aux_slice)).unwrap();
+        let mut stateless_signing_key = signing_key.clone();
+        let mut aux_data = AuxData::<H>::new(core::mem::take(aux_slice));
+
+        let mut private_key = HssPrivateKey::default();
+        let mut next_bottom_tree = NextBottomTree::default();
+        for i in 0..10 {
+            let signature = hss_sign_core::<H>(
+                Some(b"message"),
+                None,
+                &mut signing_key,
+                Some(&mut aux_data),
+                &mut private_key,
+                Some(&mut next_bottom_tree),
+                None,
+            )
+            .unwrap();
+            let stateless_signature = hss_sign::<H>(
+                b"message",
+                &mut stateless_signing_key,
+                Some(aux_data.as_mut_slice()),
+            )
+            .unwrap();
+
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+            assert_eq!(signature.as_ref(), stateless_signature.as_ref());
+
+            // After the last signature of the bottom tree, the next one is ready or in progress
+            if i % 4 == 3 {
+                #[cfg(not(feature = "std"))]
+                assert!(matches!(next_bottom_tree.state, State::Ready(_)));
+                #[cfg(feature = "std")]
+                assert!(matches!(
+                    next_bottom_tree.state,
+                    State::Background(_) | State::Ready(_)
+                ));
+                assert!(next_bottom_tree.is_next_of(&private_key.private_key[0]));
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use core::ops::ControlFlow;
+
+use crate::{constants::Node, Error};
+
+/**
+ * Progress of the computation of an LMS tree during key generation.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeygenProgress {
+    /// HSS level of the tree, starting with `0` at the top.
+    pub level: usize,
+    /// Number of leafs whose LM-OTS public key has been computed or was taken from the auxiliary
+    /// data.
+    pub leafs_processed: u64,
+    /// Number of leafs of the tree.
+    pub leafs_total: u64,
+}
+
+/**
+ * Observer of the key generation, see [`crate::keygen_with_observer`].
+ *
+ * The observer is notified after every processed leaf. Returning [`ControlFlow::Break`] cancels
+ * the key generation, which then fails with [`Error::Cancelled`].
+ */
+pub trait KeygenObserver {
+    fn on_progress(&mut self, progress: &KeygenProgress) -> ControlFlow<()>;
+}
+
+impl<F: FnMut(&KeygenProgress) -> ControlFlow<()>> KeygenObserver for F {
+    fn on_progress(&mut self, progress: &KeygenProgress) -> ControlFlow<()> {
+        self(progress)
+    }
+}
+
+/**
+ * Nodes of a tree that are already known, e.g. from a checkpoint or computed by another machine.
+ */
+pub(crate) trait NodeCache {
+    /**
+     * Returns the node at `index`, if it is known.
+     */
+    fn get_node(&self, index: usize) -> Option<Node>;
+
+    /**
+     * Called for every computed node.
+     */
+    fn complete_node(&mut self, index: usize, node: &[u8]) -> Result<(), Error>;
+}
+
+pub(crate) struct KeygenProgressTracker<'a> {
+    observer: Option<&'a mut dyn KeygenObserver>,
+    node_cache: Option<&'a mut dyn NodeCache>,
+    progress: KeygenProgress,
+}
+
+impl<'a> KeygenProgressTracker<'a> {
+    pub fn new(
+        observer: Option<&'a mut dyn KeygenObserver>,
+        node_cache: Option<&'a mut dyn NodeCache>,
+        level: usize,
+        leafs_total: u64,
+    ) -> Self {
+        Self {
+            observer,
+            node_cache,
+            progress: KeygenProgress {
+                level,
+                leafs_processed: 0,
+                leafs_total,
+            },
+        }
+    }
+
+    pub fn advance(&mut self, leafs: u64) -> Result<(), Error> {
+        self.progress.leafs_processed += leafs;
+
+        if let Some(observer) = self.observer.as_mut() {
+            if observer.on_progress(&self.progress).is_break() {
+                return Err(Error::Cancelled);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_cached_node(&self, index: usize) -> Option<Node> {
+        self.node_cache.as_ref()?.get_node(index)
+    }
+
+    pub fn complete_node(&mut self, index: usize, node: &[u8]) -> Result<(), Error> {
+        match self.node_cache.as_mut() {
+            Some(node_cache) => node_cache.complete_node(index, node),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,16 +1,23 @@
 use crate::{
     constants::{
-        LmsTreeIdentifier, D_TOPSEED, HSS_COMPRESSED_USED_LEAFS_SIZE, ILEN, MAX_ALLOWED_HSS_LEVELS,
-        MAX_HASH_SIZE, MAX_SEED_LEN, REF_IMPL_MAX_PRIVATE_KEY_SIZE, SEED_CHILD_SEED,
-        SEED_SIGNATURE_RANDOMIZER_SEED, TOPSEED_D, TOPSEED_LEN, TOPSEED_SEED, TOPSEED_WHICH,
+        LmsTreeIdentifier, D_STATE_TAG, D_TOPSEED, HSS_COMPRESSED_USED_LEAFS_SIZE, ILEN,
+        MAX_ALLOWED_HSS_LEVELS, MAX_HASH_SIZE, MAX_PRIVATE_KEY_SIZE, MAX_SEED_LEN,
+        PRIVATE_KEY_EXTENSION_HEADER_SIZE, PRIVATE_KEY_LEAF_LIMIT_SIZE,
+        REF_IMPL_MAX_PRIVATE_KEY_SIZE, SEED_CHILD_SEED, SEED_SIGNATURE_RANDOMIZER_SEED, TOPSEED_D,
+        TOPSEED_LEN, TOPSEED_SEED, TOPSEED_WHICH,
     },
     hasher::HashChain,
-    hss::{definitions::HssPrivateKey, seed_derive::SeedDerive},
+    hss::{
+        aux::{compute_hmac, compute_seed_derive},
+        definitions::HssPrivateKey,
+        seed_derive::SeedDerive,
+    },
     util::{helper::read_and_advance, ArrayVecZeroize},
-    HssParameter, LmotsAlgorithm, LmsAlgorithm,
+    Error, HssParameter, LmotsAlgorithm, LmsAlgorithm,
 };
 
 use core::{convert::TryFrom, convert::TryInto, marker::PhantomData};
+use subtle::ConstantTimeEq;
 use tinyvec::ArrayVec;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
@@ -87,6 +94,13 @@ pub struct ReferenceImplPrivateKey<H: HashChain> {
     pub compressed_used_leafs_indexes: CompressedUsedLeafsIndexes,
     pub compressed_parameter: CompressedParameterSet,
     pub seed: Seed<H>,
+    /// Top-level `I` chosen at key generation. If set, `seed` is the top-level SEED itself
+    /// instead of the input of the TOPSEED derivation of the reference implementation.
+    pub lms_tree_identifier: Option<LmsTreeIdentifier>,
+    /// Derive the signature randomizers of the child public keys as in RFC 8554 Appendix A,
+    /// instead of as in the reference implementation.
+    pub rfc_derivation: bool,
+    pub state_tag: bool,
 }
 
 impl<H: HashChain> ReferenceImplPrivateKey<H> {
@@ -94,31 +108,91 @@ impl<H: HashChain> ReferenceImplPrivateKey<H> {
         self.seed = Seed::default();
         self.compressed_parameter = CompressedParameterSet::default();
         self.compressed_used_leafs_indexes = CompressedUsedLeafsIndexes::new(0);
+        self.lms_tree_identifier = None;
+        self.rfc_derivation = false;
+        self.state_tag = false;
     }
 
-    pub fn generate(parameters: &[HssParameter<H>], seed: &Seed<H>) -> Result<Self, ()> {
+    pub fn generate(parameters: &[HssParameter<H>], seed: &Seed<H>) -> Result<Self, Error> {
         let private_key: ReferenceImplPrivateKey<H> = ReferenceImplPrivateKey {
             compressed_used_leafs_indexes: CompressedUsedLeafsIndexes::new(0),
             compressed_parameter: CompressedParameterSet::from(parameters)?,
             seed: seed.clone(),
+            lms_tree_identifier: None,
+            rfc_derivation: false,
+            state_tag: false,
         };
 
         Ok(private_key)
     }
 
-    pub fn to_binary_representation(&self) -> ArrayVec<[u8; REF_IMPL_MAX_PRIVATE_KEY_SIZE]> {
+    /**
+     * Generates a private key with explicitly chosen top-level `I` and SEED, as used by the test
+     * cases of RFC 8554 and by other implementations. The keys of all levels are derived as in
+     * RFC 8554 Appendix A, such that the signatures match those of the RFC test cases.
+     */
+    pub fn generate_with_lms_tree_identifier(
+        parameters: &[HssParameter<H>],
+        seed: &Seed<H>,
+        lms_tree_identifier: &LmsTreeIdentifier,
+    ) -> Result<Self, Error> {
+        let mut private_key = Self::generate(parameters, seed)?;
+        private_key.lms_tree_identifier = Some(*lms_tree_identifier);
+        private_key.rfc_derivation = true;
+
+        Ok(private_key)
+    }
+
+    /**
+     * The private key format of the reference implementation, optionally followed by extensions.
+     * Every extension is encoded as `tag || length || value`, with `tag` and `length` being a
+     * single byte each. Keys without extensions are binary compatible with the reference
+     * implementation.
+     *
+     * Keys generated from an explicit top-level `I` store it in an extension, which also marks
+     * the seed as top-level SEED, such that signing uses the same derivation as key generation.
+     * Keys that derive the signature randomizers of the child public keys as in RFC 8554
+     * Appendix A are marked by an extension without value.
+     *
+     * The optional state tag is always the last extension. It authenticates all preceding bytes
+     * with an HMAC keyed from the seed, such that modifications of the leaf counter are detected.
+     */
+    pub fn to_binary_representation(&self) -> ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]> {
         let mut result = ArrayVec::new();
 
         result.extend_from_slice(&self.compressed_used_leafs_indexes.count.to_be_bytes());
         result.extend_from_slice(&self.compressed_parameter.0);
         result.extend_from_slice(self.seed.as_slice());
 
+        if self.rfc_derivation {
+            result.extend_from_slice(&[PRIVATE_KEY_EXTENSION_RFC_DERIVATION, 0]);
+        }
+
+        if let Some(lms_tree_identifier) = &self.lms_tree_identifier {
+            result.extend_from_slice(&[PRIVATE_KEY_EXTENSION_LMS_TREE_IDENTIFIER, ILEN as u8]);
+            result.extend_from_slice(lms_tree_identifier);
+        }
+
+        if let Some(limit) = self.compressed_used_leafs_indexes.limit {
+            result.extend_from_slice(&[
+                PRIVATE_KEY_EXTENSION_LEAF_LIMIT,
+                PRIVATE_KEY_LEAF_LIMIT_SIZE as u8,
+            ]);
+            result.extend_from_slice(&limit.to_be_bytes());
+        }
+
+        if self.state_tag {
+            let state_tag = compute_state_tag(&self.seed, &result);
+            result.extend_from_slice(&[PRIVATE_KEY_EXTENSION_STATE_TAG, state_tag.len() as u8]);
+            result.extend_from_slice(&state_tag);
+        }
+
         result
     }
 
-    pub fn from_binary_representation(data: &[u8]) -> Result<Self, ()> {
-        if data.len() != REF_IMPL_MAX_PRIVATE_KEY_SIZE - MAX_SEED_LEN + H::OUTPUT_SIZE as usize {
-            return Err(());
+    pub fn from_binary_representation(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < REF_IMPL_MAX_PRIVATE_KEY_SIZE - MAX_SEED_LEN + H::OUTPUT_SIZE as usize {
+            return Err(Error::MalformedPrivateKey);
         }
 
         let mut result = Self::default();
@@ -138,10 +212,62 @@ impl<H: HashChain> ReferenceImplPrivateKey<H> {
             .as_mut_slice()
             .copy_from_slice(read_and_advance(data, seed_len, &mut index));
 
+        while index < data.len() {
+            if data.len() - index < PRIVATE_KEY_EXTENSION_HEADER_SIZE {
+                return Err(Error::MalformedPrivateKey);
+            }
+            let extension_start = index;
+            let tag = data[index];
+            let length = data[index + 1] as usize;
+            index += PRIVATE_KEY_EXTENSION_HEADER_SIZE;
+
+            if data.len() - index < length {
+                return Err(Error::MalformedPrivateKey);
+            }
+            let value = read_and_advance(data, length, &mut index);
+
+            // Unknown or duplicated extensions might restrict the usage of the key, thus they
+            // must not be ignored
+            match tag {
+                PRIVATE_KEY_EXTENSION_LEAF_LIMIT
+                    if length == PRIVATE_KEY_LEAF_LIMIT_SIZE
+                        && result.compressed_used_leafs_indexes.limit.is_none() =>
+                {
+                    let limit = u64::from_be_bytes(value.try_into().unwrap());
+                    if limit <= result.compressed_used_leafs_indexes.count {
+                        return Err(Error::MalformedPrivateKey);
+                    }
+                    result.compressed_used_leafs_indexes.limit = Some(limit);
+                }
+                PRIVATE_KEY_EXTENSION_RFC_DERIVATION if length == 0 && !result.rfc_derivation => {
+                    result.rfc_derivation = true;
+                }
+                PRIVATE_KEY_EXTENSION_LMS_TREE_IDENTIFIER
+                    if length == ILEN && result.lms_tree_identifier.is_none() =>
+                {
+                    result.lms_tree_identifier = Some(value.try_into().unwrap());
+                }
+                PRIVATE_KEY_EXTENSION_STATE_TAG
+                    if length == H::OUTPUT_SIZE as usize && index == data.len() =>
+                {
+                    let state_tag = compute_state_tag(&result.seed, &data[..extension_start]);
+                    if !bool::from(state_tag.as_slice().ct_eq(value)) {
+                        return Err(Error::InvalidStateTag);
+                    }
+                    result.state_tag = true;
+                }
+                _ => return Err(Error::MalformedPrivateKey),
+            }
+        }
+
         Ok(result)
     }
 
     pub fn generate_root_seed_and_lms_tree_identifier(&self) -> SeedAndLmsTreeIdentifier<H> {
+        if let Some(lms_tree_identifier) = &self.lms_tree_identifier {
+            return SeedAndLmsTreeIdentifier::new(&self.seed, lms_tree_identifier);
+        }
+
         let mut hash_preimage = [0u8; TOPSEED_LEN];
         let mut hash_postimage =
             ArrayVec::from_array_len([0u8; MAX_HASH_SIZE], H::OUTPUT_SIZE as usize);
@@ -174,6 +300,31 @@ impl<H: HashChain> ReferenceImplPrivateKey<H> {
         SeedAndLmsTreeIdentifier::new(&seed, &lms_tree_identifier)
     }
 
+    pub fn get_max_used_leafs_index(&self) -> Result<u64, Error> {
+        let parameters = self.compressed_parameter.to::<H>()?;
+        let tree_heights = parameters
+            .iter()
+            .map(|parameter| parameter.get_lms_parameter().get_tree_height())
+            .collect();
+
+        Ok(self.compressed_used_leafs_indexes.max_count(&tree_heights))
+    }
+
+    /**
+     * Restricts the key to the leafs in front of `limit`. The leafs starting at `limit` can then
+     * be handed over to another key.
+     */
+    pub fn set_leaf_limit(&mut self, limit: u64) -> Result<(), Error> {
+        if limit <= self.compressed_used_leafs_indexes.count
+            || limit > self.get_max_used_leafs_index()?
+        {
+            return Err(Error::InvalidParameters);
+        }
+
+        self.compressed_used_leafs_indexes.limit = Some(limit);
+        Ok(())
+    }
+
     pub fn increment(&mut self, hss_private_key: &HssPrivateKey<H>) {
         let tree_heights = hss_private_key
             .private_key
@@ -214,7 +365,36 @@ pub fn generate_signature_randomizer<H: HashChain>(
     derive.seed_derive(false)
 }
 
+/**
+ * Signature randomizer, with which the parent tree signs the public key of the child tree. The
+ * reference implementation derives it from the child tree, RFC 8554 Appendix A from the parent
+ * tree, like the randomizers of the message signatures.
+ */
+pub fn generate_child_signature_randomizer<H: HashChain>(
+    parent_seed: &SeedAndLmsTreeIdentifier<H>,
+    child_seed: &SeedAndLmsTreeIdentifier<H>,
+    parent_lms_leaf_identifier: &u32,
+    rfc_derivation: bool,
+) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+    let seed = if rfc_derivation {
+        parent_seed
+    } else {
+        child_seed
+    };
+
+    generate_signature_randomizer(seed, parent_lms_leaf_identifier)
+}
+
 const PARAM_SET_END: u8 = 0xff; // Marker for end of parameter set
+const PRIVATE_KEY_EXTENSION_LEAF_LIMIT: u8 = 0x01; // Exclusive upper bound of the leaf counter
+const PRIVATE_KEY_EXTENSION_STATE_TAG: u8 = 0x02; // HMAC over the preceding private key bytes
+const PRIVATE_KEY_EXTENSION_LMS_TREE_IDENTIFIER: u8 = 0x03; // Explicit top-level I
+const PRIVATE_KEY_EXTENSION_RFC_DERIVATION: u8 = 0x04; // Key derivation of RFC 8554 Appendix A
+
+fn compute_state_tag<H: HashChain>(seed: &Seed<H>, data: &[u8]) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+    let key = compute_seed_derive::<H>(seed.as_slice(), D_STATE_TAG);
+    compute_hmac::<H>(&key, data)
+}
 
 #[derive(Clone, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
 pub struct CompressedParameterSet([u8; MAX_ALLOWED_HSS_LEVELS]);
@@ -226,9 +406,9 @@ impl Default for CompressedParameterSet {
 }
 
 impl CompressedParameterSet {
-    pub fn from_slice(data: &[u8]) -> Result<Self, ()> {
+    pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
         if data.len() != MAX_ALLOWED_HSS_LEVELS {
-            return Err(());
+            return Err(Error::MalformedPrivateKey);
         }
 
         let mut result = CompressedParameterSet::default();
@@ -237,15 +417,21 @@ impl CompressedParameterSet {
         Ok(result)
     }
 
-    pub fn from<H: HashChain>(parameters: &[HssParameter<H>]) -> Result<Self, ()> {
+    pub fn from<H: HashChain>(parameters: &[HssParameter<H>]) -> Result<Self, Error> {
+        if parameters.is_empty() || parameters.len() > MAX_ALLOWED_HSS_LEVELS {
+            return Err(Error::InvalidParameters);
+        }
+
         let mut result = CompressedParameterSet::default();
 
         for (i, parameter) in parameters.iter().enumerate() {
             let lmots = parameter.get_lmots_parameter();
             let lms = parameter.get_lms_parameter();
 
-            let lmots_type = lmots.get_type_id() as u8;
-            let lms_type = lms.get_type_id() as u8;
+            // The reference implementation stores the typecodes of SHA-256/N32 independent of the
+            // hash function
+            let lmots_type = lmots.get_algorithm() as u8;
+            let lms_type = lms.get_algorithm() as u8;
 
             result.0[i] = (lms_type << 4) + lmots_type;
         }
@@ -255,7 +441,7 @@ impl CompressedParameterSet {
 
     pub fn to<H: HashChain>(
         &self,
-    ) -> Result<ArrayVec<[HssParameter<H>; MAX_ALLOWED_HSS_LEVELS]>, ()> {
+    ) -> Result<ArrayVec<[HssParameter<H>; MAX_ALLOWED_HSS_LEVELS]>, Error> {
         let mut result = ArrayVec::new();
 
         for level in 0..MAX_ALLOWED_HSS_LEVELS {
@@ -265,17 +451,25 @@ impl CompressedParameterSet {
                 break;
             }
 
-            let lms_type = parameter >> 4;
-            let lmots_type = parameter & 0x0f;
+            let lms_type = (parameter >> 4) as u32;
+            let lmots_type = (parameter & 0x0f) as u32;
 
-            let lms = LmsAlgorithm::from(lms_type as u32);
-            let lmots = LmotsAlgorithm::from(lmots_type as u32);
+            let lms = LmsAlgorithm::from(lms_type);
+            let lmots = LmotsAlgorithm::from(lmots_type);
+
+            if matches!(lms, LmsAlgorithm::LmsReserved) {
+                return Err(Error::UnsupportedTypecode(lms_type));
+            }
+            if lmots == LmotsAlgorithm::LmotsReserved {
+                return Err(Error::UnsupportedTypecode(lmots_type));
+            }
 
             result.extend_from_slice(&[HssParameter::new(lmots, lms)]);
         }
 
+        // A wiped private key has no parameters left
         if result.is_empty() {
-            return Err(());
+            return Err(Error::KeyExhausted);
         }
 
         Ok(result)
@@ -285,16 +479,41 @@ impl CompressedParameterSet {
 #[derive(Clone, Default, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
 pub struct CompressedUsedLeafsIndexes {
     count: u64,
+    limit: Option<u64>,
 }
 
 impl CompressedUsedLeafsIndexes {
     pub fn new(count: u64) -> Self {
-        CompressedUsedLeafsIndexes { count }
+        CompressedUsedLeafsIndexes { count, limit: None }
+    }
+
+    pub fn with_limit(count: u64, limit: Option<u64>) -> Self {
+        CompressedUsedLeafsIndexes { count, limit }
     }
 
     pub fn from_slice(data: &[u8]) -> Self {
         CompressedUsedLeafsIndexes {
             count: u64::from_be_bytes(data.try_into().unwrap()),
+            limit: None,
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+
+    pub fn max_count(&self, tree_heights: &ArrayVec<[u8; MAX_ALLOWED_HSS_LEVELS]>) -> u64 {
+        let total_tree_height: u32 = tree_heights.iter().map(|&height| height as u32).sum();
+        let total_leafs = 1u64.checked_shl(total_tree_height).unwrap_or(0);
+
+        match (total_leafs, self.limit) {
+            (0, None) => u64::MAX,
+            (0, Some(limit)) => limit - 1,
+            (total_leafs, limit) => total_leafs.min(limit.unwrap_or(u64::MAX)) - 1,
         }
     }
 
@@ -317,11 +536,9 @@ impl CompressedUsedLeafsIndexes {
     pub fn increment(
         &mut self,
         tree_heights: &ArrayVec<[u8; MAX_ALLOWED_HSS_LEVELS]>,
-    ) -> Result<(), ()> {
-        let total_tree_height: u32 = tree_heights.iter().sum::<u8>().into();
-
-        if self.count >= (2u64.pow(total_tree_height) - 1) {
-            return Err(());
+    ) -> Result<(), Error> {
+        if self.count >= self.max_count(tree_heights) {
+            return Err(Error::KeyExhausted);
         }
 
         self.count += 1;
@@ -333,8 +550,9 @@ impl CompressedUsedLeafsIndexes {
 mod tests {
     use super::{CompressedParameterSet, ReferenceImplPrivateKey};
     use crate::{
-        constants::MAX_ALLOWED_HSS_LEVELS, hss::definitions::HssPrivateKey, HssParameter,
-        LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+        constants::{MAX_ALLOWED_HSS_LEVELS, MAX_PRIVATE_KEY_SIZE},
+        hss::definitions::HssPrivateKey,
+        HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256,
     };
 
     use crate::util::helper::test_helper::gen_random_seed;
@@ -435,4 +653,150 @@ mod tests {
 
         assert!(key == deserialized);
     }
+
+    #[test]
+    fn test_binary_representation_leaf_limit() {
+        let parameters = [
+            HssParameter::construct_default_parameters(),
+            HssParameter::construct_default_parameters(),
+        ];
+
+        let seed = gen_random_seed::<Hasher>();
+        let mut key = ReferenceImplPrivateKey::generate(&parameters, &seed).unwrap();
+        let reference_length = key.to_binary_representation().len();
+
+        assert!(key.set_leaf_limit(0).is_err());
+        assert!(key.set_leaf_limit(1 << 10).is_err());
+        key.set_leaf_limit(42).unwrap();
+
+        let binary_representation = key.to_binary_representation();
+        assert_eq!(binary_representation.len(), reference_length + 10);
+
+        let deserialized = ReferenceImplPrivateKey::<Hasher>::from_binary_representation(
+            binary_representation.as_slice(),
+        )
+        .unwrap();
+        assert!(key == deserialized);
+        assert_eq!(deserialized.get_max_used_leafs_index(), Ok(41));
+
+        // Truncated extension
+        assert!(
+            ReferenceImplPrivateKey::<Hasher>::from_binary_representation(
+                &binary_representation[..binary_representation.len() - 1],
+            )
+            .is_err()
+        );
+
+        // Unknown extension
+        let mut unknown_extension = binary_representation;
+        unknown_extension[reference_length] = 0x7f;
+        assert!(
+            ReferenceImplPrivateKey::<Hasher>::from_binary_representation(
+                unknown_extension.as_slice(),
+            )
+            .is_err()
+        );
+
+        // Duplicated extension
+        let mut duplicated_extension = [0u8; 2 * MAX_PRIVATE_KEY_SIZE];
+        let length = binary_representation.len();
+        duplicated_extension[..length].copy_from_slice(&binary_representation);
+        duplicated_extension[length..length + 10]
+            .copy_from_slice(&binary_representation[reference_length..]);
+        assert!(
+            ReferenceImplPrivateKey::<Hasher>::from_binary_representation(
+                &duplicated_extension[..length + 10],
+            )
+            .is_err()
+        );
+
+        // Limit not above the counter
+        let mut exhausted_limit = binary_representation;
+        exhausted_limit[..8].copy_from_slice(&42u64.to_be_bytes());
+        assert!(
+            ReferenceImplPrivateKey::<Hasher>::from_binary_representation(
+                exhausted_limit.as_slice(),
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_binary_representation_lms_tree_identifier() {
+        let parameters = [
+            HssParameter::construct_default_parameters(),
+            HssParameter::construct_default_parameters(),
+        ];
+
+        let seed = gen_random_seed::<Hasher>();
+        let lms_tree_identifier = [0x42u8; 16];
+        let reference_key = ReferenceImplPrivateKey::generate(&parameters, &seed).unwrap();
+        let key = ReferenceImplPrivateKey::generate_with_lms_tree_identifier(
+            &parameters,
+            &seed,
+            &lms_tree_identifier,
+        )
+        .unwrap();
+
+        let binary_representation = key.to_binary_representation();
+        assert_eq!(
+            binary_representation.len(),
+            reference_key.to_binary_representation().len() + 20
+        );
+
+        let deserialized = ReferenceImplPrivateKey::<Hasher>::from_binary_representation(
+            binary_representation.as_slice(),
+        )
+        .unwrap();
+        assert!(key == deserialized);
+        assert!(deserialized.rfc_derivation && !reference_key.rfc_derivation);
+
+        let root_seed = deserialized.generate_root_seed_and_lms_tree_identifier();
+        assert_eq!(root_seed.seed, seed);
+        assert_eq!(root_seed.lms_tree_identifier, lms_tree_identifier);
+        assert_ne!(
+            reference_key
+                .generate_root_seed_and_lms_tree_identifier()
+                .lms_tree_identifier,
+            lms_tree_identifier
+        );
+
+        // Wrong length
+        let mut wrong_length = binary_representation;
+        wrong_length[binary_representation.len() - 17] = 15;
+        assert!(
+            ReferenceImplPrivateKey::<Hasher>::from_binary_representation(wrong_length.as_slice())
+                .is_err()
+        );
+
+        // Derivation marker with value
+        let mut marker_with_value = binary_representation;
+        marker_with_value[binary_representation.len() - 19] = 1;
+        assert!(
+            ReferenceImplPrivateKey::<Hasher>::from_binary_representation(
+                marker_with_value.as_slice()
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn exhaust_state_at_leaf_limit() {
+        let lmots = LmotsAlgorithm::LmotsW4;
+        let lms = LmsAlgorithm::LmsH5;
+        let parameters = [HssParameter::<Hasher>::new(lmots, lms)];
+
+        let seed = gen_random_seed::<Hasher>();
+        let mut rfc_private_key = ReferenceImplPrivateKey::generate(&parameters, &seed).unwrap();
+        rfc_private_key.set_leaf_limit(3).unwrap();
+
+        let hss_private_key = HssPrivateKey::from(&rfc_private_key, &mut None).unwrap();
+
+        for _ in 0..2 {
+            rfc_private_key.increment(&hss_private_key);
+            assert_eq!(rfc_private_key.seed, seed);
+        }
+        rfc_private_key.increment(&hss_private_key);
+        assert_ne!(rfc_private_key.seed, seed);
+    }
 }
@@ -0,0 +1,271 @@
+use core::marker::PhantomData;
+use tinyvec::ArrayVec;
+
+use crate::{
+    constants::MAX_PRIVATE_KEY_SIZE,
+    hss::{reference_impl_private_key::ReferenceImplPrivateKey, state_store::StateStore},
+    Error, HashChain,
+};
+
+#[cfg(feature = "std")]
+use {
+    crate::hss::state_store::write_atomically,
+    core::convert::TryInto,
+    std::{
+        fs,
+        io::ErrorKind,
+        path::{Path, PathBuf},
+    },
+};
+
+/**
+ * Counter that can only move forward, e.g. a hardware counter of a TPM or secure element.
+ *
+ * The counter records the leaf counter of the latest stored private key state. It has to be kept
+ * apart from the private key, such that restoring a backup or snapshot of the private key does
+ * not restore the counter as well.
+ */
+pub trait MonotonicCounter {
+    /**
+     * Returns the current value of the counter.
+     */
+    fn get(&mut self) -> Result<u64, Error>;
+
+    /**
+     * Raises the counter to `value`. The counter must be left unchanged if `value` is not larger
+     * than the current value.
+     */
+    fn advance(&mut self, value: u64) -> Result<(), Error>;
+}
+
+impl<C: MonotonicCounter + ?Sized> MonotonicCounter for &mut C {
+    fn get(&mut self) -> Result<u64, Error> {
+        (**self).get()
+    }
+
+    fn advance(&mut self, value: u64) -> Result<(), Error> {
+        (**self).advance(value)
+    }
+}
+
+/**
+ * File backed [`MonotonicCounter`].
+ *
+ * The counter is stored as 8 byte big endian value and replaced atomically like the private key of
+ * a [`FileStateStore`](crate::FileStateStore). A missing file is treated as counter value `0`.
+ * The file has to be excluded from backups and snapshots of the private key.
+ */
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileMonotonicCounter {
+    path: PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FileMonotonicCounter {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(feature = "std")]
+impl MonotonicCounter for FileMonotonicCounter {
+    fn get(&mut self) -> Result<u64, Error> {
+        match fs::read(&self.path) {
+            Ok(value) => Ok(u64::from_be_bytes(
+                value.as_slice().try_into().map_err(|_| Error::StateStore)?,
+            )),
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(0),
+            Err(_) => Err(Error::StateStore),
+        }
+    }
+
+    fn advance(&mut self, value: u64) -> Result<(), Error> {
+        if value <= self.get()? {
+            return Ok(());
+        }
+
+        write_atomically(&self.path, &value.to_be_bytes()).map_err(|_| Error::StateStore)
+    }
+}
+
+/**
+ * [`StateStore`] that refuses to load private key states older than the latest stored state.
+ *
+ * The leaf counter of every stored private key is recorded in a [`MonotonicCounter`]. Loading a
+ * private key with a lower leaf counter fails with [`Error::StateRollback`], as its one-time keys
+ * might have been used already. The counter is advanced after the private key was stored, so the
+ * signature is only released if both succeeded.
+ *
+ * Wrap this store into a [`ReservedStateStore`](crate::ReservedStateStore) to only advance the
+ * counter once per reservation. Enabling the state tag of the private key with
+ * [`SigningKey::enable_state_tag`](crate::SigningKey::enable_state_tag) additionally detects
+ * modified leaf counters.
+ */
+pub struct RollbackProtectedStateStore<H: HashChain, S: StateStore, C: MonotonicCounter> {
+    state_store: S,
+    counter: C,
+    phantom_data: PhantomData<H>,
+}
+
+impl<H: HashChain, S: StateStore, C: MonotonicCounter> RollbackProtectedStateStore<H, S, C> {
+    pub fn new(state_store: S, counter: C) -> Self {
+        Self {
+            state_store,
+            counter,
+            phantom_data: PhantomData,
+        }
+    }
+}
+
+impl<H: HashChain, S: StateStore, C: MonotonicCounter> StateStore
+    for RollbackProtectedStateStore<H, S, C>
+{
+    fn lock(&mut self) -> Result<(), Error> {
+        self.state_store.lock()
+    }
+
+    fn unlock(&mut self) -> Result<(), Error> {
+        self.state_store.unlock()
+    }
+
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+        let private_key = self.state_store.load()?;
+        let parsed_private_key =
+            ReferenceImplPrivateKey::<H>::from_binary_representation(&private_key)?;
+
+        // A wiped private key has no leaf counter to compare
+        parsed_private_key.compressed_parameter.to::<H>()?;
+
+        if parsed_private_key.compressed_used_leafs_indexes.count() < self.counter.get()? {
+            return Err(Error::StateRollback);
+        }
+
+        Ok(private_key)
+    }
+
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+        let parsed_private_key =
+            ReferenceImplPrivateKey::<H>::from_binary_representation(private_key)?;
+
+        self.state_store.store(private_key)?;
+        self.counter
+            .advance(parsed_private_key.compressed_used_leafs_indexes.count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hss::{hss_keygen, hss_sign, hss_verify},
+        util::helper::test_helper::gen_random_seed,
+        HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+    };
+
+    struct MemoryCounter(u64);
+
+    impl MonotonicCounter for MemoryCounter {
+        fn get(&mut self) -> Result<u64, Error> {
+            Ok(self.0)
+        }
+
+        fn advance(&mut self, value: u64) -> Result<(), Error> {
+            self.0 = self.0.max(value);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn refuse_rolled_back_state() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let parameters = [HssParameter::new(
+            LmotsAlgorithm::LmotsW2,
+            LmsAlgorithm::LmsH5,
+        )];
+
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let backup = signing_key.clone();
+        let mut counter = MemoryCounter(0);
+
+        for _ in 0..2 {
+            let mut state_store =
+                RollbackProtectedStateStore::<H, _, _>::new(&mut signing_key, &mut counter);
+            let signature = hss_sign::<H>(b"message", &mut state_store, None).unwrap();
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+        }
+        assert_eq!(counter.0, 2);
+
+        signing_key = backup;
+        let mut state_store =
+            RollbackProtectedStateStore::<H, _, _>::new(&mut signing_key, &mut counter);
+        assert_eq!(
+            hss_sign::<H>(b"message", &mut state_store, None).err(),
+            Some(Error::StateRollback)
+        );
+    }
+
+    #[test]
+    fn state_tag() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let parameters = [HssParameter::new(
+            LmotsAlgorithm::LmotsW2,
+            LmsAlgorithm::LmsH5,
+        )];
+
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let untagged_len = signing_key.as_slice().len();
+        signing_key.enable_state_tag().unwrap();
+        assert_eq!(signing_key.as_slice().len(), untagged_len + 2 + 32);
+
+        for _ in 0..2 {
+            let signature = hss_sign::<H>(b"message", &mut signing_key, None).unwrap();
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+        }
+        assert_eq!(signing_key.as_slice().len(), untagged_len + 2 + 32);
+
+        let split_off_key = signing_key.split_off(4).unwrap();
+        assert_eq!(split_off_key.as_slice().len(), untagged_len + 2 + 32);
+        assert_eq!(split_off_key.get_lifetime(), Ok(4));
+        assert_eq!(signing_key.as_slice().len(), untagged_len + 2 + 8 + 2 + 32);
+        assert_eq!(signing_key.get_lifetime(), Ok(26));
+
+        // Roll back the leaf counter
+        signing_key.as_mut_slice()[7] = 0;
+        assert_eq!(signing_key.get_lifetime(), Err(Error::InvalidStateTag));
+        assert_eq!(
+            hss_sign::<H>(b"message", &mut signing_key, None).err(),
+            Some(Error::InvalidStateTag)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn file_monotonic_counter() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("key.counter");
+
+        let mut counter = FileMonotonicCounter::new(&path);
+        assert_eq!(counter.get(), Ok(0));
+
+        counter.advance(5).unwrap();
+        counter.advance(3).unwrap();
+        assert_eq!(counter.get(), Ok(5));
+        assert_eq!(FileMonotonicCounter::new(&path).get(), Ok(5));
+
+        fs::write(&path, b"broken").unwrap();
+        assert_eq!(counter.get(), Err(Error::StateStore));
+    }
+}
@@ -11,9 +11,10 @@ use crate::{
         self,
         definitions::{InMemoryLmsPublicKey, LmsPublicKey},
         signing::{InMemoryLmsSignature, LmsSignature},
+        traversal::MerkleTraversal,
     },
-    util::helper::read_and_advance,
-    HashChain,
+    util::helper::try_read_and_advance,
+    Error, HashChain,
 };
 
 use super::definitions::HssPrivateKey;
@@ -34,7 +35,8 @@ impl<H: HashChain> HssSignature<H> {
         message: Option<&[u8]>,
         message_mut: Option<&mut [u8]>,
         aux_data: &mut Option<MutableExpandedAuxData>,
-    ) -> Result<HssSignature<H>, ()> {
+        tree_traversal: Option<&mut MerkleTraversal<H>>,
+    ) -> Result<HssSignature<H>, Error> {
         let max_level = private_key.get_length();
 
         let prv = &mut private_key.private_key;
@@ -43,7 +45,7 @@ impl<H: HashChain> HssSignature<H> {
 
         // Raise error, if array already contains a signature at index max_level - 1.
         if sig.get_mut(max_level - 1).is_some() {
-            return Err(());
+            return Err(Error::InvalidParameters);
         }
 
         // Sign the message
@@ -63,16 +65,18 @@ impl<H: HashChain> HssSignature<H> {
                 message_mut,
                 &mut signature_randomizer,
                 aux_data,
+                tree_traversal,
             );
             #[cfg(not(feature = "fast_verify"))]
-            let lms_sig = Err(());
+            let lms_sig = Err(Error::InvalidMessage);
             lms_sig
         } else {
-            lms::signing::LmsSignature::sign(
+            lms::signing::LmsSignature::sign_with_tree_traversal(
                 &mut prv[max_level - 1],
                 message.unwrap(),
                 &signature_randomizer,
                 aux_data,
+                tree_traversal,
             )
         }?;
         sig.push(new_signature);
@@ -142,11 +146,18 @@ impl<'a, H: HashChain> PartialEq<HssSignature<H>> for InMemoryHssSignature<'a, H
 }
 
 impl<'a, H: HashChain> InMemoryHssSignature<'a, H> {
-    pub fn new(data: &'a [u8]) -> Option<Self> {
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
         let mut index = 0;
 
-        let level =
-            u32::from_be_bytes(read_and_advance(data, 4, &mut index).try_into().unwrap()) as usize;
+        let level = u32::from_be_bytes(
+            try_read_and_advance(data, 4, &mut index, Error::MalformedSignature)?
+                .try_into()
+                .unwrap(),
+        ) as usize;
+
+        if level >= MAX_ALLOWED_HSS_LEVELS {
+            return Err(Error::MalformedSignature);
+        }
 
         let mut signed_public_keys = ArrayVec::new();
 
@@ -158,7 +169,7 @@ impl<'a, H: HashChain> InMemoryHssSignature<'a, H> {
 
         let signature = InMemoryLmsSignature::<'a, H>::new(&data[index..])?;
 
-        Some(Self {
+        Ok(Self {
             level,
             signed_public_keys,
             signature,
@@ -203,7 +214,7 @@ impl<H: HashChain> HssSignedPublicKey<H> {
 }
 
 impl<'a, H: HashChain> InMemoryHssSignedPublicKey<'a, H> {
-    pub fn new(data: &'a [u8]) -> Option<Self> {
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
         let sig = InMemoryLmsSignature::new(data)?;
 
         let sig_size = lms_signature_length(
@@ -216,9 +227,13 @@ impl<'a, H: HashChain> InMemoryHssSignedPublicKey<'a, H> {
             sig.lms_parameter.get_tree_height() as usize,
         );
 
-        let public_key = InMemoryLmsPublicKey::new(&data[sig_size..])?;
+        let public_key =
+            InMemoryLmsPublicKey::new(&data[sig_size..]).map_err(|error| match error {
+                Error::MalformedPublicKey => Error::MalformedSignature,
+                error => error,
+            })?;
 
-        Some(Self { sig, public_key })
+        Ok(Self { sig, public_key })
     }
 
     pub fn len(&self) -> usize {
@@ -280,10 +295,10 @@ mod tests {
 
         let message = [2, 56, 123, 22, 42, 49, 22];
 
-        let _ = HssSignature::sign(&mut private_key, Some(&message), None, &mut None)
+        let _ = HssSignature::sign(&mut private_key, Some(&message), None, &mut None, None)
             .expect("Should generate HSS signature");
 
-        let _ = HssSignature::sign(&mut private_key, Some(&message), None, &mut None)
+        let _ = HssSignature::sign(&mut private_key, Some(&message), None, &mut None, None)
             .expect("Signing should panic!");
     }
 
@@ -342,7 +357,7 @@ mod tests {
         let mut message = [0u8; 64];
         message[..message_values.len()].copy_from_slice(&message_values);
 
-        let signature = HssSignature::sign(&mut private_key, Some(&message), None, &mut None)
+        let signature = HssSignature::sign(&mut private_key, Some(&message), None, &mut None, None)
             .expect("Should generate HSS signature");
 
         let binary_representation = signature.to_binary_representation();
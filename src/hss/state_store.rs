@@ -0,0 +1,715 @@
+use core::{convert::TryFrom, marker::PhantomData};
+use tinyvec::ArrayVec;
+
+use crate::{
+    constants::MAX_PRIVATE_KEY_SIZE,
+    hss::{
+        key_usage::KeyUsage,
+        reference_impl_private_key::{CompressedUsedLeafsIndexes, ReferenceImplPrivateKey},
+        SigningKey,
+    },
+    lms::LmsSigningKey,
+    Error, HashChain,
+};
+
+#[cfg(feature = "std")]
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+/**
+ * Persistent storage for the private key state of a stateful signing key.
+ *
+ * Signing with LMS/HSS consumes a one-time key on every call, so the advanced private key has to
+ * be saved before a signature may be released. The signing functions of this crate call the
+ * methods of a [`StateStore`] in the following order:
+ *
+ * 1. [`StateStore::lock`] to get exclusive access to the private key,
+ * 2. [`StateStore::load`] to read the current private key,
+ * 3. [`StateStore::store`] with the advanced private key,
+ * 4. [`StateStore::unlock`] to release the exclusive access again.
+ *
+ * A signature is only returned if [`StateStore::store`] succeeded. Implementations therefore must
+ * only return `Ok` from [`StateStore::store`], if the new private key has been durably committed.
+ */
+pub trait StateStore {
+    /**
+     * Acquires exclusive access to the private key. Calling it while the lock is already held by
+     * this instance must succeed.
+     */
+    fn lock(&mut self) -> Result<(), Error>;
+
+    /**
+     * Releases the exclusive access acquired by [`StateStore::lock`].
+     */
+    fn unlock(&mut self) -> Result<(), Error>;
+
+    /**
+     * Loads the current private key in the format of the reference implementation.
+     */
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error>;
+
+    /**
+     * Replaces the stored private key with `private_key`. Must only return `Ok`, if the new
+     * private key has been durably committed.
+     */
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error>;
+}
+
+impl<S: StateStore + ?Sized> StateStore for &mut S {
+    fn lock(&mut self) -> Result<(), Error> {
+        (**self).lock()
+    }
+
+    fn unlock(&mut self) -> Result<(), Error> {
+        (**self).unlock()
+    }
+
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+        (**self).load()
+    }
+
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+        (**self).store(private_key)
+    }
+}
+
+/**
+ * A [`SigningKey`] acts as in-memory [`StateStore`] of itself.
+ */
+impl<H: HashChain> StateStore for SigningKey<H> {
+    fn lock(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn unlock(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+        Ok(self.bytes)
+    }
+
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+        self.bytes = ArrayVec::try_from(private_key).map_err(|_| Error::MalformedPrivateKey)?;
+        Ok(())
+    }
+}
+
+impl<H: HashChain> StateStore for LmsSigningKey<H> {
+    fn lock(&mut self) -> Result<(), Error> {
+        self.signing_key.lock()
+    }
+
+    fn unlock(&mut self) -> Result<(), Error> {
+        self.signing_key.unlock()
+    }
+
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+        self.signing_key.load()
+    }
+
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+        self.signing_key.store(private_key)
+    }
+}
+
+/**
+ * File backed [`StateStore`].
+ *
+ * The private key is replaced atomically: the new key is written to `<path>.tmp`, synced to disk
+ * and renamed to `<path>`. Afterwards the containing directory is synced as well (on unix).
+ *
+ * Exclusive access is guarded by the advisory lock file `<path>.lock`, which is created on
+ * [`StateStore::lock`] and removed on [`StateStore::unlock`] or when the store is dropped. If a
+ * process holding the lock crashes, the lock file stays in place and has to be removed manually
+ * after making sure that no other process signs with the key.
+ */
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct FileStateStore {
+    path: PathBuf,
+    locked: bool,
+}
+
+#[cfg(feature = "std")]
+impl FileStateStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            locked: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        sibling_path(&self.path, ".lock")
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path.as_os_str());
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/**
+ * Replaces the content of `path` by writing `data` to `<path>.tmp`, syncing it to disk and
+ * renaming it to `path`. Afterwards the containing directory is synced as well (on unix).
+ */
+#[cfg(feature = "std")]
+pub(crate) fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let temporary_path = sibling_path(path, ".tmp");
+
+    let mut file = File::create(&temporary_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temporary_path, path)?;
+
+    #[cfg(unix)]
+    {
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(directory)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+impl StateStore for FileStateStore {
+    fn lock(&mut self) -> Result<(), Error> {
+        if self.locked {
+            return Ok(());
+        }
+
+        let mut lock_file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(self.lock_path())
+            .map_err(|_| Error::StateStore)?;
+        self.locked = true;
+
+        // The content is informational only, the existence of the file is the lock
+        let _ = write!(lock_file, "{}", std::process::id());
+
+        Ok(())
+    }
+
+    fn unlock(&mut self) -> Result<(), Error> {
+        if !self.locked {
+            return Ok(());
+        }
+
+        match fs::remove_file(self.lock_path()) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(Error::StateStore),
+            _ => {
+                self.locked = false;
+                Ok(())
+            }
+        }
+    }
+
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+        if !self.locked {
+            return Err(Error::StateStore);
+        }
+
+        let private_key = fs::read(&self.path).map_err(|_| Error::StateStore)?;
+        ArrayVec::try_from(private_key.as_slice()).map_err(|_| Error::MalformedPrivateKey)
+    }
+
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+        if !self.locked {
+            return Err(Error::StateStore);
+        }
+
+        write_atomically(&self.path, private_key).map_err(|_| Error::StateStore)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for FileStateStore {
+    fn drop(&mut self) {
+        let _ = self.unlock();
+    }
+}
+
+/**
+ * [`StateStore`] that reserves leafs ahead to reduce the number of writes to the wrapped store.
+ *
+ * Instead of persisting the private key after every signature, the leaf counter is advanced up to
+ * `reservation_size` leafs ahead in the wrapped store, while the actual private key is kept in
+ * memory. Subsequent signatures are generated from memory until the reservation is used up. If
+ * the process terminates, the unused reserved leafs are skipped and never reused.
+ *
+ * A `reservation_size` of `1` (or `0`) writes the private key on every signature.
+ *
+ * The lock of the wrapped store is acquired on the first use and held until the
+ * [`ReservedStateStore`] is dropped, because nobody else must use the private key while leafs
+ * are reserved in memory.
+ */
+pub struct ReservedStateStore<H: HashChain, S: StateStore> {
+    state_store: S,
+    private_key: Option<ReferenceImplPrivateKey<H>>,
+    reserved_until: u64,
+    reservation_size: u64,
+}
+
+impl<H: HashChain, S: StateStore> ReservedStateStore<H, S> {
+    pub fn new(state_store: S, reservation_size: u64) -> Self {
+        Self {
+            state_store,
+            private_key: None,
+            reserved_until: 0,
+            reservation_size,
+        }
+    }
+
+    pub fn get_reservation_size(&self) -> u64 {
+        self.reservation_size
+    }
+
+    pub fn set_reservation_size(&mut self, reservation_size: u64) {
+        self.reservation_size = reservation_size;
+    }
+
+    /**
+     * Returns how many signatures can be generated without writing to the wrapped store.
+     */
+    pub fn remaining_reservation(&self) -> u64 {
+        self.private_key.as_ref().map_or(0, |private_key| {
+            self.reserved_until
+                .saturating_sub(private_key.compressed_used_leafs_indexes.count())
+        })
+    }
+
+    /**
+     * Reserves the next `leafs` leafs in the wrapped store immediately, independent of the
+     * configured reservation size. Existing reservations are never shortened.
+     */
+    pub fn reserve(&mut self, leafs: u64) -> Result<(), Error> {
+        self.lock()?;
+        self.load()?;
+
+        let private_key = self.private_key.as_ref().ok_or(Error::StateStore)?;
+        let reserved_until = private_key
+            .compressed_used_leafs_indexes
+            .count()
+            .saturating_add(leafs);
+
+        if reserved_until > self.reserved_until {
+            self.reserved_until =
+                Self::persist_reservation(&mut self.state_store, private_key, reserved_until)?;
+        }
+
+        Ok(())
+    }
+
+    fn persist_reservation(
+        state_store: &mut S,
+        private_key: &ReferenceImplPrivateKey<H>,
+        reserved_until: u64,
+    ) -> Result<u64, Error> {
+        let reserved_until = reserved_until.min(private_key.get_max_used_leafs_index()?);
+
+        let mut reserved_private_key = private_key.clone();
+        reserved_private_key.compressed_used_leafs_indexes =
+            CompressedUsedLeafsIndexes::new(reserved_until);
+        state_store.store(&reserved_private_key.to_binary_representation())?;
+
+        Ok(reserved_until)
+    }
+}
+
+impl<H: HashChain, S: StateStore> StateStore for ReservedStateStore<H, S> {
+    fn lock(&mut self) -> Result<(), Error> {
+        self.state_store.lock()
+    }
+
+    /**
+     * Keeps the wrapped store locked, as leafs might still be reserved in memory.
+     */
+    fn unlock(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+        if let Some(private_key) = self.private_key.as_ref() {
+            return Ok(private_key.to_binary_representation());
+        }
+
+        let private_key = self.state_store.load()?;
+        let parsed_private_key =
+            ReferenceImplPrivateKey::from_binary_representation(private_key.as_slice())?;
+
+        self.reserved_until = parsed_private_key.compressed_used_leafs_indexes.count();
+        self.private_key = Some(parsed_private_key);
+
+        Ok(private_key)
+    }
+
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+        let current_private_key = self.private_key.as_ref().ok_or(Error::StateStore)?;
+        let new_private_key =
+            ReferenceImplPrivateKey::<H>::from_binary_representation(private_key)?;
+        let count = new_private_key.compressed_used_leafs_indexes.count();
+
+        if new_private_key.compressed_parameter != current_private_key.compressed_parameter
+            || new_private_key.seed != current_private_key.seed
+        {
+            // Key got exhausted (wiped) or replaced, nothing can be reserved anymore
+            self.state_store.store(private_key)?;
+            self.reserved_until = count;
+        } else if count > self.reserved_until {
+            let reserved_until = count.saturating_add(self.reservation_size.max(1) - 1);
+            self.reserved_until =
+                Self::persist_reservation(&mut self.state_store, &new_private_key, reserved_until)?;
+        }
+
+        self.private_key = Some(new_private_key);
+        Ok(())
+    }
+}
+
+impl<H: HashChain, S: StateStore> Drop for ReservedStateStore<H, S> {
+    fn drop(&mut self) {
+        let _ = self.state_store.unlock();
+    }
+}
+
+/**
+ * [`StateStore`] that reports the [`KeyUsage`] of the private key to a callback, once the number
+ * of remaining signatures dropped to `low_water_mark` or below.
+ *
+ * The callback is invoked on every signature below the mark, after the advanced private key was
+ * stored successfully. The private key is wiped with its last signature, so a `low_water_mark`
+ * of `0` never invokes the callback. Use a mark which leaves enough signatures to rotate the key.
+ */
+pub struct MonitoredStateStore<H: HashChain, S: StateStore, F: FnMut(&KeyUsage)> {
+    state_store: S,
+    low_water_mark: u64,
+    callback: F,
+    phantom_data: PhantomData<H>,
+}
+
+impl<H: HashChain, S: StateStore, F: FnMut(&KeyUsage)> MonitoredStateStore<H, S, F> {
+    pub fn new(state_store: S, low_water_mark: u64, callback: F) -> Self {
+        Self {
+            state_store,
+            low_water_mark,
+            callback,
+            phantom_data: PhantomData,
+        }
+    }
+
+    pub fn get_low_water_mark(&self) -> u64 {
+        self.low_water_mark
+    }
+
+    pub fn set_low_water_mark(&mut self, low_water_mark: u64) {
+        self.low_water_mark = low_water_mark;
+    }
+}
+
+impl<H: HashChain, S: StateStore, F: FnMut(&KeyUsage)> StateStore for MonitoredStateStore<H, S, F> {
+    fn lock(&mut self) -> Result<(), Error> {
+        self.state_store.lock()
+    }
+
+    fn unlock(&mut self) -> Result<(), Error> {
+        self.state_store.unlock()
+    }
+
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+        self.state_store.load()
+    }
+
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+        self.state_store.store(private_key)?;
+
+        // A wiped private key has no usage left to report
+        if let Ok(usage) = ReferenceImplPrivateKey::<H>::from_binary_representation(private_key)
+            .and_then(|private_key| KeyUsage::from_private_key(&private_key))
+        {
+            if usage.remaining <= self.low_water_mark {
+                (self.callback)(&usage);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hss::{hss_keygen, hss_sign, hss_verify},
+        util::helper::test_helper::gen_random_seed,
+        HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+    };
+
+    struct FailingStateStore<H: HashChain> {
+        signing_key: SigningKey<H>,
+    }
+
+    impl<H: HashChain> StateStore for FailingStateStore<H> {
+        fn lock(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn unlock(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+            Ok(self.signing_key.bytes)
+        }
+
+        fn store(&mut self, _private_key: &[u8]) -> Result<(), Error> {
+            Err(Error::StateStore)
+        }
+    }
+
+    struct CountingStateStore<H: HashChain> {
+        signing_key: SigningKey<H>,
+        writes: usize,
+    }
+
+    impl<H: HashChain> StateStore for CountingStateStore<H> {
+        fn lock(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn unlock(&mut self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+            Ok(self.signing_key.bytes)
+        }
+
+        fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+            self.writes += 1;
+            self.signing_key.store(private_key)
+        }
+    }
+
+    fn stored_count<H: HashChain>(state_store: &CountingStateStore<H>) -> u64 {
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&state_store.signing_key.as_slice()[..8]);
+        u64::from_be_bytes(count)
+    }
+
+    #[test]
+    fn reserve_ahead() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let parameters = [HssParameter::new(
+            LmotsAlgorithm::LmotsW2,
+            LmsAlgorithm::LmsH5,
+        )];
+
+        let (signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let mut counting_store = CountingStateStore {
+            signing_key,
+            writes: 0,
+        };
+
+        {
+            let mut state_store = ReservedStateStore::<H, _>::new(&mut counting_store, 4);
+            assert_eq!(state_store.remaining_reservation(), 0);
+
+            for index in 0..10u64 {
+                let signature = hss_sign::<H>(b"message", &mut state_store, None).unwrap();
+                assert!(
+                    hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice())
+                        .is_ok()
+                );
+                assert_eq!(state_store.remaining_reservation(), 3 - index % 4);
+            }
+        }
+
+        // Leafs 10 and 11 were reserved, but never used
+        assert_eq!(counting_store.writes, 3);
+        assert_eq!(stored_count(&counting_store), 12);
+
+        let mut state_store = ReservedStateStore::<H, _>::new(&mut counting_store, 1);
+        state_store.reserve(8).unwrap();
+        state_store.reserve(2).unwrap();
+        assert_eq!(state_store.remaining_reservation(), 8);
+        assert!(hss_sign::<H>(b"message", &mut state_store, None).is_ok());
+        assert_eq!(state_store.remaining_reservation(), 7);
+        drop(state_store);
+
+        assert_eq!(counting_store.writes, 4);
+        assert_eq!(stored_count(&counting_store), 20);
+    }
+
+    #[test]
+    fn reserve_ahead_exhaust_keypair() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let (signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let keypair_lifetime = signing_key.get_lifetime().unwrap();
+        let mut counting_store = CountingStateStore {
+            signing_key,
+            writes: 0,
+        };
+
+        let mut state_store = ReservedStateStore::<H, _>::new(&mut counting_store, 5);
+        for _ in 0..keypair_lifetime {
+            let signature = hss_sign::<H>(b"message", &mut state_store, None).unwrap();
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+        }
+        assert!(hss_sign::<H>(b"message", &mut state_store, None).is_err());
+        drop(state_store);
+
+        assert!(counting_store.signing_key.get_lifetime().is_err());
+        assert_eq!(
+            counting_store.signing_key.as_slice()[16..],
+            [0u8; H::OUTPUT_SIZE as usize]
+        );
+    }
+
+    #[test]
+    fn report_low_water_mark() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)];
+
+        let (mut signing_key, _) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let keypair_lifetime = signing_key.get_lifetime().unwrap();
+
+        let mut reported = [0u64; 4];
+        let mut reports = 0;
+        {
+            let mut state_store =
+                MonitoredStateStore::<H, _, _>::new(&mut signing_key, 3, |usage: &KeyUsage| {
+                    reported[reports] = usage.remaining;
+                    reports += 1;
+                });
+            for _ in 0..keypair_lifetime {
+                hss_sign::<H>(b"message", &mut state_store, None).unwrap();
+            }
+            assert!(hss_sign::<H>(b"message", &mut state_store, None).is_err());
+        }
+
+        // The private key is wiped with its last signature, which is not reported
+        assert_eq!(reports, 3);
+        assert_eq!(reported[..reports], [3, 2, 1]);
+    }
+
+    #[test]
+    fn signing_key_as_state_store() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let parameters = [HssParameter::new(
+            LmotsAlgorithm::LmotsW2,
+            LmsAlgorithm::LmsH5,
+        )];
+
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let signing_key_const = signing_key.clone();
+
+        let signature = hss_sign::<H>(b"message", &mut signing_key, None).unwrap();
+
+        assert!(hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok());
+        assert_ne!(signing_key, signing_key_const);
+    }
+
+    #[test]
+    fn no_signature_without_committed_state() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let parameters = [HssParameter::new(
+            LmotsAlgorithm::LmotsW2,
+            LmsAlgorithm::LmsH5,
+        )];
+
+        let (signing_key, _) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let mut state_store = FailingStateStore { signing_key };
+
+        assert!(hss_sign::<H>(b"message", &mut state_store, None).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn file_state_store() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let parameters = [HssParameter::new(
+            LmotsAlgorithm::LmotsW2,
+            LmsAlgorithm::LmsH5,
+        )];
+
+        let (signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("key.prv");
+        fs::write(&path, signing_key.as_slice()).unwrap();
+
+        let mut state_store = FileStateStore::new(&path);
+
+        for _ in 0..2 {
+            let signature = hss_sign::<H>(b"message", &mut state_store, None).unwrap();
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+        }
+
+        assert!(!state_store.is_locked());
+        assert!(!state_store.lock_path().exists());
+        assert!(!sibling_path(&path, ".tmp").exists());
+
+        let private_key = fs::read(&path).unwrap();
+        assert_eq!(private_key.len(), signing_key.as_slice().len());
+        assert_eq!(private_key[..8], 2u64.to_be_bytes());
+        assert_eq!(private_key[8..], signing_key.as_slice()[8..]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn file_state_store_lock() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("key.prv");
+
+        let mut first = FileStateStore::new(&path);
+        let mut second = FileStateStore::new(&path);
+
+        assert!(first.lock().is_ok());
+        assert!(first.lock().is_ok());
+        assert!(second.lock().is_err());
+        assert!(second.load().is_err());
+        assert!(second.store(&[0u8; 48]).is_err());
+
+        drop(first);
+
+        assert!(second.lock().is_ok());
+        assert!(second.unlock().is_ok());
+        assert!(!second.lock_path().exists());
+    }
+}
@@ -0,0 +1,348 @@
+use crate::{
+    hss::{
+        aux_data::{with_aux_slice, AuxData},
+        definitions::HssPrivateKey,
+        hss_sign_core,
+        pregeneration::NextBottomTree,
+        state_store::StateStore,
+    },
+    lms::traversal::MerkleTraversal,
+    Error, HashChain, Signature,
+};
+
+/**
+ * Long-lived signer which keeps the expanded HSS key hierarchy in memory.
+ *
+ * [`crate::sign`] regenerates every LMS tree of the hierarchy and signs all child public keys
+ * again on every call. [`StatefulSigner`] keeps the LMS private keys, the child public keys and
+ * their signatures across calls and only regenerates the levels below a tree that moved on to
+ * another leaf. Thus, the signing time of multi-level keys is dominated by the bottom tree.
+ *
+ * The bottom tree that follows the current one is pregenerated together with the signature of
+ * its public key, so that the signature which exhausts the current bottom tree does not pay for a
+ * whole new tree. With `std`, the pregeneration runs on a background thread. Without `std`, every
+ * signature computes a share of the next tree. The signatures are the same as without
+ * pregeneration.
+ *
+ * Signers created with [`StatefulSigner::with_tree_traversal`] additionally keep a traversal
+ * state of the bottom tree, which yields the authentication path of every signature with at most
+ * `2 * h - 1` leaf computations for a tree of height `h`. Without it, the authentication path is
+ * taken from the aux data or recomputed from the whole tree. The traversal state can be saved and
+ * loaded, so that it survives a restart of the signer.
+ *
+ * The private key itself is still loaded from and stored to a [`StateStore`] on every signature,
+ * in the format of the reference implementation. If the loaded private key differs from the
+ * cached one (e.g. another signer used the key in the meantime), the hierarchy is updated
+ * accordingly.
+ *
+ * # Example
+ * ```
+ * use rand::{rngs::OsRng, RngCore};
+ * use hbs_lms::{keygen, verify, HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256, Seed,
+ *     StatefulSigner,
+ * };
+ *
+ * let parameters = [
+ *      HssParameter::new(LmotsAlgorithm::LmotsW4, LmsAlgorithm::LmsH5),
+ *      HssParameter::new(LmotsAlgorithm::LmotsW4, LmsAlgorithm::LmsH5),
+ * ];
+ * let mut seed = Seed::default();
+ * OsRng.fill_bytes(seed.as_mut_slice());
+ *
+ * let (mut signing_key, verifying_key) =
+ *      keygen::<Sha256_256>(&parameters, &seed, None).unwrap();
+ *
+ * let mut signer = StatefulSigner::<Sha256_256>::new();
+ * for message in [b"first", b"other"] {
+ *     let signature = signer.try_sign(message, &mut signing_key, None).unwrap();
+ *     assert!(verify::<Sha256_256>(message, signature.as_ref(), verifying_key.as_slice()).is_ok());
+ * }
+ * ```
+ */
+#[derive(Default)]
+pub struct StatefulSigner<H: HashChain> {
+    private_key: HssPrivateKey<H>,
+    next_bottom_tree: NextBottomTree<H>,
+    use_tree_traversal: bool,
+    tree_traversal: Option<MerkleTraversal<H>>,
+}
+
+impl<H: HashChain> StatefulSigner<H> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /**
+     * Creates a signer which keeps a traversal state of the bottom tree. Its setup processes the
+     * whole bottom tree once, afterwards every signature computes at most `2 * h - 1` leafs.
+     */
+    pub fn with_tree_traversal() -> Self {
+        Self {
+            use_tree_traversal: true,
+            ..Self::default()
+        }
+    }
+
+    /**
+     * Returns the length of the traversal state, or `0` if there is none yet.
+     */
+    pub fn tree_traversal_len(&self) -> usize {
+        self.tree_traversal
+            .as_ref()
+            .map_or(0, |tree_traversal| tree_traversal.binary_len())
+    }
+
+    /**
+     * Saves the traversal state into `data` and returns its length, see
+     * [`StatefulSigner::tree_traversal_len`]. Fails with [`Error::InvalidParameters`] if there
+     * is no traversal state of the current bottom tree.
+     */
+    pub fn save_tree_traversal(&self, data: &mut [u8]) -> Result<usize, Error> {
+        let bottom = self
+            .private_key
+            .private_key
+            .last()
+            .ok_or(Error::InvalidParameters)?;
+
+        match self.tree_traversal.as_ref() {
+            Some(tree_traversal) if tree_traversal.is_at(bottom, bottom.used_leafs_index) => {
+                tree_traversal.to_binary_representation(bottom, data)
+            }
+            _ => Err(Error::InvalidParameters),
+        }
+    }
+
+    /**
+     * Loads a traversal state saved by [`StatefulSigner::save_tree_traversal`] and enables the
+     * traversal. Its HMAC is checked by the next signature, which fails with
+     * [`Error::InvalidTreeTraversal`] if the state was modified. A state of another tree or leaf
+     * is replaced by a new setup.
+     */
+    pub fn load_tree_traversal(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.tree_traversal = Some(MerkleTraversal::from_binary_representation(data)?);
+        self.use_tree_traversal = true;
+        Ok(())
+    }
+
+    /**
+     * Generate a [`Signature`] with the private key of `state_store`.
+     *
+     * # Arguments
+     * * `message` - The message that should be signed.
+     * * `state_store` - The [`StateStore`] that holds the private key. The signature is only returned if the advanced private key was stored successfully.
+     * * `aux_data` - Auxiliary data to speedup the regeneration of the top level tree if available
+     */
+    pub fn try_sign(
+        &mut self,
+        message: &[u8],
+        state_store: &mut dyn StateStore,
+        aux_data: Option<&mut &mut [u8]>,
+    ) -> Result<Signature, Error> {
+        with_aux_slice(aux_data, |aux_data| {
+            self.try_sign_with_aux_data_core(message, state_store, aux_data)
+        })
+    }
+
+    /**
+     * Like [`StatefulSigner::try_sign`], but takes the aux data as [`AuxData`], which may be
+     * populated lazily.
+     */
+    pub fn try_sign_with_aux_data(
+        &mut self,
+        message: &[u8],
+        state_store: &mut dyn StateStore,
+        aux_data: &mut AuxData<H>,
+    ) -> Result<Signature, Error> {
+        self.try_sign_with_aux_data_core(message, state_store, Some(aux_data))
+    }
+
+    fn try_sign_with_aux_data_core(
+        &mut self,
+        message: &[u8],
+        state_store: &mut dyn StateStore,
+        aux_data: Option<&mut AuxData<H>>,
+    ) -> Result<Signature, Error> {
+        let tree_traversal = if self.use_tree_traversal {
+            Some(&mut self.tree_traversal)
+        } else {
+            None
+        };
+
+        hss_sign_core::<H>(
+            Some(message),
+            None,
+            state_store,
+            aux_data,
+            &mut self.private_key,
+            Some(&mut self.next_bottom_tree),
+            tree_traversal,
+        )
+    }
+
+    #[cfg(feature = "fast_verify")]
+    pub fn try_sign_mut(
+        &mut self,
+        message_mut: &mut [u8],
+        state_store: &mut dyn StateStore,
+        aux_data: Option<&mut &mut [u8]>,
+    ) -> Result<Signature, Error> {
+        crate::hss::check_message_mut::<H>(message_mut)?;
+
+        let tree_traversal = if self.use_tree_traversal {
+            Some(&mut self.tree_traversal)
+        } else {
+            None
+        };
+
+        let private_key = &mut self.private_key;
+        let next_bottom_tree = &mut self.next_bottom_tree;
+        with_aux_slice(aux_data, |aux_data| {
+            hss_sign_core::<H>(
+                None,
+                Some(message_mut),
+                state_store,
+                aux_data,
+                private_key,
+                Some(next_bottom_tree),
+                tree_traversal,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hss::{hss_keygen, hss_sign, hss_verify},
+        util::helper::test_helper::gen_random_seed,
+        HssParameter, LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+    };
+
+    #[test]
+    fn same_signatures_as_stateless_signing() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [
+            HssParameter::new(lmots, lms),
+            HssParameter::new(lmots, lms),
+            HssParameter::new(lmots, lms),
+        ];
+
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let mut stateless_signing_key = signing_key.clone();
+        let keypair_lifetime = signing_key.get_lifetime().unwrap();
+
+        let mut signer = StatefulSigner::<H>::new();
+        for _ in 0..keypair_lifetime {
+            let signature = signer.try_sign(b"message", &mut signing_key, None).unwrap();
+            let stateless_signature =
+                hss_sign::<H>(b"message", &mut stateless_signing_key, None).unwrap();
+
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+            assert_eq!(signature.as_ref(), stateless_signature.as_ref());
+            assert_eq!(signing_key, stateless_signing_key);
+        }
+
+        assert!(signer.try_sign(b"message", &mut signing_key, None).is_err());
+    }
+
+    #[test]
+    fn regenerate_changed_levels_only() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH2;
+        let parameters = [
+            HssParameter::new(lmots, lms),
+            HssParameter::new(lmots, lms),
+            HssParameter::new(lmots, lms),
+        ];
+
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let mut signer = StatefulSigner::<H>::new();
+
+        signer.try_sign(b"message", &mut signing_key, None).unwrap();
+        let private_key = signer.private_key.private_key.clone();
+        let signatures = signer.private_key.signatures.clone();
+
+        // The bottom tree rolls over, thus only the middle tree moves on to its next leaf
+        for _ in 0..4 {
+            signer.try_sign(b"message", &mut signing_key, None).unwrap();
+        }
+        assert_eq!(signer.private_key.private_key[0], private_key[0]);
+        assert_eq!(signer.private_key.signatures[0], signatures[0]);
+        assert_ne!(signer.private_key.signatures[1], signatures[1]);
+
+        // Use the key without the signer, which has to catch up afterwards
+        hss_sign::<H>(b"message", &mut signing_key, None).unwrap();
+        for _ in 0..16 {
+            let signature = signer.try_sign(b"message", &mut signing_key, None).unwrap();
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+        }
+        assert_ne!(signer.private_key.signatures[0], signatures[0]);
+    }
+
+    #[test]
+    fn sign_with_tree_traversal() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let parameters = [
+            HssParameter::new(lmots, LmsAlgorithm::LmsH5),
+            HssParameter::new(lmots, LmsAlgorithm::LmsH2),
+        ];
+
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let mut stateless_signing_key = signing_key.clone();
+
+        let mut signer = StatefulSigner::<H>::with_tree_traversal();
+        assert_eq!(signer.tree_traversal_len(), 0);
+
+        for i in 0..20 {
+            if i == 5 {
+                let mut tree_traversal = [0u8; 1_024];
+                let tree_traversal_len = signer.save_tree_traversal(&mut tree_traversal).unwrap();
+                assert_eq!(tree_traversal_len, signer.tree_traversal_len());
+
+                // A modified traversal state is rejected
+                let mut modified_tree_traversal = tree_traversal;
+                modified_tree_traversal[tree_traversal_len - 1] ^= 1;
+                let mut other_signer = StatefulSigner::<H>::new();
+                other_signer
+                    .load_tree_traversal(&modified_tree_traversal[..tree_traversal_len])
+                    .unwrap();
+                assert_eq!(
+                    other_signer
+                        .try_sign(b"message", &mut signing_key.clone(), None)
+                        .map(|_| ()),
+                    Err(Error::InvalidTreeTraversal)
+                );
+
+                // Restart the signer with the saved traversal state
+                signer = StatefulSigner::<H>::new();
+                signer
+                    .load_tree_traversal(&tree_traversal[..tree_traversal_len])
+                    .unwrap();
+            }
+
+            let signature = signer.try_sign(b"message", &mut signing_key, None).unwrap();
+            let stateless_signature =
+                hss_sign::<H>(b"message", &mut stateless_signing_key, None).unwrap();
+
+            assert!(
+                hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok()
+            );
+            assert_eq!(signature.as_ref(), stateless_signature.as_ref());
+        }
+    }
+}
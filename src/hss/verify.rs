@@ -1,31 +1,326 @@
+use core::marker::PhantomData;
+use tinyvec::ArrayVec;
+
 use crate::{
+    constants::Node,
     hasher::HashChain,
-    lms::{self},
+    lms::{self, definitions::InMemoryLmsPublicKey},
+    Error,
 };
 
 use super::{definitions::InMemoryHssPublicKey, signing::InMemoryHssSignature};
 
+/// Number of chains of signed public keys [`verify_batch`] keeps the result of.
+const VERIFIED_CHAINS: usize = 32;
+
+/// Length of the number of signed public keys at the start of an HSS signature.
+const SIGNED_PUBLIC_KEYS_COUNT_LEN: usize = 4;
+
 pub fn verify<'a, H: HashChain>(
     signature: &InMemoryHssSignature<'a, H>,
     public_key: &InMemoryHssPublicKey<'a, H>,
     message: &[u8],
-) -> Result<(), ()> {
+) -> Result<(), Error> {
+    let key = verify_signed_public_keys(signature, public_key, None)?;
+
+    verify_bottom_level(signature, key, public_key, message)
+}
+
+/**
+ * Verifies the signed public keys of `signature` and returns the public key of the bottom level.
+ *
+ * With `verified`, the result of every signed public key is looked up before it is verified.
+ * `signature_data` are the bytes `signature` was parsed from.
+ */
+fn verify_signed_public_keys<'a, 'b, H: HashChain>(
+    signature: &'b InMemoryHssSignature<'a, H>,
+    public_key: &'b InMemoryHssPublicKey<'a, H>,
+    mut verified: Option<(&'a [u8], &mut dyn VerifiedSignedPublicKeys<'a>)>,
+) -> Result<&'b InMemoryLmsPublicKey<'a, H>, Error> {
     if signature.level + 1 != public_key.level {
-        return Err(());
+        return Err(Error::LevelMismatch {
+            signature: signature.level + 1,
+            public_key: public_key.level,
+        });
     }
 
+    let mut chain_len = SIGNED_PUBLIC_KEYS_COUNT_LEN;
     let mut key = &public_key.public_key;
-    for i in 0..public_key.level - 1 {
-        let sig = &signature.signed_public_keys[i].as_ref().unwrap().sig;
-        let msg = &signature.signed_public_keys[i].as_ref().unwrap().public_key;
+    for level in 0..public_key.level - 1 {
+        let signed_public_key = signature.signed_public_keys[level]
+            .as_ref()
+            .ok_or(Error::MalformedSignature)?;
+        let sig = &signed_public_key.sig;
+        let msg = &signed_public_key.public_key;
+        chain_len += signed_public_key.len();
 
-        if lms::verify::verify(sig, key, msg.as_slice()).is_err() {
-            return Err(());
+        let verify_level = || lms::verify::verify(sig, key, msg.as_slice()).is_ok();
+        let is_valid = match verified.as_mut() {
+            Some((signature_data, verified)) => {
+                let chain = &signature_data[..chain_len];
+                let signed = &chain[chain_len - signed_public_key.len()..];
+                verified
+                    .get(level, key.as_slice(), chain, signed)
+                    .unwrap_or_else(|| {
+                        let is_valid = verify_level();
+                        verified.insert(level, key.as_slice(), chain, signed, is_valid);
+                        is_valid
+                    })
+            }
+            None => verify_level(),
+        };
+
+        if !is_valid {
+            return Err(Error::VerificationFailed { level });
         }
         key = msg;
     }
 
-    lms::verify::verify(&signature.signature, key, message)
+    Ok(key)
+}
+
+/**
+ * Results of signed public keys that were verified before.
+ *
+ * A signed public key at `level` is verified with `key`, the public key of the level above.
+ * `chain` are the bytes of the signature up to and including `signed_public_key`.
+ */
+trait VerifiedSignedPublicKeys<'a> {
+    fn get(
+        &self,
+        level: usize,
+        key: &[u8],
+        chain: &'a [u8],
+        signed_public_key: &[u8],
+    ) -> Option<bool>;
+
+    fn insert(
+        &mut self,
+        level: usize,
+        key: &[u8],
+        chain: &'a [u8],
+        signed_public_key: &[u8],
+        is_valid: bool,
+    );
+}
+
+/**
+ * Results of already verified chains of signed public keys, identified by their bytes. As every
+ * chain is rooted in the same public key, equal bytes yield the same result. The oldest result is
+ * replaced once all entries are used.
+ */
+#[derive(Default)]
+struct VerifiedChains<'a> {
+    chains: ArrayVec<[(&'a [u8], bool); VERIFIED_CHAINS]>,
+    next: usize,
+}
+
+impl<'a> VerifiedSignedPublicKeys<'a> for VerifiedChains<'a> {
+    fn get(&self, _: usize, _: &[u8], chain: &'a [u8], _: &[u8]) -> Option<bool> {
+        self.chains
+            .iter()
+            .find(|(verified_chain, _)| *verified_chain == chain)
+            .map(|(_, is_valid)| *is_valid)
+    }
+
+    fn insert(&mut self, _: usize, _: &[u8], chain: &'a [u8], _: &[u8], is_valid: bool) {
+        if self.chains.len() < self.chains.capacity() {
+            self.chains.push((chain, is_valid));
+        } else {
+            self.chains[self.next] = (chain, is_valid);
+            self.next = (self.next + 1) % self.chains.len();
+        }
+    }
+}
+
+/**
+ * Cache of signed public keys that were verified by earlier calls of [`VerificationCache::verify`].
+ *
+ * Consecutive signatures of the same bottom tree share their signed public keys. The cache
+ * remembers up to `N` of them, so that later signatures only need the bottom level to be verified.
+ * An entry is the digest of the level, the public key of the level above and the bytes of the
+ * signed public key, and is only added once the signed public key was verified successfully.
+ * The oldest entry is replaced once all entries are used.
+ *
+ * The upper levels of a signature are looked up in order, so an entry is only used after the
+ * public key it was verified with has been verified under the current public key as well.
+ */
+pub struct VerificationCache<H: HashChain, const N: usize = 16> {
+    entries: [Node; N],
+    len: usize,
+    next: usize,
+    phantom: PhantomData<H>,
+}
+
+impl<H: HashChain, const N: usize> Default for VerificationCache<H, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: HashChain, const N: usize> VerificationCache<H, N> {
+    pub fn new() -> Self {
+        Self {
+            entries: [Node::new(); N],
+            len: 0,
+            next: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /**
+     * Verifies `signature` of `message` under `public_key` like [`crate::verify`], but skips the
+     * signed public keys that are in the cache. The ones verified successfully are added.
+     */
+    pub fn verify(
+        &mut self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<(), Error> {
+        let hss_signature = InMemoryHssSignature::<H>::new(signature)?;
+        let public_key = InMemoryHssPublicKey::<H>::new(public_key)?;
+
+        let key = verify_signed_public_keys(&hss_signature, &public_key, Some((signature, self)))?;
+        verify_bottom_level(&hss_signature, key, &public_key, message)
+    }
+
+    /// Number of cached signed public keys.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.next = 0;
+    }
+
+    fn entry(level: usize, key: &[u8], signed_public_key: &[u8]) -> Node {
+        let mut hasher = H::default();
+        hasher.update(&(level as u32).to_be_bytes());
+        hasher.update(key);
+        hasher.update(signed_public_key);
+        hasher.finalize()
+    }
+}
+
+impl<'a, H: HashChain, const N: usize> VerifiedSignedPublicKeys<'a> for VerificationCache<H, N> {
+    fn get(&self, level: usize, key: &[u8], _: &'a [u8], signed_public_key: &[u8]) -> Option<bool> {
+        let entry = Self::entry(level, key, signed_public_key);
+        self.entries[..self.len].contains(&entry).then_some(true)
+    }
+
+    fn insert(
+        &mut self,
+        level: usize,
+        key: &[u8],
+        _: &'a [u8],
+        signed_public_key: &[u8],
+        is_valid: bool,
+    ) {
+        if !is_valid || N == 0 {
+            return;
+        }
+        let entry = Self::entry(level, key, signed_public_key);
+        if self.len < N {
+            self.entries[self.len] = entry;
+            self.len += 1;
+        } else {
+            self.entries[self.next] = entry;
+            self.next = (self.next + 1) % N;
+        }
+    }
+}
+
+/**
+ * Verifies every `(message, signature)` of `items` under `public_key` and stores the result in
+ * the corresponding element of `results`. The signed public keys, which signatures of the same
+ * lower-level trees share, are only verified once.
+ */
+pub fn verify_batch<'a, H: HashChain>(
+    public_key: &InMemoryHssPublicKey<'a, H>,
+    items: &[(&'a [u8], &'a [u8])],
+    results: &mut [Result<(), Error>],
+) {
+    let mut verified_chains = VerifiedChains::default();
+
+    for ((message, signature_data), result) in items.iter().zip(results.iter_mut()) {
+        *result = InMemoryHssSignature::<H>::new(signature_data).and_then(|signature| {
+            let key = verify_signed_public_keys(
+                &signature,
+                public_key,
+                Some((signature_data, &mut verified_chains)),
+            )?;
+            verify_bottom_level(&signature, key, public_key, message)
+        });
+    }
+}
+
+/**
+ * Like [`verify_batch`], but verifies the bottom level of the signatures on `threads` threads.
+ * The signed public keys are verified beforehand.
+ */
+#[cfg(feature = "std")]
+pub fn verify_batch_parallel<'a, H: HashChain>(
+    public_key: &InMemoryHssPublicKey<'a, H>,
+    items: &[(&'a [u8], &'a [u8])],
+    results: &mut [Result<(), Error>],
+    threads: usize,
+) {
+    let mut verified_chains = VerifiedChains::default();
+
+    for ((_, signature_data), result) in items.iter().zip(results.iter_mut()) {
+        *result = InMemoryHssSignature::<H>::new(signature_data).and_then(|signature| {
+            verify_signed_public_keys(
+                &signature,
+                public_key,
+                Some((signature_data, &mut verified_chains)),
+            )
+            .map(|_| ())
+        });
+    }
+
+    let threads = threads.max(1);
+    let chunk_size = ((items.len() + threads - 1) / threads).max(1);
+    std::thread::scope(|scope| {
+        for (items, results) in items.chunks(chunk_size).zip(results.chunks_mut(chunk_size)) {
+            scope.spawn(move || {
+                for ((message, signature_data), result) in items.iter().zip(results.iter_mut()) {
+                    if result.is_err() {
+                        continue;
+                    }
+                    // The signed public keys were verified already
+                    *result =
+                        InMemoryHssSignature::<H>::new(signature_data).and_then(|signature| {
+                            let key = signature
+                                .signed_public_keys
+                                .last()
+                                .and_then(Option::as_ref)
+                                .map_or(&public_key.public_key, |signed_public_key| {
+                                    &signed_public_key.public_key
+                                });
+                            verify_bottom_level(&signature, key, public_key, message)
+                        });
+                }
+            });
+        }
+    });
+}
+
+fn verify_bottom_level<'a, H: HashChain>(
+    signature: &InMemoryHssSignature<'a, H>,
+    key: &InMemoryLmsPublicKey<'a, H>,
+    public_key: &InMemoryHssPublicKey<'a, H>,
+    message: &[u8],
+) -> Result<(), Error> {
+    lms::verify::verify(&signature.signature, key, message).map_err(|_| Error::VerificationFailed {
+        level: public_key.level - 1,
+    })
 }
 
 #[cfg(test)]
@@ -72,10 +367,10 @@ mod tests {
         message: &mut [u8],
     ) {
         let signature = if cfg!(feature = "fast_verify") {
-            HssSignature::sign(private_key, None, Some(message), &mut None)
+            HssSignature::sign(private_key, None, Some(message), &mut None, None)
                 .expect("Should sign message")
         } else {
-            HssSignature::sign(private_key, Some(message), None, &mut None)
+            HssSignature::sign(private_key, Some(message), None, &mut None, None)
                 .expect("Should sign message")
         };
 
@@ -91,4 +386,147 @@ mod tests {
 
         assert!(verify(&mem_sig, &mem_pub, message).is_err());
     }
+
+    #[test]
+    fn verify_batch_reports_every_item() {
+        use crate::{
+            constants::MAX_HSS_SIGNATURE_LENGTH,
+            hss::{hss_keygen, hss_sign, hss_verify, hss_verify_batch},
+            Error, LmotsAlgorithm, LmsAlgorithm,
+        };
+        use tinyvec::ArrayVec;
+
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let parameters = [
+            HssParameter::new(lmots, LmsAlgorithm::LmsH5),
+            HssParameter::new(lmots, LmsAlgorithm::LmsH2),
+        ];
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+
+        // Signatures of three bottom trees
+        let messages: [&[u8]; 10] = [b"0", b"1", b"2", b"3", b"4", b"5", b"6", b"7", b"8", b"9"];
+        let mut signatures: [ArrayVec<[u8; MAX_HSS_SIGNATURE_LENGTH]>; 10] = Default::default();
+        for (message, signature) in messages.iter().zip(signatures.iter_mut()) {
+            let hss_signature = hss_sign::<H>(message, &mut signing_key, None).unwrap();
+            signature.extend_from_slice(hss_signature.as_ref());
+        }
+
+        // Forged bottom and upper levels, a wrong message and a truncated signature
+        let signature_len = signatures[1].len();
+        signatures[1][signature_len - 1] ^= 1;
+        signatures[5][20] ^= 1;
+        signatures[9].truncate(signature_len - 1);
+        let mut items: [(&[u8], &[u8]); 10] = Default::default();
+        for (item, (message, signature)) in
+            items.iter_mut().zip(messages.iter().zip(signatures.iter()))
+        {
+            *item = (message, signature.as_slice());
+        }
+        items[7].0 = b"wrong message";
+
+        let mut results = [Ok(()); 10];
+        hss_verify_batch::<H>(verifying_key.as_slice(), &items, &mut results).unwrap();
+        for ((message, signature), result) in items.iter().zip(results.iter()) {
+            assert_eq!(
+                *result,
+                hss_verify::<H>(message, signature, verifying_key.as_slice())
+            );
+        }
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 6);
+        assert_eq!(results[5], Err(Error::VerificationFailed { level: 0 }));
+
+        #[cfg(feature = "std")]
+        {
+            let mut parallel_results = [Ok(()); 10];
+            crate::hss::hss_verify_batch_parallel::<H>(
+                verifying_key.as_slice(),
+                &items,
+                &mut parallel_results,
+                3,
+            )
+            .unwrap();
+            assert_eq!(parallel_results, results);
+        }
+
+        assert_eq!(
+            hss_verify_batch::<H>(verifying_key.as_slice(), &items, &mut results[1..]),
+            Err(Error::InvalidParameters)
+        );
+    }
+
+    #[test]
+    fn verification_cache_skips_verified_signed_public_keys() {
+        use crate::{
+            constants::MAX_HSS_SIGNATURE_LENGTH,
+            hss::{hss_keygen, hss_sign, verify::VerificationCache},
+            Error, LmotsAlgorithm, LmsAlgorithm,
+        };
+        use tinyvec::ArrayVec;
+
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let parameters = [
+            HssParameter::new(lmots, LmsAlgorithm::LmsH5),
+            HssParameter::new(lmots, LmsAlgorithm::LmsH2),
+            HssParameter::new(lmots, LmsAlgorithm::LmsH2),
+        ];
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let mut cache = VerificationCache::<H, 3>::new();
+
+        let mut signature = ArrayVec::<[u8; MAX_HSS_SIGNATURE_LENGTH]>::new();
+        signature.extend_from_slice(
+            hss_sign::<H>(b"0", &mut signing_key, None)
+                .unwrap()
+                .as_ref(),
+        );
+        let mut forged = signature;
+        assert!(verifying_key
+            .verify_with_cache(b"0", &signature, &mut cache)
+            .is_ok());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(
+            verifying_key.verify_with_cache(b"1", &signature, &mut cache),
+            Err(Error::VerificationFailed { level: 2 })
+        );
+
+        // A forged signed public key is never cached
+        forged[20] ^= 1;
+        assert_eq!(
+            verifying_key.verify_with_cache(b"0", &forged, &mut cache),
+            Err(Error::VerificationFailed { level: 0 })
+        );
+        assert_eq!(cache.len(), 2);
+
+        // Signatures of the same bottom tree share both entries
+        let mut signature = ArrayVec::<[u8; MAX_HSS_SIGNATURE_LENGTH]>::new();
+        signature.extend_from_slice(
+            hss_sign::<H>(b"1", &mut signing_key, None)
+                .unwrap()
+                .as_ref(),
+        );
+        assert!(verifying_key
+            .verify_with_cache(b"1", &signature, &mut cache)
+            .is_ok());
+        assert_eq!(cache.len(), 2);
+
+        // The next bottom tree adds one entry, replacing the oldest once the cache is full
+        for message in [b"2", b"3", b"4", b"5", b"6", b"7", b"8", b"9"] {
+            let mut signature = ArrayVec::<[u8; MAX_HSS_SIGNATURE_LENGTH]>::new();
+            signature.extend_from_slice(
+                hss_sign::<H>(message, &mut signing_key, None)
+                    .unwrap()
+                    .as_ref(),
+            );
+            assert!(verifying_key
+                .verify_with_cache(message, &signature, &mut cache)
+                .is_ok());
+        }
+        assert_eq!(cache.len(), 3);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
 }
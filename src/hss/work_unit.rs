@@ -0,0 +1,567 @@
+use core::convert::TryInto;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    constants::{Node, D_WORK_UNIT, ILEN, TREE_HEIGHTS},
+    hasher::HashChain,
+    hss::{
+        aux::{compute_hmac, compute_seed_derive, hss_optimal_aux_level, MutableExpandedAuxData},
+        parameter::HssParameter,
+        progress::NodeCache,
+        reference_impl_private_key::ReferenceImplPrivateKey,
+    },
+    lms::{definitions::LmsPrivateKey, helper::get_tree_element},
+    Error, Seed,
+};
+
+const WORK_UNIT_LMS_TYPE: usize = 0;
+const WORK_UNIT_LMOTS_TYPE: usize = 4;
+const WORK_UNIT_I: usize = 8;
+const WORK_UNIT_AUX_LEVEL: usize = WORK_UNIT_I + ILEN;
+const WORK_UNIT_SUBTREE_LEVEL: usize = WORK_UNIT_AUX_LEVEL + 4;
+const WORK_UNIT_FIRST_SUBTREE: usize = WORK_UNIT_SUBTREE_LEVEL + 4;
+const WORK_UNIT_SUBTREE_COUNT: usize = WORK_UNIT_FIRST_SUBTREE + 4;
+const WORK_UNIT_NODES: usize = WORK_UNIT_SUBTREE_COUNT + 4;
+
+/**
+ * Range of subtrees of the top-level tree, whose roots are computed by one machine.
+ *
+ * The top-level tree is split at `subtree_level` into `2^subtree_level` subtrees. The work units
+ * of all subtrees are computed with [`hss_keygen_work_unit`] and merged by
+ * [`crate::keygen_combine`].
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorkUnit {
+    /// Level of the subtree roots, starting with `0` at the root of the top-level tree.
+    pub subtree_level: u8,
+    /// Index of the first subtree within `subtree_level`, starting with `0` at the left.
+    pub first_subtree: u32,
+    /// Number of subtrees.
+    pub subtree_count: u32,
+}
+
+impl WorkUnit {
+    /**
+     * Splits the subtrees at `subtree_level` into at most `count` work units of nearly equal
+     * size. Returns [`Error::InvalidParameters`] if `subtree_level` exceeds the maximum height of
+     * the top-level tree. Levels below the chosen top-level tree are rejected by
+     * [`hss_work_unit_len`].
+     */
+    pub fn split(subtree_level: u8, count: u32) -> Result<impl Iterator<Item = WorkUnit>, Error> {
+        if subtree_level as usize > TREE_HEIGHTS[0] {
+            return Err(Error::InvalidParameters);
+        }
+
+        // Tree heights are at most 25, thus neither the products nor the indexes overflow
+        let subtrees = 1u64 << subtree_level;
+        let count = count as u64;
+
+        Ok((0..count)
+            .map(move |i| {
+                let first_subtree = subtrees * i / count;
+                let next_subtree = subtrees * (i + 1) / count;
+                WorkUnit {
+                    subtree_level,
+                    first_subtree: first_subtree as u32,
+                    subtree_count: (next_subtree - first_subtree) as u32,
+                }
+            })
+            .filter(|work_unit| work_unit.subtree_count > 0))
+    }
+
+    fn contains(&self, subtree: usize) -> bool {
+        let first_subtree = self.first_subtree as usize;
+        first_subtree <= subtree && subtree < first_subtree + self.subtree_count as usize
+    }
+
+    fn overlaps(&self, other: &WorkUnit) -> bool {
+        self.first_subtree < other.first_subtree + other.subtree_count
+            && other.first_subtree < self.first_subtree + self.subtree_count
+    }
+
+    /// Number of nodes of the work unit at `level`.
+    fn nodes_at(&self, level: usize) -> usize {
+        (self.subtree_count as usize) << (level - self.subtree_level as usize)
+    }
+
+    /// Aux data levels contained in the work unit.
+    fn aux_levels(&self, aux_level: u32, tree_height: usize) -> impl Iterator<Item = usize> {
+        (self.subtree_level as usize..=tree_height)
+            .filter(move |level| (aux_level >> level) & 1 == 1)
+    }
+}
+
+/**
+ * Returns the length of the serialized `work_unit`, see [`hss_keygen_work_unit`].
+ *
+ * Besides the subtree roots, the work unit contains the nodes of the subtrees stored in aux data of
+ * length `aux_data_len`, as chosen by the combining machine. Returns [`Error::InvalidParameters`]
+ * if `work_unit` does not fit into the top-level tree.
+ */
+pub fn hss_work_unit_len<H: HashChain>(
+    parameters: &[HssParameter<H>],
+    work_unit: &WorkUnit,
+    aux_data_len: usize,
+) -> Result<usize, Error> {
+    let lms_parameter = parameters
+        .first()
+        .ok_or(Error::InvalidParameters)?
+        .get_lms_parameter();
+    let tree_height = lms_parameter.get_tree_height();
+
+    if work_unit.subtree_level > tree_height
+        || work_unit.subtree_count == 0
+        || work_unit.first_subtree as u64 + work_unit.subtree_count as u64
+            > 1u64 << work_unit.subtree_level
+    {
+        return Err(Error::InvalidParameters);
+    }
+
+    let aux_level = hss_optimal_aux_level(aux_data_len, *lms_parameter, None);
+    let nodes = work_unit.subtree_count as usize
+        + work_unit
+            .aux_levels(aux_level, tree_height as usize)
+            .map(|level| work_unit.nodes_at(level))
+            .sum::<usize>();
+
+    Ok(WORK_UNIT_NODES + (nodes + 1) * H::OUTPUT_SIZE as usize)
+}
+
+/**
+ * Computes the subtree roots of `work_unit` and serializes them into `output`.
+ *
+ * `parameters` and `seed` have to be the same on every machine. `aux_data_len` is the length of
+ * the aux data passed to [`crate::keygen_combine`], or `0` if no aux data is used. Returns the
+ * length of the work unit, see [`hss_work_unit_len`].
+ *
+ * The work unit is authenticated with an HMAC keyed from `seed`.
+ *
+ * Layout: `u32str(lms type) || u32str(lmots type) || I || u32str(aux level) ||
+ * u32str(subtree level) || u32str(first subtree) || u32str(subtree count) || subtree roots ||
+ * aux data nodes of the subtrees || HMAC`
+ */
+pub fn hss_keygen_work_unit<H: HashChain>(
+    parameters: &[HssParameter<H>],
+    seed: &Seed<H>,
+    work_unit: &WorkUnit,
+    aux_data_len: usize,
+    output: &mut [u8],
+) -> Result<usize, Error> {
+    let work_unit_len = hss_work_unit_len(parameters, work_unit, aux_data_len)?;
+    if output.len() < work_unit_len {
+        return Err(Error::InvalidParameters);
+    }
+
+    let private_key = ReferenceImplPrivateKey::generate(parameters, seed)?;
+    let root_seed = private_key.generate_root_seed_and_lms_tree_identifier();
+    let lms_private_key = LmsPrivateKey::new(
+        root_seed.seed.clone(),
+        root_seed.lms_tree_identifier,
+        0,
+        *parameters[0].get_lmots_parameter(),
+        *parameters[0].get_lms_parameter(),
+    );
+
+    let tree_height = lms_private_key.lms_parameter.get_tree_height() as usize;
+    let aux_level = hss_optimal_aux_level(aux_data_len, lms_private_key.lms_parameter, None);
+    let size_hash = H::OUTPUT_SIZE as usize;
+
+    let output = &mut output[..work_unit_len];
+    output.fill(0);
+
+    let (header, data) = output.split_at_mut(WORK_UNIT_NODES);
+    header[WORK_UNIT_LMS_TYPE..WORK_UNIT_LMOTS_TYPE]
+        .copy_from_slice(&lms_private_key.lms_parameter.get_type_id().to_be_bytes());
+    header[WORK_UNIT_LMOTS_TYPE..WORK_UNIT_I]
+        .copy_from_slice(&lms_private_key.lmots_parameter.get_type_id().to_be_bytes());
+    header[WORK_UNIT_I..WORK_UNIT_AUX_LEVEL].copy_from_slice(&lms_private_key.lms_tree_identifier);
+    header[WORK_UNIT_AUX_LEVEL..WORK_UNIT_SUBTREE_LEVEL].copy_from_slice(&aux_level.to_be_bytes());
+    header[WORK_UNIT_SUBTREE_LEVEL..WORK_UNIT_FIRST_SUBTREE]
+        .copy_from_slice(&(work_unit.subtree_level as u32).to_be_bytes());
+    header[WORK_UNIT_FIRST_SUBTREE..WORK_UNIT_SUBTREE_COUNT]
+        .copy_from_slice(&work_unit.first_subtree.to_be_bytes());
+    header[WORK_UNIT_SUBTREE_COUNT..WORK_UNIT_NODES]
+        .copy_from_slice(&work_unit.subtree_count.to_be_bytes());
+
+    let (roots, mut data) = data.split_at_mut(work_unit.subtree_count as usize * size_hash);
+
+    // Aux data view covering only the subtrees of the work unit
+    let mut expanded_aux_data = MutableExpandedAuxData {
+        level: aux_level,
+        subtree_level: work_unit.subtree_level as usize,
+        subtree_index: work_unit.first_subtree as usize,
+        ..Default::default()
+    };
+    for level in work_unit.aux_levels(aux_level, tree_height) {
+        let (level_data, rest) = data.split_at_mut(work_unit.nodes_at(level) * size_hash);
+        expanded_aux_data.data[level] = Some(level_data);
+        data = rest;
+    }
+    let mut expanded_aux_data = Some(expanded_aux_data);
+
+    let first_index = (1usize << work_unit.subtree_level) + work_unit.first_subtree as usize;
+    for (offset, root) in roots.chunks_mut(size_hash).enumerate() {
+        let node = get_tree_element(
+            first_index + offset,
+            &lms_private_key,
+            &mut expanded_aux_data,
+        );
+        root.copy_from_slice(node.as_slice());
+    }
+
+    let (work_unit_data, hmac) = output.split_at_mut(work_unit_len - size_hash);
+    let key = compute_seed_derive::<H>(private_key.seed.as_slice(), D_WORK_UNIT);
+    hmac.copy_from_slice(compute_hmac::<H>(&key, work_unit_data).as_slice());
+
+    Ok(work_unit_len)
+}
+
+/**
+ * Subtree roots of all work units, used to compute the levels above them.
+ */
+pub(crate) struct WorkUnitNodes<'a, H: HashChain> {
+    work_units: &'a [&'a [u8]],
+    subtree_level: usize,
+    aux_level: u32,
+    tree_height: usize,
+    phantom_data: core::marker::PhantomData<H>,
+}
+
+impl<'a, H: HashChain> WorkUnitNodes<'a, H> {
+    /**
+     * Checks that `work_units` belong to `private_key` and cover all subtrees.
+     */
+    pub fn new(
+        work_units: &'a [&'a [u8]],
+        seed: &Seed<H>,
+        private_key: &LmsPrivateKey<H>,
+        aux_level: u32,
+    ) -> Result<Self, Error> {
+        let first_work_unit = work_units.first().ok_or(Error::IncompleteWorkUnits)?;
+        if first_work_unit.len() < WORK_UNIT_NODES {
+            return Err(Error::InvalidWorkUnit);
+        }
+
+        let nodes = Self {
+            work_units,
+            subtree_level: read_u32(first_work_unit, WORK_UNIT_SUBTREE_LEVEL) as usize,
+            aux_level,
+            tree_height: private_key.lms_parameter.get_tree_height() as usize,
+            phantom_data: core::marker::PhantomData,
+        };
+        if nodes.subtree_level > nodes.tree_height {
+            return Err(Error::InvalidWorkUnit);
+        }
+
+        let key = compute_seed_derive::<H>(seed.as_slice(), D_WORK_UNIT);
+        let size_hash = H::OUTPUT_SIZE as usize;
+
+        let mut subtrees = 0u64;
+        for (i, work_unit) in work_units.iter().enumerate() {
+            if work_unit.len() < WORK_UNIT_NODES + size_hash
+                || read_u32(work_unit, WORK_UNIT_LMS_TYPE)
+                    != private_key.lms_parameter.get_type_id()
+                || read_u32(work_unit, WORK_UNIT_LMOTS_TYPE)
+                    != private_key.lmots_parameter.get_type_id()
+                || work_unit[WORK_UNIT_I..WORK_UNIT_AUX_LEVEL] != private_key.lms_tree_identifier
+                || read_u32(work_unit, WORK_UNIT_AUX_LEVEL) != aux_level
+                || read_u32(work_unit, WORK_UNIT_SUBTREE_LEVEL) as usize != nodes.subtree_level
+            {
+                return Err(Error::InvalidWorkUnit);
+            }
+
+            let parsed_work_unit = parse_work_unit(work_unit);
+            if parsed_work_unit.subtree_count == 0
+                || parsed_work_unit.first_subtree as u64 + parsed_work_unit.subtree_count as u64
+                    > 1u64 << nodes.subtree_level
+                || work_unit.len() != nodes.work_unit_len(&parsed_work_unit)
+            {
+                return Err(Error::InvalidWorkUnit);
+            }
+
+            let (work_unit_data, hmac) = work_unit.split_at(work_unit.len() - size_hash);
+            if !bool::from(compute_hmac::<H>(&key, work_unit_data).ct_eq(hmac)) {
+                return Err(Error::InvalidWorkUnit);
+            }
+
+            if work_units[..i]
+                .iter()
+                .any(|other| parse_work_unit(other).overlaps(&parsed_work_unit))
+            {
+                return Err(Error::InvalidWorkUnit);
+            }
+            subtrees += parsed_work_unit.subtree_count as u64;
+        }
+
+        if subtrees != 1u64 << nodes.subtree_level {
+            return Err(Error::IncompleteWorkUnits);
+        }
+
+        Ok(nodes)
+    }
+
+    /**
+     * Copies the aux data nodes of the work units into `aux_data`.
+     */
+    pub fn copy_aux_data(&self, aux_data: &mut MutableExpandedAuxData) {
+        let size_hash = H::OUTPUT_SIZE as usize;
+
+        for work_unit in self.work_units {
+            let parsed_work_unit = parse_work_unit(work_unit);
+            let mut start = WORK_UNIT_NODES + parsed_work_unit.subtree_count as usize * size_hash;
+
+            for level in parsed_work_unit.aux_levels(self.aux_level, self.tree_height) {
+                let len = parsed_work_unit.nodes_at(level) * size_hash;
+                if let Some(level_data) = aux_data.data[level].as_mut() {
+                    let offset = ((parsed_work_unit.first_subtree as usize)
+                        << (level - self.subtree_level))
+                        * size_hash;
+                    level_data[offset..offset + len]
+                        .copy_from_slice(&work_unit[start..start + len]);
+                }
+                start += len;
+            }
+        }
+    }
+
+    fn work_unit_len(&self, work_unit: &WorkUnit) -> usize {
+        let nodes = work_unit.subtree_count as usize
+            + work_unit
+                .aux_levels(self.aux_level, self.tree_height)
+                .map(|level| work_unit.nodes_at(level))
+                .sum::<usize>();
+        WORK_UNIT_NODES + (nodes + 1) * H::OUTPUT_SIZE as usize
+    }
+}
+
+impl<'a, H: HashChain> NodeCache for WorkUnitNodes<'a, H> {
+    fn get_node(&self, index: usize) -> Option<Node> {
+        let level = core::mem::size_of::<usize>() * 8 - index.leading_zeros() as usize - 1;
+        if level != self.subtree_level {
+            return None;
+        }
+
+        let subtree = index - (1 << level);
+        let work_unit = self
+            .work_units
+            .iter()
+            .find(|work_unit| parse_work_unit(work_unit).contains(subtree))?;
+
+        let size_hash = H::OUTPUT_SIZE as usize;
+        let start = WORK_UNIT_NODES
+            + (subtree - read_u32(work_unit, WORK_UNIT_FIRST_SUBTREE) as usize) * size_hash;
+        Some(
+            work_unit[start..start + size_hash]
+                .iter()
+                .copied()
+                .collect(),
+        )
+    }
+
+    fn complete_node(&mut self, _index: usize, _node: &[u8]) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+fn parse_work_unit(work_unit: &[u8]) -> WorkUnit {
+    WorkUnit {
+        subtree_level: read_u32(work_unit, WORK_UNIT_SUBTREE_LEVEL) as u8,
+        first_subtree: read_u32(work_unit, WORK_UNIT_FIRST_SUBTREE),
+        subtree_count: read_u32(work_unit, WORK_UNIT_SUBTREE_COUNT),
+    }
+}
+
+fn read_u32(data: &[u8], start: usize) -> u32 {
+    u32::from_be_bytes(data[start..start + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hss::{hss_keygen, hss_keygen_combine},
+        util::helper::test_helper::gen_random_seed,
+        LmotsAlgorithm, LmsAlgorithm, Sha256_256,
+    };
+
+    type H = Sha256_256;
+
+    const AUX_DATA_LEN: usize = 1_000;
+    const MAX_WORK_UNIT_LEN: usize = 2_048;
+
+    fn parameters() -> [HssParameter<H>; 2] {
+        let lmots = LmotsAlgorithm::LmotsW2;
+        let lms = LmsAlgorithm::LmsH5;
+        [HssParameter::new(lmots, lms), HssParameter::new(lmots, lms)]
+    }
+
+    fn generate_work_units(
+        seed: &Seed<H>,
+        aux_data_len: usize,
+        buffers: &mut [[u8; MAX_WORK_UNIT_LEN]; 3],
+    ) -> [usize; 3] {
+        let parameters = parameters();
+        let mut lengths = [0; 3];
+        for (i, work_unit) in WorkUnit::split(3, 3).unwrap().enumerate() {
+            let len = hss_keygen_work_unit::<H>(
+                &parameters,
+                seed,
+                &work_unit,
+                aux_data_len,
+                &mut buffers[i],
+            )
+            .unwrap();
+            assert_eq!(
+                hss_work_unit_len(&parameters, &work_unit, aux_data_len),
+                Ok(len)
+            );
+            lengths[i] = len;
+        }
+        lengths
+    }
+
+    #[test]
+    fn split_work_units() {
+        let mut work_units = WorkUnit::split(3, 3).unwrap();
+        assert_eq!(
+            work_units.next(),
+            Some(WorkUnit {
+                subtree_level: 3,
+                first_subtree: 0,
+                subtree_count: 2
+            })
+        );
+        assert_eq!(work_units.map(|w| w.subtree_count).sum::<u32>(), 6);
+        assert_eq!(WorkUnit::split(1, 4).unwrap().count(), 2);
+        assert_eq!(
+            WorkUnit::split(TREE_HEIGHTS[0] as u8, 2).unwrap().last(),
+            Some(WorkUnit {
+                subtree_level: TREE_HEIGHTS[0] as u8,
+                first_subtree: 1 << (TREE_HEIGHTS[0] - 1),
+                subtree_count: 1 << (TREE_HEIGHTS[0] - 1)
+            })
+        );
+    }
+
+    #[test]
+    fn split_out_of_range() {
+        assert!(WorkUnit::split(TREE_HEIGHTS[0] as u8 + 1, 3).is_err());
+        assert!(WorkUnit::split(u8::MAX, u32::MAX).is_err());
+
+        // Levels within the maximum height, but below the top-level tree
+        let work_unit = WorkUnit::split(6, 1).unwrap().next().unwrap();
+        assert_eq!(
+            hss_work_unit_len(&parameters(), &work_unit, 0),
+            Err(Error::InvalidParameters)
+        );
+    }
+
+    #[test]
+    fn combine_work_units() {
+        let seed = gen_random_seed::<H>();
+        let parameters = parameters();
+
+        let mut aux_data = [0u8; AUX_DATA_LEN];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+        let keys = hss_keygen::<H>(&parameters, &seed, Some(aux_slice)).unwrap();
+
+        let mut buffers = [[0u8; MAX_WORK_UNIT_LEN]; 3];
+        let lengths = generate_work_units(&seed, AUX_DATA_LEN, &mut buffers);
+
+        // The order of the work units does not matter
+        let work_units = [
+            &buffers[2][..lengths[2]],
+            &buffers[0][..lengths[0]],
+            &buffers[1][..lengths[1]],
+        ];
+        let mut combined_aux_data = [0u8; AUX_DATA_LEN];
+        let combined_aux_slice: &mut &mut [u8] = &mut &mut combined_aux_data[..];
+        assert_eq!(
+            hss_keygen_combine::<H>(&parameters, &seed, &work_units, Some(combined_aux_slice)),
+            Ok(keys.clone())
+        );
+        assert_eq!(combined_aux_slice, aux_slice);
+
+        let mut buffers = [[0u8; MAX_WORK_UNIT_LEN]; 3];
+        let lengths = generate_work_units(&seed, 0, &mut buffers);
+        let work_units = [
+            &buffers[0][..lengths[0]],
+            &buffers[1][..lengths[1]],
+            &buffers[2][..lengths[2]],
+        ];
+        assert_eq!(
+            hss_keygen_combine::<H>(&parameters, &seed, &work_units, None),
+            Ok(keys)
+        );
+    }
+
+    #[test]
+    fn reject_partial_work_units() {
+        let seed = gen_random_seed::<H>();
+        let parameters = parameters();
+
+        let mut buffers = [[0u8; MAX_WORK_UNIT_LEN]; 3];
+        let lengths = generate_work_units(&seed, 0, &mut buffers);
+
+        let work_units = [&buffers[0][..lengths[0]], &buffers[2][..lengths[2]]];
+        assert_eq!(
+            hss_keygen_combine::<H>(&parameters, &seed, &work_units, None),
+            Err(Error::IncompleteWorkUnits)
+        );
+        assert_eq!(
+            hss_keygen_combine::<H>(&parameters, &seed, &[], None),
+            Err(Error::IncompleteWorkUnits)
+        );
+
+        let work_units = [
+            &buffers[0][..lengths[0]],
+            &buffers[1][..lengths[1]],
+            &buffers[1][..lengths[1]],
+        ];
+        assert_eq!(
+            hss_keygen_combine::<H>(&parameters, &seed, &work_units, None),
+            Err(Error::InvalidWorkUnit)
+        );
+    }
+
+    #[test]
+    fn reject_invalid_work_units() {
+        let seed = gen_random_seed::<H>();
+        let parameters = parameters();
+
+        let mut buffers = [[0u8; MAX_WORK_UNIT_LEN]; 3];
+        let lengths = generate_work_units(&seed, AUX_DATA_LEN, &mut buffers);
+
+        // Aux data of another length
+        let work_units = [
+            &buffers[0][..lengths[0]],
+            &buffers[1][..lengths[1]],
+            &buffers[2][..lengths[2]],
+        ];
+        assert_eq!(
+            hss_keygen_combine::<H>(&parameters, &seed, &work_units, None),
+            Err(Error::InvalidWorkUnit)
+        );
+
+        // Another seed
+        let other_seed = gen_random_seed::<H>();
+        let mut aux_data = [0u8; AUX_DATA_LEN];
+        let aux_slice: &mut &mut [u8] = &mut &mut aux_data[..];
+        assert_eq!(
+            hss_keygen_combine::<H>(&parameters, &other_seed, &work_units, Some(aux_slice)),
+            Err(Error::InvalidWorkUnit)
+        );
+
+        // Tampered subtree root
+        buffers[1][WORK_UNIT_NODES] ^= 1;
+        let work_units = [
+            &buffers[0][..lengths[0]],
+            &buffers[1][..lengths[1]],
+            &buffers[2][..lengths[2]],
+        ];
+        assert_eq!(
+            hss_keygen_combine::<H>(&parameters, &seed, &work_units, Some(aux_slice)),
+            Err(Error::InvalidWorkUnit)
+        );
+    }
+}
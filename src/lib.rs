@@ -85,15 +85,20 @@
 extern crate core;
 
 mod constants;
+mod error;
 mod hasher;
+#[cfg(feature = "hazmat")]
+pub mod hazmat;
 mod hss;
 mod lm_ots;
-mod lms;
+pub mod lms;
 mod util;
 
 // Re-export the `signature` crate
 pub use signature::{self};
 
+pub use crate::error::Error;
+
 #[doc(hidden)]
 pub use crate::constants::MAX_HASH_SIZE;
 #[doc(hidden)]
@@ -109,15 +114,39 @@ pub use crate::hss::parameter::HssParameter;
 pub use crate::lm_ots::parameters::LmotsAlgorithm;
 pub use crate::lms::parameters::LmsAlgorithm;
 
+pub use crate::hss::aux_data::{AuxData, AuxDataStatus};
+pub use crate::hss::checkpoint::{hss_checkpoint_len as keygen_checkpoint_len, CheckpointStore};
+pub use crate::hss::dyn_verify::{verify_any, DynVerifyingKey};
 pub use crate::hss::hss_keygen as keygen;
+pub use crate::hss::hss_keygen_combine as keygen_combine;
+#[cfg(feature = "std")]
+pub use crate::hss::hss_keygen_parallel as keygen_parallel;
+pub use crate::hss::hss_keygen_with_checkpoint as keygen_with_checkpoint;
+pub use crate::hss::hss_keygen_with_lms_tree_identifier as keygen_with_lms_tree_identifier;
+pub use crate::hss::hss_keygen_with_observer as keygen_with_observer;
 pub use crate::hss::hss_sign as sign;
 #[cfg(feature = "fast_verify")]
 pub use crate::hss::hss_sign_mut as sign_mut;
 pub use crate::hss::hss_verify as verify;
+pub use crate::hss::hss_verify_batch as verify_batch;
+#[cfg(feature = "std")]
+pub use crate::hss::hss_verify_batch_parallel as verify_batch_parallel;
+pub use crate::hss::key_usage::KeyUsage;
+pub use crate::hss::progress::{KeygenObserver, KeygenProgress};
+#[cfg(feature = "std")]
+pub use crate::hss::rollback::FileMonotonicCounter;
+pub use crate::hss::rollback::{MonotonicCounter, RollbackProtectedStateStore};
+#[cfg(feature = "std")]
+pub use crate::hss::state_store::FileStateStore;
+pub use crate::hss::state_store::{MonitoredStateStore, ReservedStateStore, StateStore};
+pub use crate::hss::stateful_signer::StatefulSigner;
+pub use crate::hss::verify::VerificationCache;
+pub use crate::hss::work_unit::{
+    hss_keygen_work_unit as keygen_work_unit, hss_work_unit_len as keygen_work_unit_len, WorkUnit,
+};
 pub use crate::hss::{SigningKey, VerifyingKey};
 
 use core::convert::TryFrom;
-use signature::Error;
 use tinyvec::ArrayVec;
 
 use constants::MAX_HSS_SIGNATURE_LENGTH;
@@ -134,7 +163,7 @@ pub struct Signature {
 
 impl Signature {
     pub(crate) fn from_bytes_verbose(bytes: &[u8], _hash_iterations: u32) -> Result<Self, Error> {
-        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::new())?;
+        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::MalformedSignature)?;
 
         Ok(Self {
             bytes,
@@ -151,8 +180,8 @@ impl AsRef<[u8]> for Signature {
 }
 
 impl signature::Signature for Signature {
-    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
-        Signature::from_bytes_verbose(bytes, 0)
+    fn from_bytes(bytes: &[u8]) -> Result<Self, signature::Error> {
+        Ok(Signature::from_bytes_verbose(bytes, 0)?)
     }
 }
 
@@ -179,8 +208,8 @@ impl<'a> AsRef<[u8]> for VerifierSignature<'a> {
 }
 
 impl<'a> signature::Signature for VerifierSignature<'a> {
-    fn from_bytes(_bytes: &[u8]) -> Result<Self, Error> {
-        Err(Error::new())
+    fn from_bytes(_bytes: &[u8]) -> Result<Self, signature::Error> {
+        Err(signature::Error::new())
     }
 }
 
@@ -96,7 +96,7 @@ mod tests {
         1,
         200,
         7,
-        1
+        5
     );
     generate_parameter_test!(
         lmots_sha256_n16_w1_parameter_test,
@@ -106,7 +106,7 @@ mod tests {
         1,
         136,
         7,
-        1
+        0xf000_0101
     );
     generate_parameter_test!(
         lmots_sha256_n32_w2_parameter_test,
@@ -126,7 +126,7 @@ mod tests {
         2,
         101,
         6,
-        2
+        6
     );
     generate_parameter_test!(
         lmots_sha256_n16_w2_parameter_test,
@@ -136,7 +136,7 @@ mod tests {
         2,
         68,
         6,
-        2
+        0xf000_0102
     );
     generate_parameter_test!(
         lmots_sha256_n32_w4_parameter_test,
@@ -156,7 +156,7 @@ mod tests {
         4,
         51,
         4,
-        3
+        7
     );
     generate_parameter_test!(
         lmots_sha256_n16_w4_parameter_test,
@@ -166,7 +166,7 @@ mod tests {
         4,
         35,
         4,
-        3
+        0xf000_0103
     );
     generate_parameter_test!(
         lmots_sha256_n32_w8_parameter_test,
@@ -186,7 +186,7 @@ mod tests {
         8,
         26,
         0,
-        4
+        8
     );
     generate_parameter_test!(
         lmots_sha256_n16_w8_parameter_test,
@@ -196,6 +196,6 @@ mod tests {
         8,
         18,
         0,
-        4
+        0xf000_0104
     );
 }
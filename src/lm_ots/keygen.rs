@@ -16,14 +16,18 @@ pub fn generate_private_key<H: HashChain>(
 
     let mut hasher = lmots_parameter.get_hasher();
 
+    // I || q || i || 0xff || SEED, of which only i changes
+    let mut data = ArrayVec::<[u8; ILEN + 4 + 2 + 1 + MAX_SEED_LEN]>::new();
+    data.extend_from_slice(&lms_tree_identifier);
+    data.extend_from_slice(&lms_leaf_identifier);
+    data.extend_from_slice(&[0, 0, 0xff]);
+    data.extend_from_slice(seed.as_slice());
+    let index_offset = ILEN + 4;
+
     for index in 0..lmots_parameter.get_num_winternitz_chains() {
-        hasher.update(&lms_tree_identifier);
-        hasher.update(&lms_leaf_identifier);
-        hasher.update(&index.to_be_bytes());
-        hasher.update(&[0xff]);
-        hasher.update(seed.as_slice());
+        data[index_offset..index_offset + 2].copy_from_slice(&index.to_be_bytes());
 
-        key.push(hasher.finalize_reset());
+        key.push(hasher.hash_short(&data));
     }
 
     LmotsPrivateKey::new(
@@ -11,7 +11,7 @@ use crate::{
 
 use crate::util::coef::coef_helper;
 
-/// Specifies the used Winternitz parameter.
+/// Specifies the used Winternitz parameter. The discriminants are the typecodes of SHA-256/N32.
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub enum LmotsAlgorithm {
     #[default]
@@ -40,28 +40,30 @@ impl LmotsAlgorithm {
     }
 
     pub fn construct_parameter<H: HashChain>(&self) -> Option<LmotsParameter<H>> {
+        let type_id = *self as u32 + H::LMOTS_TYPECODE_OFFSET;
+
         match *self {
             LmotsAlgorithm::LmotsReserved => None,
             LmotsAlgorithm::LmotsW1 => Some(LmotsParameter::new(
-                1,
+                type_id,
                 1,
                 get_num_winternitz_chains(1, H::OUTPUT_SIZE as usize) as u16,
                 7,
             )),
             LmotsAlgorithm::LmotsW2 => Some(LmotsParameter::new(
-                2,
+                type_id,
                 2,
                 get_num_winternitz_chains(2, H::OUTPUT_SIZE as usize) as u16,
                 6,
             )),
             LmotsAlgorithm::LmotsW4 => Some(LmotsParameter::new(
-                3,
+                type_id,
                 4,
                 get_num_winternitz_chains(4, H::OUTPUT_SIZE as usize) as u16,
                 4,
             )),
             LmotsAlgorithm::LmotsW8 => Some(LmotsParameter::new(
-                4,
+                type_id,
                 8,
                 get_num_winternitz_chains(8, H::OUTPUT_SIZE as usize) as u16,
                 0,
@@ -69,8 +71,12 @@ impl LmotsAlgorithm {
         }
     }
 
+    /**
+     * Returns the parameter of the typecode `_type`, if it is assigned to the hash function and
+     * output size of `H`.
+     */
     pub fn get_from_type<H: HashChain>(_type: u32) -> Option<LmotsParameter<H>> {
-        match _type {
+        match _type.checked_sub(H::LMOTS_TYPECODE_OFFSET)? {
             1 => LmotsAlgorithm::LmotsW1.construct_parameter(),
             2 => LmotsAlgorithm::LmotsW2.construct_parameter(),
             3 => LmotsAlgorithm::LmotsW4.construct_parameter(),
@@ -115,6 +121,10 @@ impl<H: HashChain> LmotsParameter<H> {
         self.type_id
     }
 
+    pub fn get_algorithm(&self) -> LmotsAlgorithm {
+        LmotsAlgorithm::from(self.type_id - H::LMOTS_TYPECODE_OFFSET)
+    }
+
     pub fn get_winternitz(&self) -> u8 {
         self.winternitz
     }
@@ -2,7 +2,8 @@ use crate::{
     constants::{D_MESG, MAX_HASH_SIZE, MAX_LMOTS_SIGNATURE_LENGTH, MAX_NUM_WINTERNITZ_CHAINS},
     hasher::HashChain,
     lm_ots::parameters::LmotsAlgorithm,
-    util::{coef::coef, helper::read_and_advance},
+    util::{coef::coef, helper::try_read_and_advance},
+    Error,
 };
 
 use core::convert::TryInto;
@@ -216,23 +217,32 @@ impl<H: HashChain> LmotsSignature<H> {
 }
 
 impl<'a, H: HashChain> InMemoryLmotsSignature<'a, H> {
-    pub fn new(data: &'a [u8]) -> Option<Self> {
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
         let mut index = 0;
 
-        let lmots_parameter = LmotsAlgorithm::get_from_type::<H>(u32::from_be_bytes(
-            read_and_advance(data, 4, &mut index).try_into().unwrap(),
-        ))
-        .unwrap();
+        let _type = u32::from_be_bytes(
+            try_read_and_advance(data, 4, &mut index, Error::MalformedSignature)?
+                .try_into()
+                .unwrap(),
+        );
+        let lmots_parameter =
+            LmotsAlgorithm::get_from_type::<H>(_type).ok_or(Error::UnsupportedTypecode(_type))?;
 
-        let signature_randomizer = read_and_advance(data, H::OUTPUT_SIZE as usize, &mut index);
+        let signature_randomizer = try_read_and_advance(
+            data,
+            H::OUTPUT_SIZE as usize,
+            &mut index,
+            Error::MalformedSignature,
+        )?;
 
-        let signature_data = read_and_advance(
+        let signature_data = try_read_and_advance(
             data,
             (H::OUTPUT_SIZE * lmots_parameter.get_num_winternitz_chains()) as usize,
             &mut index,
-        );
+            Error::MalformedSignature,
+        )?;
 
-        Some(Self {
+        Ok(Self {
             signature_randomizer,
             signature_data,
             lmots_parameter,
@@ -346,6 +356,7 @@ mod tests {
         lm_ots::{
             parameters::LmotsAlgorithm, signing::InMemoryLmotsSignature, signing::LmotsSignature,
         },
+        Error,
     };
 
     macro_rules! generate_test {
@@ -403,4 +414,22 @@ mod tests {
     generate_test!(lmots_shake256_n24_binary_representation_test, Shake256_192);
 
     generate_test!(lmots_shake256_n16_binary_representation_test, Shake256_256);
+
+    #[test]
+    fn reject_typecode_of_other_hasher() {
+        let lmots_parameter = LmotsAlgorithm::LmotsW8.construct_parameter::<Sha256_192>();
+        assert_eq!(lmots_parameter.unwrap().get_type_id(), 8);
+
+        let mut binary_rep = [0u8; 4 + 24 * (26 + 1)];
+        binary_rep[3] = 8;
+        assert!(InMemoryLmotsSignature::<Sha256_192>::new(&binary_rep).is_ok());
+        assert_eq!(
+            InMemoryLmotsSignature::<Sha256_256>::new(&binary_rep).err(),
+            Some(Error::UnsupportedTypecode(8))
+        );
+        assert_eq!(
+            InMemoryLmotsSignature::<Shake256_192>::new(&binary_rep).err(),
+            Some(Error::UnsupportedTypecode(8))
+        );
+    }
 }
@@ -27,15 +27,15 @@ struct HashChainArray<H: HashChain> {
 impl<H: HashChain> HashChainArray<H> {
     pub fn new(lmots_parameter: &LmotsParameter<H>) -> Self {
         let mut hash_chain_array = HashChainArray::<H>::default();
-        if LmotsAlgorithm::from(lmots_parameter.get_type_id()) == LmotsAlgorithm::LmotsW8 {
+        if lmots_parameter.get_algorithm() == LmotsAlgorithm::LmotsW8 {
             hash_chain_array.array_w8 = Some(ArrayVec::<
                 [ArrayVec<[u8; MAX_HASH_SIZE]>; get_num_winternitz_chains(8, MAX_HASH_SIZE)],
             >::default());
-        } else if LmotsAlgorithm::from(lmots_parameter.get_type_id()) == LmotsAlgorithm::LmotsW4 {
+        } else if lmots_parameter.get_algorithm() == LmotsAlgorithm::LmotsW4 {
             hash_chain_array.array_w4 = Some(ArrayVec::<
                 [ArrayVec<[u8; MAX_HASH_SIZE]>; get_num_winternitz_chains(4, MAX_HASH_SIZE)],
             >::default());
-        } else if LmotsAlgorithm::from(lmots_parameter.get_type_id()) == LmotsAlgorithm::LmotsW2 {
+        } else if lmots_parameter.get_algorithm() == LmotsAlgorithm::LmotsW2 {
             hash_chain_array.array_w2 = Some(ArrayVec::<
                 [ArrayVec<[u8; MAX_HASH_SIZE]>; get_num_winternitz_chains(2, MAX_HASH_SIZE)],
             >::default());
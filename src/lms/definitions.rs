@@ -1,12 +1,15 @@
 use crate::constants::*;
 use crate::hasher::HashChain;
+use crate::hss::progress::KeygenProgressTracker;
 use crate::lm_ots::definitions::LmotsPrivateKey;
 use crate::lm_ots::parameters::{LmotsAlgorithm, LmotsParameter};
-use crate::lms::helper::get_tree_element;
+#[cfg(feature = "std")]
+use crate::lms::helper::get_tree_root_parallel;
+use crate::lms::helper::{get_tree_element, get_tree_element_tracked};
 use crate::lms::parameters::LmsAlgorithm;
 use crate::lms::MutableExpandedAuxData;
-use crate::util::helper::read_and_advance;
-use crate::{lm_ots, Seed};
+use crate::util::helper::try_read_and_advance;
+use crate::{lm_ots, Error, Seed};
 
 use core::convert::TryInto;
 use tinyvec::ArrayVec;
@@ -42,11 +45,11 @@ impl<H: HashChain> LmsPrivateKey<H> {
         }
     }
 
-    pub fn use_lmots_private_key(&mut self) -> Result<LmotsPrivateKey<H>, ()> {
+    pub fn use_lmots_private_key(&mut self) -> Result<LmotsPrivateKey<H>, Error> {
         let number_of_lm_ots_keys = self.lms_parameter.number_of_lm_ots_keys();
 
         if self.used_leafs_index as usize >= number_of_lm_ots_keys {
-            return Err(());
+            return Err(Error::KeyExhausted);
         }
 
         let key = lm_ots::keygen::generate_private_key(
@@ -84,6 +87,37 @@ impl<H: HashChain> LmsPublicKey<H> {
         }
     }
 
+    pub fn new_tracked(
+        private_key: &LmsPrivateKey<H>,
+        aux_data: &mut Option<MutableExpandedAuxData>,
+        progress_tracker: &mut Option<KeygenProgressTracker>,
+    ) -> Result<Self, Error> {
+        let public_key = get_tree_element_tracked(1, private_key, aux_data, progress_tracker)?;
+
+        Ok(Self {
+            key: public_key,
+            lms_tree_identifier: private_key.lms_tree_identifier,
+            lmots_parameter: private_key.lmots_parameter,
+            lms_parameter: private_key.lms_parameter,
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn new_parallel(
+        private_key: &LmsPrivateKey<H>,
+        aux_data: &mut Option<MutableExpandedAuxData>,
+        threads: usize,
+    ) -> Self {
+        let public_key = get_tree_root_parallel(private_key, aux_data, threads);
+
+        Self {
+            key: public_key,
+            lms_tree_identifier: private_key.lms_tree_identifier,
+            lmots_parameter: private_key.lmots_parameter,
+            lms_parameter: private_key.lms_parameter,
+        }
+    }
+
     pub fn to_binary_representation(&self) -> ArrayVec<[u8; MAX_LMS_PUBLIC_KEY_LENGTH]> {
         let mut result = ArrayVec::new();
 
@@ -118,24 +152,34 @@ impl<'a, H: HashChain> PartialEq<LmsPublicKey<H>> for InMemoryLmsPublicKey<'a, H
 }
 
 impl<'a, H: HashChain> InMemoryLmsPublicKey<'a, H> {
-    pub fn new(data: &'a [u8]) -> Option<Self> {
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
         // Parsing like desribed in 5.4.2
         let mut data_index = 0;
 
-        let lms_parameter = LmsAlgorithm::get_from_type(u32::from_be_bytes(
-            read_and_advance(data, 4, &mut data_index)
+        let lms_type = u32::from_be_bytes(
+            try_read_and_advance(data, 4, &mut data_index, Error::MalformedPublicKey)?
                 .try_into()
                 .unwrap(),
-        ))?;
-        let lmots_parameter = LmotsAlgorithm::get_from_type(u32::from_be_bytes(
-            read_and_advance(data, 4, &mut data_index)
+        );
+        let lms_parameter =
+            LmsAlgorithm::get_from_type(lms_type).ok_or(Error::UnsupportedTypecode(lms_type))?;
+        let lmots_type = u32::from_be_bytes(
+            try_read_and_advance(data, 4, &mut data_index, Error::MalformedPublicKey)?
                 .try_into()
                 .unwrap(),
-        ))?;
-        let lms_tree_identifier = read_and_advance(data, 16, &mut data_index);
-        let key = read_and_advance(data, H::OUTPUT_SIZE.into(), &mut data_index);
+        );
+        let lmots_parameter = LmotsAlgorithm::get_from_type(lmots_type)
+            .ok_or(Error::UnsupportedTypecode(lmots_type))?;
+        let lms_tree_identifier =
+            try_read_and_advance(data, 16, &mut data_index, Error::MalformedPublicKey)?;
+        let key = try_read_and_advance(
+            data,
+            H::OUTPUT_SIZE.into(),
+            &mut data_index,
+            Error::MalformedPublicKey,
+        )?;
 
-        Some(Self {
+        Ok(Self {
             lmots_parameter,
             lms_parameter,
             lms_tree_identifier,
@@ -152,12 +196,18 @@ impl<'a, H: HashChain> InMemoryLmsPublicKey<'a, H> {
 #[cfg(test)]
 mod tests {
     use crate::{
+        hasher::{
+            sha256::{Sha256_128, Sha256_192, Sha256_256},
+            shake256::{Shake256_128, Shake256_192, Shake256_256},
+            HashChain,
+        },
         lm_ots::parameters::LmotsAlgorithm,
         lms::{
             definitions::{InMemoryLmsPublicKey, LmsPrivateKey, LmsPublicKey},
             parameters::LmsAlgorithm,
             SeedAndLmsTreeIdentifier,
         },
+        Error,
     };
 
     use rand::{rngs::OsRng, RngCore};
@@ -182,4 +232,83 @@ mod tests {
 
         assert!(deserialized == public_key);
     }
+
+    #[test]
+    fn test_public_key_typecodes() {
+        let mut seed_and_lms_tree_identifier = SeedAndLmsTreeIdentifier::<Shake256_192>::default();
+        OsRng.fill_bytes(seed_and_lms_tree_identifier.seed.as_mut_slice());
+        let private_key = LmsPrivateKey::new(
+            seed_and_lms_tree_identifier.seed.clone(),
+            seed_and_lms_tree_identifier.lms_tree_identifier,
+            0,
+            LmotsAlgorithm::LmotsW1.construct_parameter().unwrap(),
+            LmsAlgorithm::LmsH5.construct_parameter().unwrap(),
+        );
+
+        let public_key = LmsPublicKey::new(&private_key, &mut None);
+        let serialized = public_key.to_binary_representation();
+
+        // SHAKE256/M24 with H5 and SHAKE256/N24 with W1 as assigned by NIST SP 800-208
+        assert_eq!(&serialized[..8], &[0, 0, 0, 0x14, 0, 0, 0, 0x0d]);
+        assert!(InMemoryLmsPublicKey::<Shake256_192>::new(serialized.as_slice()).is_ok());
+        assert_eq!(
+            InMemoryLmsPublicKey::<Sha256_192>::new(serialized.as_slice()).err(),
+            Some(Error::UnsupportedTypecode(0x14))
+        );
+
+        let mut wrong_lmots_type = serialized;
+        wrong_lmots_type[7] = 0x05;
+        assert_eq!(
+            InMemoryLmsPublicKey::<Shake256_192>::new(wrong_lmots_type.as_slice()).err(),
+            Some(Error::UnsupportedTypecode(0x05))
+        );
+    }
+
+    #[test]
+    fn test_private_typecodes() {
+        let mut seed_and_lms_tree_identifier = SeedAndLmsTreeIdentifier::<Sha256_128>::default();
+        OsRng.fill_bytes(seed_and_lms_tree_identifier.seed.as_mut_slice());
+        let private_key = LmsPrivateKey::new(
+            seed_and_lms_tree_identifier.seed.clone(),
+            seed_and_lms_tree_identifier.lms_tree_identifier,
+            0,
+            LmotsAlgorithm::LmotsW1.construct_parameter().unwrap(),
+            LmsAlgorithm::LmsH5.construct_parameter().unwrap(),
+        );
+
+        let public_key = LmsPublicKey::new(&private_key, &mut None);
+        let serialized = public_key.to_binary_representation();
+
+        // No typecodes are registered for 128 bit outputs
+        assert_eq!(
+            &serialized[..8],
+            &[0xf0, 0, 0x01, 0x05, 0xf0, 0, 0x01, 0x01]
+        );
+        assert!(InMemoryLmsPublicKey::<Sha256_128>::new(serialized.as_slice()).is_ok());
+        assert_eq!(
+            InMemoryLmsPublicKey::<Sha256_256>::new(serialized.as_slice()).err(),
+            Some(Error::UnsupportedTypecode(0xf000_0105))
+        );
+
+        // The test-only tree height stays out of the registered typecodes
+        fn assert_private_h2<H: HashChain>() {
+            let type_id = LmsAlgorithm::LmsH2
+                .construct_parameter::<H>()
+                .unwrap()
+                .get_type_id();
+            assert!(type_id >= 0xdddd_dddd);
+            assert!(matches!(
+                LmsAlgorithm::get_from_type::<H>(type_id)
+                    .map(|parameter| parameter.get_algorithm()),
+                Some(LmsAlgorithm::LmsH2)
+            ));
+        }
+        assert_private_h2::<Sha256_256>();
+        assert_private_h2::<Sha256_192>();
+        assert_private_h2::<Sha256_128>();
+        assert_private_h2::<Shake256_256>();
+        assert_private_h2::<Shake256_192>();
+        assert_private_h2::<Shake256_128>();
+        assert!(LmsAlgorithm::get_from_type::<Sha256_192>(6).is_none());
+    }
 }
@@ -1,9 +1,13 @@
 use tinyvec::ArrayVec;
 
-use crate::constants::{D_INTR, D_LEAF, MAX_HASH_SIZE};
+use crate::constants::{D_INTR, D_LEAF, ILEN, MAX_HASH_SIZE, MAX_TREE_HEIGHT};
 use crate::hasher::HashChain;
+#[cfg(feature = "std")]
+use crate::hss::aux::hss_split_aux_data;
 use crate::hss::aux::{hss_extract_aux_data, hss_save_aux_data, MutableExpandedAuxData};
+use crate::hss::progress::KeygenProgressTracker;
 use crate::lm_ots;
+use crate::Error;
 
 use super::definitions::LmsPrivateKey;
 
@@ -12,18 +16,44 @@ pub fn get_tree_element<H: HashChain>(
     private_key: &LmsPrivateKey<H>,
     aux_data: &mut Option<MutableExpandedAuxData>,
 ) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+    // Without a progress tracker the computation can not be cancelled
+    get_tree_element_tracked(index, private_key, aux_data, &mut None).unwrap()
+}
+
+/**
+ * Like `get_tree_element`, but reports every processed leaf to `progress_tracker`, which may
+ * cancel the computation.
+ */
+pub fn get_tree_element_tracked<H: HashChain>(
+    index: usize,
+    private_key: &LmsPrivateKey<H>,
+    aux_data: &mut Option<MutableExpandedAuxData>,
+    progress_tracker: &mut Option<KeygenProgressTracker>,
+) -> Result<ArrayVec<[u8; MAX_HASH_SIZE]>, Error> {
+    let max_private_keys = private_key.lms_parameter.number_of_lm_ots_keys();
+
     // Check if we already have the value cached
     if let Some(aux_data) = aux_data {
         if let Some(result) = hss_extract_aux_data::<H>(aux_data, index) {
-            return result;
+            if let Some(progress_tracker) = progress_tracker {
+                let level = usize::BITS - 1 - index.leading_zeros();
+                progress_tracker.advance((max_private_keys >> level) as u64)?;
+            }
+            return Ok(result);
         }
     }
 
-    let max_private_keys = private_key.lms_parameter.number_of_lm_ots_keys();
-
-    let hasher = H::default()
-        .chain(private_key.lms_tree_identifier)
-        .chain((index as u32).to_be_bytes());
+    // Nodes from a checkpoint or of another machine
+    if let Some(progress_tracker) = progress_tracker {
+        if let Some(result) = progress_tracker.get_cached_node(index) {
+            if let Some(expanded_aux_data) = aux_data.as_mut() {
+                hss_save_aux_data::<H>(expanded_aux_data, index, result.as_slice());
+            }
+            let level = usize::BITS - 1 - index.leading_zeros();
+            progress_tracker.advance((max_private_keys >> level) as u64)?;
+            return Ok(result);
+        }
+    }
 
     let result = if index >= max_private_keys {
         let lms_ots_private_key = lm_ots::keygen::generate_private_key(
@@ -34,24 +64,145 @@ pub fn get_tree_element<H: HashChain>(
         );
         let lm_ots_public_key = lm_ots::keygen::generate_public_key(&lms_ots_private_key);
 
-        hasher
-            .chain(D_LEAF)
-            .chain(lm_ots_public_key.key.as_slice())
-            .finalize()
+        if let Some(progress_tracker) = progress_tracker {
+            progress_tracker.advance(1)?;
+        }
+
+        let mut data = ArrayVec::<[u8; NODE_PREFIX_LEN + MAX_HASH_SIZE]>::new();
+        data.extend_from_slice(&node_prefix(index, private_key, D_LEAF));
+        data.extend_from_slice(lm_ots_public_key.key.as_slice());
+        H::default().hash_short(&data)
     } else {
-        let left = get_tree_element(2 * index, private_key, aux_data);
-        let right = get_tree_element(2 * index + 1, private_key, aux_data);
-
-        hasher
-            .chain(D_INTR)
-            .chain(left.as_slice())
-            .chain(right.as_slice())
-            .finalize()
+        let left = get_tree_element_tracked(2 * index, private_key, aux_data, progress_tracker)?;
+        let right =
+            get_tree_element_tracked(2 * index + 1, private_key, aux_data, progress_tracker)?;
+
+        get_intermediate_node(index, private_key, &left, &right)
     };
 
     if let Some(expanded_aux_data) = aux_data.as_mut() {
         hss_save_aux_data::<H>(expanded_aux_data, index, result.as_slice());
     }
 
-    result
+    if let Some(progress_tracker) = progress_tracker {
+        progress_tracker.complete_node(index, result.as_slice())?;
+    }
+
+    Ok(result)
+}
+
+pub fn get_intermediate_node<H: HashChain>(
+    index: usize,
+    private_key: &LmsPrivateKey<H>,
+    left: &[u8],
+    right: &[u8],
+) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+    let mut data = ArrayVec::<[u8; NODE_PREFIX_LEN + 2 * MAX_HASH_SIZE]>::new();
+    data.extend_from_slice(&node_prefix(index, private_key, D_INTR));
+    data.extend_from_slice(left);
+    data.extend_from_slice(right);
+    H::default().hash_short(&data)
+}
+
+/// Length of `I || r || D_LEAF` and `I || r || D_INTR`.
+const NODE_PREFIX_LEN: usize = ILEN + 4 + 2;
+
+fn node_prefix<H: HashChain>(
+    index: usize,
+    private_key: &LmsPrivateKey<H>,
+    separator: [u8; 2],
+) -> [u8; NODE_PREFIX_LEN] {
+    let mut prefix = [0u8; NODE_PREFIX_LEN];
+    prefix[..ILEN].copy_from_slice(&private_key.lms_tree_identifier);
+    prefix[ILEN..ILEN + 4].copy_from_slice(&(index as u32).to_be_bytes());
+    prefix[ILEN + 4..].copy_from_slice(&separator);
+    prefix
+}
+
+/**
+ * Computes the root of the tree like `get_tree_element(1, ..)`, but splits the tree into subtrees
+ * whose roots are computed on `threads` threads. Every thread fills the auxiliary data of its
+ * subtrees, the levels above are filled afterwards.
+ */
+#[cfg(feature = "std")]
+pub fn get_tree_root_parallel<H: HashChain>(
+    private_key: &LmsPrivateKey<H>,
+    aux_data: &mut Option<MutableExpandedAuxData>,
+    threads: usize,
+) -> ArrayVec<[u8; MAX_HASH_SIZE]> {
+    let tree_height = private_key.lms_parameter.get_tree_height() as usize;
+    // Smallest level with at least one subtree per thread
+    let subtree_level = (usize::BITS - threads.saturating_sub(1).leading_zeros()) as usize;
+    let subtree_level = subtree_level.min(tree_height);
+
+    if subtree_level == 0 {
+        return get_tree_element(1, private_key, aux_data);
+    }
+
+    let subtree_count = 1usize << subtree_level;
+    let mut subtree_aux_data: Vec<Option<MutableExpandedAuxData>> = match aux_data.as_mut() {
+        Some(aux_data) => hss_split_aux_data::<H>(aux_data, subtree_level)
+            .into_iter()
+            .map(Some)
+            .collect(),
+        None => (0..subtree_count).map(|_| None).collect(),
+    };
+
+    let mut nodes: Vec<ArrayVec<[u8; MAX_HASH_SIZE]>> = vec![ArrayVec::new(); subtree_count];
+    std::thread::scope(|scope| {
+        let worker_count = threads.min(subtree_count);
+        let mut workers: Vec<Vec<_>> = (0..worker_count).map(|_| Vec::new()).collect();
+        for (subtree_index, subtree) in nodes
+            .iter_mut()
+            .zip(subtree_aux_data.iter_mut())
+            .enumerate()
+        {
+            workers[subtree_index % worker_count].push((subtree_count + subtree_index, subtree));
+        }
+
+        for subtrees in workers {
+            scope.spawn(move || {
+                for (index, (node, aux_data)) in subtrees {
+                    *node = get_tree_element(index, private_key, aux_data);
+                }
+            });
+        }
+    });
+
+    for level in (0..subtree_level).rev() {
+        let first_index = 1usize << level;
+        nodes = nodes
+            .chunks(2)
+            .enumerate()
+            .map(|(offset, children)| {
+                let index = first_index + offset;
+                let node = get_intermediate_node(index, private_key, &children[0], &children[1]);
+                if let Some(expanded_aux_data) = aux_data.as_mut() {
+                    hss_save_aux_data::<H>(expanded_aux_data, index, node.as_slice());
+                }
+                node
+            })
+            .collect();
+    }
+
+    nodes.remove(0)
+}
+
+pub fn get_authentication_path<H: HashChain>(
+    lms_leaf_identifier: u32,
+    private_key: &LmsPrivateKey<H>,
+    aux_data: &mut Option<MutableExpandedAuxData>,
+) -> ArrayVec<[ArrayVec<[u8; MAX_HASH_SIZE]>; MAX_TREE_HEIGHT]> {
+    let tree_height = private_key.lms_parameter.get_tree_height();
+    let leaf_index =
+        private_key.lms_parameter.number_of_lm_ots_keys() + lms_leaf_identifier as usize;
+
+    let mut authentication_path = ArrayVec::new();
+
+    for i in 0..tree_height.into() {
+        let tree_index = (leaf_index / (2usize.pow(i as u32))) ^ 0x1;
+        authentication_path.push(get_tree_element(tree_index, private_key, aux_data));
+    }
+
+    authentication_path
 }
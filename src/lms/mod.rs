@@ -1,22 +1,61 @@
-use crate::hasher::HashChain;
-use crate::hss::aux::MutableExpandedAuxData;
-use crate::hss::parameter::HssParameter;
-use crate::hss::reference_impl_private_key::SeedAndLmsTreeIdentifier;
-use crate::lms::definitions::LmsPrivateKey;
-use crate::lms::definitions::LmsPublicKey;
-
-pub mod definitions;
-mod helper;
-pub mod parameters;
-pub mod signing;
-pub mod verify;
-
-pub struct LmsKeyPair<H: HashChain> {
+//! Single-tree LMS as defined in [RFC 8554 Section 5](<https://datatracker.ietf.org/doc/html/rfc8554#section-5>).
+//!
+//! LMS public keys and signatures are used without the HSS headers, i.e. the number of levels `L`
+//! of the public key and the number of signed public keys `Nspk` of the signature. The private key
+//! format is the one of a single-level HSS key, thus every [`StateStore`] can be used.
+//!
+//! # Example
+//! ```
+//! use rand::{rngs::OsRng, RngCore};
+//! use hbs_lms::{
+//!     lms, HssParameter, LmotsAlgorithm, LmsAlgorithm, Seed, Sha256_256,
+//!     signature::{SignerMut, Verifier},
+//! };
+//!
+//! let parameter = HssParameter::<Sha256_256>::new(LmotsAlgorithm::LmotsW4, LmsAlgorithm::LmsH5);
+//! let mut seed = Seed::default();
+//! OsRng.fill_bytes(seed.as_mut_slice());
+//!
+//! let (mut signing_key, verifying_key) = lms::keygen(parameter, &seed, None).unwrap();
+//!
+//! let signature = signing_key.try_sign(b"message").unwrap();
+//! assert!(verifying_key.verify(b"message", &signature).is_ok());
+//! ```
+
+use core::{convert::TryFrom, marker::PhantomData};
+use tinyvec::ArrayVec;
+
+use crate::{
+    constants::{
+        LmsTreeIdentifier, MAX_LMS_PUBLIC_KEY_LENGTH, MAX_LMS_SIGNATURE_LENGTH,
+        MAX_PRIVATE_KEY_SIZE,
+    },
+    hasher::HashChain,
+    hss::{
+        aux::MutableExpandedAuxData,
+        hss_keygen, hss_keygen_with_lms_tree_identifier, hss_sign,
+        parameter::HssParameter,
+        reference_impl_private_key::{ReferenceImplPrivateKey, SeedAndLmsTreeIdentifier},
+        state_store::StateStore,
+    },
+    lms::definitions::{InMemoryLmsPublicKey, LmsPrivateKey, LmsPublicKey},
+    signature::{SignerMut, Verifier},
+    Error, KeyUsage, Seed, SigningKey, VerifyingKey,
+};
+
+pub(crate) mod definitions;
+pub(crate) mod helper;
+pub(crate) mod parameters;
+pub(crate) mod signing;
+pub(crate) mod traversal;
+pub(crate) mod verify;
+
+pub(crate) struct LmsKeyPair<H: HashChain> {
     pub private_key: LmsPrivateKey<H>,
     pub public_key: LmsPublicKey<H>,
 }
 
-pub fn generate_key_pair<H: HashChain>(
+pub(crate) fn generate_key_pair<H: HashChain>(
     seed: &SeedAndLmsTreeIdentifier<H>,
     parameter: &HssParameter<H>,
     used_leafs_index: &u32,
@@ -39,3 +78,396 @@ pub fn generate_key_pair<H: HashChain>(
         public_key,
     }
 }
+
+/// Value of `L` of a single-level HSS public key.
+const SINGLE_LEVEL: [u8; 4] = 1u32.to_be_bytes();
+/// Value of `Nspk` of a single-level HSS signature.
+const NO_SIGNED_PUBLIC_KEYS: [u8; 4] = 0u32.to_be_bytes();
+
+/**
+ * LMS signature according to RFC 8554 Section 5.4, implementing [`signature::Signature`].
+ */
+#[derive(Debug)]
+pub struct LmsSignature {
+    bytes: ArrayVec<[u8; MAX_LMS_SIGNATURE_LENGTH]>,
+}
+
+impl AsRef<[u8]> for LmsSignature {
+    fn as_ref(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+}
+
+impl signature::Signature for LmsSignature {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, signature::Error> {
+        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::MalformedSignature)?;
+        Ok(Self { bytes })
+    }
+}
+
+/**
+ * Strips `Nspk` of a single-level HSS signature. Fails with [`Error::InvalidParameters`] for
+ * signatures of more than one level.
+ */
+impl TryFrom<crate::Signature> for LmsSignature {
+    type Error = Error;
+
+    fn try_from(signature: crate::Signature) -> Result<Self, Error> {
+        let bytes = signature.as_ref();
+        if bytes.len() < NO_SIGNED_PUBLIC_KEYS.len() {
+            return Err(Error::MalformedSignature);
+        }
+        if bytes[..NO_SIGNED_PUBLIC_KEYS.len()] != NO_SIGNED_PUBLIC_KEYS {
+            return Err(Error::InvalidParameters);
+        }
+
+        let bytes = ArrayVec::try_from(&bytes[NO_SIGNED_PUBLIC_KEYS.len()..])
+            .map_err(|_| Error::MalformedSignature)?;
+        Ok(Self { bytes })
+    }
+}
+
+/**
+ * Prepends `Nspk = 0`, resulting in a single-level HSS signature.
+ */
+impl From<LmsSignature> for crate::Signature {
+    fn from(signature: LmsSignature) -> Self {
+        let mut bytes = ArrayVec::<[u8; MAX_LMS_SIGNATURE_LENGTH + 4]>::new();
+        bytes.extend_from_slice(&NO_SIGNED_PUBLIC_KEYS);
+        bytes.extend_from_slice(signature.as_ref());
+
+        crate::Signature::from_bytes_verbose(&bytes, 0).unwrap()
+    }
+}
+
+/**
+ * Single-tree signing key, implementing [`SignerMut`] using [`LmsSignature`].
+ *
+ * The key uses the private key format of a single-level [`SigningKey`].
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LmsSigningKey<H: HashChain> {
+    pub(crate) signing_key: SigningKey<H>,
+}
+
+impl<H: HashChain> LmsSigningKey<H> {
+    /**
+     * Fails with [`Error::InvalidParameters`] if the key has more than one level.
+     */
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::try_from(SigningKey::from_bytes(bytes)?)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.signing_key.as_slice()
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.signing_key.as_mut_slice()
+    }
+
+    pub fn usage(&self) -> Result<KeyUsage, Error> {
+        self.signing_key.usage()
+    }
+
+    pub fn try_sign_with_aux(
+        &mut self,
+        msg: &[u8],
+        aux_data: Option<&mut &mut [u8]>,
+    ) -> Result<LmsSignature, Error> {
+        sign::<H>(msg, self, aux_data)
+    }
+}
+
+/**
+ * Fails with [`Error::InvalidParameters`] if the key has more than one level.
+ */
+impl<H: HashChain> TryFrom<SigningKey<H>> for LmsSigningKey<H> {
+    type Error = Error;
+
+    fn try_from(signing_key: SigningKey<H>) -> Result<Self, Error> {
+        if signing_key.usage()?.leaf_indexes.len() != 1 {
+            return Err(Error::InvalidParameters);
+        }
+
+        Ok(Self { signing_key })
+    }
+}
+
+impl<H: HashChain> From<LmsSigningKey<H>> for SigningKey<H> {
+    fn from(signing_key: LmsSigningKey<H>) -> Self {
+        signing_key.signing_key
+    }
+}
+
+impl<H: HashChain> SignerMut<LmsSignature> for LmsSigningKey<H> {
+    fn try_sign(&mut self, msg: &[u8]) -> Result<LmsSignature, signature::Error> {
+        Ok(self.try_sign_with_aux(msg, None)?)
+    }
+}
+
+/**
+ * LMS public key according to RFC 8554 Section 5.3, implementing [`Verifier`] using
+ * [`LmsSignature`].
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LmsVerifyingKey<H: HashChain> {
+    bytes: ArrayVec<[u8; MAX_LMS_PUBLIC_KEY_LENGTH]>,
+    phantom_data: PhantomData<H>,
+}
+
+impl<H: HashChain> LmsVerifyingKey<H> {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let bytes = ArrayVec::try_from(bytes).map_err(|_| Error::MalformedPublicKey)?;
+
+        Ok(Self {
+            bytes,
+            phantom_data: PhantomData,
+        })
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+}
+
+/**
+ * Strips `L` of a single-level HSS public key. Fails with [`Error::InvalidParameters`] for
+ * public keys of more than one level.
+ */
+impl<H: HashChain> TryFrom<VerifyingKey<H>> for LmsVerifyingKey<H> {
+    type Error = Error;
+
+    fn try_from(verifying_key: VerifyingKey<H>) -> Result<Self, Error> {
+        let bytes = verifying_key.as_slice();
+        if bytes.len() < SINGLE_LEVEL.len() {
+            return Err(Error::MalformedPublicKey);
+        }
+        if bytes[..SINGLE_LEVEL.len()] != SINGLE_LEVEL {
+            return Err(Error::InvalidParameters);
+        }
+
+        Self::from_bytes(&bytes[SINGLE_LEVEL.len()..])
+    }
+}
+
+/**
+ * Prepends `L = 1`, resulting in a single-level HSS public key.
+ */
+impl<H: HashChain> From<LmsVerifyingKey<H>> for VerifyingKey<H> {
+    fn from(verifying_key: LmsVerifyingKey<H>) -> Self {
+        let mut bytes = ArrayVec::<[u8; MAX_LMS_PUBLIC_KEY_LENGTH + 4]>::new();
+        bytes.extend_from_slice(&SINGLE_LEVEL);
+        bytes.extend_from_slice(verifying_key.as_slice());
+
+        VerifyingKey::from_bytes(&bytes).unwrap()
+    }
+}
+
+impl<H: HashChain> Verifier<LmsSignature> for LmsVerifyingKey<H> {
+    fn verify(&self, msg: &[u8], signature: &LmsSignature) -> Result<(), signature::Error> {
+        Ok(verify::<H>(msg, signature.as_ref(), &self.bytes)?)
+    }
+}
+
+/**
+ * Generate [`LmsSigningKey`] and [`LmsVerifyingKey`].
+ *
+ * # Arguments
+ *
+ * * `HashChain` - The hasher implementation that should be used.
+ * * `parameter` - Winternitz parameter and tree height of the LMS tree.
+ * * `seed` - The seed, from which SEED and `I` are derived like for [`crate::keygen`].
+ * * `aux_data` - The reference to a slice to auxiliary data. This can be used to speedup signature generation.
+ */
+pub fn keygen<H: HashChain>(
+    parameter: HssParameter<H>,
+    seed: &Seed<H>,
+    aux_data: Option<&mut &mut [u8]>,
+) -> Result<(LmsSigningKey<H>, LmsVerifyingKey<H>), Error> {
+    let (signing_key, verifying_key) = hss_keygen::<H>(&[parameter], seed, aux_data)?;
+
+    Ok((
+        LmsSigningKey::try_from(signing_key)?,
+        LmsVerifyingKey::try_from(verifying_key)?,
+    ))
+}
+
+/**
+ * Generate [`LmsSigningKey`] and [`LmsVerifyingKey`] from explicit SEED and `I`, see
+ * [`crate::keygen_with_lms_tree_identifier`].
+ */
+pub fn keygen_with_lms_tree_identifier<H: HashChain>(
+    parameter: HssParameter<H>,
+    seed: &Seed<H>,
+    lms_tree_identifier: &LmsTreeIdentifier,
+    aux_data: Option<&mut &mut [u8]>,
+) -> Result<(LmsSigningKey<H>, LmsVerifyingKey<H>), Error> {
+    let (signing_key, verifying_key) = hss_keygen_with_lms_tree_identifier::<H>(
+        &[parameter],
+        seed,
+        lms_tree_identifier,
+        aux_data,
+    )?;
+
+    Ok((
+        LmsSigningKey::try_from(signing_key)?,
+        LmsVerifyingKey::try_from(verifying_key)?,
+    ))
+}
+
+/**
+ * Generate an [`LmsSignature`].
+ *
+ * # Arguments
+ * * `HashChain` - The hasher implementation that should be used.
+ * * `message` - The message that should be signed.
+ * * `state_store` - The [`StateStore`] that holds the single-level private key. Private keys of more than one level fail with [`Error::InvalidParameters`] without advancing their state.
+ * * `aux_data` - Auxiliary data to speedup signature generation if available.
+ */
+pub fn sign<H: HashChain>(
+    message: &[u8],
+    state_store: &mut dyn StateStore,
+    aux_data: Option<&mut &mut [u8]>,
+) -> Result<LmsSignature, Error> {
+    let mut state_store = SingleLevelStateStore::<H> {
+        state_store,
+        phantom: PhantomData,
+    };
+    LmsSignature::try_from(hss_sign::<H>(message, &mut state_store, aux_data)?)
+}
+
+/**
+ * Fails to load private keys of more than one level, so that they are refused before signing.
+ */
+struct SingleLevelStateStore<'a, H: HashChain> {
+    state_store: &'a mut dyn StateStore,
+    phantom: PhantomData<H>,
+}
+
+impl<'a, H: HashChain> StateStore for SingleLevelStateStore<'a, H> {
+    fn lock(&mut self) -> Result<(), Error> {
+        self.state_store.lock()
+    }
+
+    fn unlock(&mut self) -> Result<(), Error> {
+        self.state_store.unlock()
+    }
+
+    fn load(&mut self) -> Result<ArrayVec<[u8; MAX_PRIVATE_KEY_SIZE]>, Error> {
+        let private_key = self.state_store.load()?;
+        let rfc_private_key =
+            ReferenceImplPrivateKey::<H>::from_binary_representation(private_key.as_slice())?;
+        if KeyUsage::from_private_key(&rfc_private_key)?
+            .leaf_indexes
+            .len()
+            != 1
+        {
+            return Err(Error::InvalidParameters);
+        }
+
+        Ok(private_key)
+    }
+
+    fn store(&mut self, private_key: &[u8]) -> Result<(), Error> {
+        self.state_store.store(private_key)
+    }
+}
+
+/**
+ * Verify an [`LmsSignature`] with an LMS public key.
+ *
+ * # Arguments
+ * * `HashChain` - The hasher implementation that should be used.
+ * * `message` - The message that should be verified.
+ * * `signature` - The LMS signature that should be used for verification.
+ * * `public_key` - The LMS public key that should be used for verification.
+ */
+pub fn verify<H: HashChain>(
+    message: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<(), Error> {
+    let signature = signing::InMemoryLmsSignature::<H>::new(signature)?;
+    let public_key = InMemoryLmsPublicKey::<H>::new(public_key)?;
+
+    verify::verify(&signature, &public_key, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hss::hss_verify, util::helper::test_helper::gen_random_seed, LmotsAlgorithm, LmsAlgorithm,
+        Sha256_256, Shake256_192,
+    };
+
+    #[test]
+    fn sign_and_verify() {
+        type H = Shake256_192;
+        let seed = gen_random_seed::<H>();
+        let parameter = HssParameter::new(LmotsAlgorithm::LmotsW2, LmsAlgorithm::LmsH5);
+
+        let (mut signing_key, verifying_key) = keygen::<H>(parameter, &seed, None).unwrap();
+
+        let signature = signing_key.try_sign(b"message").unwrap();
+        assert!(verifying_key.verify(b"message", &signature).is_ok());
+        assert!(verifying_key.verify(b"massage", &signature).is_err());
+        assert!(verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok());
+
+        let signature = sign::<H>(b"message", &mut signing_key, None).unwrap();
+        assert!(verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok());
+        assert_eq!(signing_key.usage().unwrap().used, 2);
+    }
+
+    #[test]
+    fn convert_from_and_to_hss() {
+        type H = Sha256_256;
+        let seed = gen_random_seed::<H>();
+        let parameter = HssParameter::new(LmotsAlgorithm::LmotsW2, LmsAlgorithm::LmsH5);
+
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&[parameter], &seed, None).unwrap();
+        let signature = hss_sign::<H>(b"message", &mut signing_key, None).unwrap();
+        let hss_signature_bytes =
+            ArrayVec::<[u8; MAX_LMS_SIGNATURE_LENGTH + 4]>::try_from(signature.as_ref()).unwrap();
+
+        let lms_signing_key = LmsSigningKey::try_from(signing_key.clone()).unwrap();
+        let lms_verifying_key = LmsVerifyingKey::try_from(verifying_key.clone()).unwrap();
+        let lms_signature = LmsSignature::try_from(signature).unwrap();
+
+        assert_eq!(lms_signing_key.as_slice(), signing_key.as_slice());
+        assert_eq!(lms_verifying_key.as_slice(), &verifying_key.as_slice()[4..]);
+        assert_eq!(lms_signature.as_ref(), &hss_signature_bytes[4..]);
+        assert!(lms_verifying_key.verify(b"message", &lms_signature).is_ok());
+
+        assert_eq!(SigningKey::from(lms_signing_key), signing_key);
+        assert_eq!(VerifyingKey::from(lms_verifying_key), verifying_key);
+        let signature = crate::Signature::from(lms_signature);
+        assert_eq!(signature.as_ref(), hss_signature_bytes.as_slice());
+        assert!(hss_verify::<H>(b"message", signature.as_ref(), verifying_key.as_slice()).is_ok());
+
+        // Keys and signatures of more than one level can not be converted
+        let parameters = [parameter, parameter];
+        let (mut signing_key, verifying_key) = hss_keygen::<H>(&parameters, &seed, None).unwrap();
+        let signature = hss_sign::<H>(b"message", &mut signing_key, None).unwrap();
+
+        assert_eq!(
+            LmsSigningKey::try_from(signing_key.clone()),
+            Err(Error::InvalidParameters)
+        );
+        assert_eq!(
+            LmsVerifyingKey::try_from(verifying_key),
+            Err(Error::InvalidParameters)
+        );
+        assert_eq!(
+            LmsSignature::try_from(signature).err(),
+            Some(Error::InvalidParameters)
+        );
+        let stored_signing_key = signing_key.clone();
+        assert_eq!(
+            sign::<H>(b"message", &mut signing_key, None).err(),
+            Some(Error::InvalidParameters)
+        );
+        assert_eq!(signing_key, stored_signing_key);
+    }
+}
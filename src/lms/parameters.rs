@@ -2,11 +2,13 @@ use core::marker::PhantomData;
 
 use crate::hasher::{sha256::Sha256_256, HashChain};
 
-/// Specifies the used Tree height.
+/// Specifies the used Tree height. The discriminants are the typecodes of SHA-256/M32.
 #[derive(Default, Clone, Copy)]
 pub enum LmsAlgorithm {
     #[default]
     LmsReserved = 0,
+    /// Test-only, the discriminant is only used by the compressed parameter set. Its typecode is
+    /// in the private use range.
     #[cfg(test)]
     LmsH2 = 1,
     LmsH5 = 5,
@@ -31,28 +33,50 @@ impl From<u32> for LmsAlgorithm {
     }
 }
 
+/// Test-only typecode of [`LmsAlgorithm::LmsH2`] in the private use range. Added to the offset of
+/// any hash function, it wraps around into the private use range again.
+#[cfg(test)]
+const LMS_H2_TYPECODE: u32 = 0xf000_0001;
+
 impl LmsAlgorithm {
     pub fn construct_default_parameter() -> LmsParameter<Sha256_256> {
         LmsAlgorithm::LmsH5.construct_parameter().unwrap()
     }
 
+    fn get_type_id<H: HashChain>(&self) -> u32 {
+        match *self {
+            #[cfg(test)]
+            LmsAlgorithm::LmsH2 => LMS_H2_TYPECODE.wrapping_add(H::LMS_TYPECODE_OFFSET),
+            _ => *self as u32 + H::LMS_TYPECODE_OFFSET,
+        }
+    }
+
     pub fn construct_parameter<H: HashChain>(&self) -> Option<LmsParameter<H>> {
+        let type_id = self.get_type_id::<H>();
+
         match *self {
             LmsAlgorithm::LmsReserved => None,
             #[cfg(test)]
-            LmsAlgorithm::LmsH2 => Some(LmsParameter::new(1, 2)),
-            LmsAlgorithm::LmsH5 => Some(LmsParameter::new(5, 5)),
-            LmsAlgorithm::LmsH10 => Some(LmsParameter::new(6, 10)),
-            LmsAlgorithm::LmsH15 => Some(LmsParameter::new(7, 15)),
-            LmsAlgorithm::LmsH20 => Some(LmsParameter::new(8, 20)),
-            LmsAlgorithm::LmsH25 => Some(LmsParameter::new(9, 25)),
+            LmsAlgorithm::LmsH2 => Some(LmsParameter::new(type_id, 2)),
+            LmsAlgorithm::LmsH5 => Some(LmsParameter::new(type_id, 5)),
+            LmsAlgorithm::LmsH10 => Some(LmsParameter::new(type_id, 10)),
+            LmsAlgorithm::LmsH15 => Some(LmsParameter::new(type_id, 15)),
+            LmsAlgorithm::LmsH20 => Some(LmsParameter::new(type_id, 20)),
+            LmsAlgorithm::LmsH25 => Some(LmsParameter::new(type_id, 25)),
         }
     }
 
+    /**
+     * Returns the parameter of the typecode `_type`, if it is assigned to the hash function and
+     * output size of `H`.
+     */
     pub fn get_from_type<H: HashChain>(_type: u32) -> Option<LmsParameter<H>> {
-        match _type {
-            #[cfg(test)]
-            1 => LmsAlgorithm::LmsH2.construct_parameter(),
+        #[cfg(test)]
+        if _type == LmsAlgorithm::LmsH2.get_type_id::<H>() {
+            return LmsAlgorithm::LmsH2.construct_parameter();
+        }
+
+        match _type.checked_sub(H::LMS_TYPECODE_OFFSET)? {
             5 => LmsAlgorithm::LmsH5.construct_parameter(),
             6 => LmsAlgorithm::LmsH10.construct_parameter(),
             7 => LmsAlgorithm::LmsH15.construct_parameter(),
@@ -89,6 +113,15 @@ impl<H: HashChain> LmsParameter<H> {
         self.type_id
     }
 
+    pub fn get_algorithm(&self) -> LmsAlgorithm {
+        #[cfg(test)]
+        if self.type_id == LmsAlgorithm::LmsH2.get_type_id::<H>() {
+            return LmsAlgorithm::LmsH2;
+        }
+
+        LmsAlgorithm::from(self.type_id - H::LMS_TYPECODE_OFFSET)
+    }
+
     pub fn get_hash_function_output_size(&self) -> usize {
         Self::HASH_FUNCTION_OUTPUT_SIZE
     }
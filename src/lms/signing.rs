@@ -10,13 +10,15 @@ use crate::lm_ots::signing::InMemoryLmotsSignature;
 use crate::lm_ots::signing::LmotsSignature;
 use crate::lms::definitions::LmsPrivateKey;
 use crate::lms::parameters::LmsAlgorithm;
-use crate::util::helper::{read, read_and_advance};
+use crate::util::helper::try_read_and_advance;
+use crate::Error;
 
 use core::convert::TryInto;
 use tinyvec::ArrayVec;
 
-use super::helper::get_tree_element;
+use super::helper::get_authentication_path;
 use super::parameters::LmsParameter;
+use super::traversal::MerkleTraversal;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct LmsSignature<H: HashChain> {
@@ -65,19 +67,23 @@ impl<H: HashChain> LmsSignature<H> {
         lms_private_key: &mut LmsPrivateKey<H>,
         lm_ots_private_key: &LmotsPrivateKey<H>,
         aux_data: &mut Option<MutableExpandedAuxData>,
-    ) -> Result<ArrayVec<[ArrayVec<[u8; MAX_HASH_SIZE]>; MAX_TREE_HEIGHT]>, ()> {
-        let tree_height = lms_private_key.lms_parameter.get_tree_height();
-        let signature_leaf_index = 2usize.pow(tree_height as u32)
-            + u32::from_be_bytes(lm_ots_private_key.lms_leaf_identifier) as usize;
-
-        let mut authentication_path = ArrayVec::new();
-
-        for i in 0..tree_height.into() {
-            let tree_index = (signature_leaf_index / (2usize.pow(i as u32))) ^ 0x1;
-            authentication_path.push(get_tree_element(tree_index, lms_private_key, aux_data));
+        tree_traversal: Option<&mut MerkleTraversal<H>>,
+    ) -> Result<ArrayVec<[ArrayVec<[u8; MAX_HASH_SIZE]>; MAX_TREE_HEIGHT]>, Error> {
+        let lms_leaf_identifier = u32::from_be_bytes(lm_ots_private_key.lms_leaf_identifier);
+
+        if let Some(tree_traversal) = tree_traversal {
+            if tree_traversal.is_at(lms_private_key, lms_leaf_identifier) {
+                let authentication_path = *tree_traversal.authentication_path();
+                tree_traversal.next(lms_private_key);
+                return Ok(authentication_path);
+            }
         }
 
-        Ok(authentication_path)
+        Ok(get_authentication_path(
+            lms_leaf_identifier,
+            lms_private_key,
+            aux_data,
+        ))
     }
 
     #[cfg(feature = "fast_verify")]
@@ -87,7 +93,8 @@ impl<H: HashChain> LmsSignature<H> {
         message_mut: Option<&mut [u8]>,
         signature_randomizer: &mut ArrayVec<[u8; MAX_HASH_SIZE]>,
         aux_data: &mut Option<MutableExpandedAuxData>,
-    ) -> Result<LmsSignature<H>, ()> {
+        tree_traversal: Option<&mut MerkleTraversal<H>>,
+    ) -> Result<LmsSignature<H>, Error> {
         let lm_ots_private_key = lms_private_key.use_lmots_private_key()?;
 
         let ots_signature = LmotsSignature::sign_fast_verify(
@@ -101,6 +108,7 @@ impl<H: HashChain> LmsSignature<H> {
             lms_private_key,
             &lm_ots_private_key,
             aux_data,
+            tree_traversal,
         )?;
 
         let signature = LmsSignature {
@@ -118,7 +126,27 @@ impl<H: HashChain> LmsSignature<H> {
         message: &[u8],
         signature_randomizer: &ArrayVec<[u8; MAX_HASH_SIZE]>,
         aux_data: &mut Option<MutableExpandedAuxData>,
-    ) -> Result<LmsSignature<H>, ()> {
+    ) -> Result<LmsSignature<H>, Error> {
+        Self::sign_with_tree_traversal(
+            lms_private_key,
+            message,
+            signature_randomizer,
+            aux_data,
+            None,
+        )
+    }
+
+    /**
+     * Like [`LmsSignature::sign`], but takes the authentication path from `tree_traversal` if it
+     * is at the leaf of the signature.
+     */
+    pub fn sign_with_tree_traversal(
+        lms_private_key: &mut LmsPrivateKey<H>,
+        message: &[u8],
+        signature_randomizer: &ArrayVec<[u8; MAX_HASH_SIZE]>,
+        aux_data: &mut Option<MutableExpandedAuxData>,
+        tree_traversal: Option<&mut MerkleTraversal<H>>,
+    ) -> Result<LmsSignature<H>, Error> {
         let lm_ots_private_key = lms_private_key.use_lmots_private_key()?;
 
         let ots_signature =
@@ -128,6 +156,7 @@ impl<H: HashChain> LmsSignature<H> {
             lms_private_key,
             &lm_ots_private_key,
             aux_data,
+            tree_traversal,
         )?;
 
         let signature = LmsSignature {
@@ -160,39 +189,52 @@ impl<H: HashChain> LmsSignature<H> {
 }
 
 impl<'a, H: HashChain> InMemoryLmsSignature<'a, H> {
-    pub fn new(data: &'a [u8]) -> Option<Self> {
+    pub fn new(data: &'a [u8]) -> Result<Self, Error> {
         // Parsing like 5.4.2 Algorithm 6a
         let mut index = 0;
 
-        let lms_leaf_identifier =
-            u32::from_be_bytes(read_and_advance(data, 4, &mut index).try_into().unwrap());
+        let lms_leaf_identifier = u32::from_be_bytes(
+            try_read_and_advance(data, 4, &mut index, Error::MalformedSignature)?
+                .try_into()
+                .unwrap(),
+        );
 
         // LMOTS Signature consists of LMOTS parameter, signature randomizer & signature data
-        let lmots_parameter = LmotsAlgorithm::get_from_type::<H>(u32::from_be_bytes(
-            read(data, 4, &index).try_into().unwrap(),
-        ))
-        .unwrap();
-        let lmots_signature = lm_ots::signing::InMemoryLmotsSignature::new(read_and_advance(
+        let lmots_type = u32::from_be_bytes(
+            data.get(index..index + 4)
+                .ok_or(Error::MalformedSignature)?
+                .try_into()
+                .unwrap(),
+        );
+        let lmots_parameter = LmotsAlgorithm::get_from_type::<H>(lmots_type)
+            .ok_or(Error::UnsupportedTypecode(lmots_type))?;
+        let lmots_signature = lm_ots::signing::InMemoryLmotsSignature::new(try_read_and_advance(
             data,
             (4 + H::OUTPUT_SIZE * (1 + lmots_parameter.get_num_winternitz_chains())) as usize,
             &mut index,
-        ))
-        .unwrap();
+            Error::MalformedSignature,
+        )?)?;
 
-        let _type = u32::from_be_bytes(read_and_advance(data, 4, &mut index).try_into().unwrap());
+        let _type = u32::from_be_bytes(
+            try_read_and_advance(data, 4, &mut index, Error::MalformedSignature)?
+                .try_into()
+                .unwrap(),
+        );
 
-        let lms_parameter = LmsAlgorithm::get_from_type(_type).unwrap();
-        let authentication_path = read_and_advance(
+        let lms_parameter =
+            LmsAlgorithm::get_from_type(_type).ok_or(Error::UnsupportedTypecode(_type))?;
+        let authentication_path = try_read_and_advance(
             data,
             (H::OUTPUT_SIZE * lms_parameter.get_tree_height() as u16) as usize,
             &mut index,
-        );
+            Error::MalformedSignature,
+        )?;
 
         if lms_leaf_identifier >= lms_parameter.number_of_lm_ots_keys() as u32 {
-            return None;
+            return Err(Error::MalformedSignature);
         }
 
-        Some(Self {
+        Ok(Self {
             lms_parameter,
             lms_leaf_identifier,
             lmots_signature,
@@ -0,0 +1,512 @@
+use core::convert::TryInto;
+
+use subtle::ConstantTimeEq;
+use tinyvec::ArrayVec;
+
+use crate::{
+    constants::{LmsTreeIdentifier, Node, D_TREE_TRAVERSAL, ILEN, MAX_TREE_HEIGHT},
+    hasher::HashChain,
+    hss::aux::{compute_hmac_ipad, compute_hmac_opad, compute_seed_derive},
+    lm_ots::parameters::LmotsAlgorithm,
+    lms::{
+        definitions::LmsPrivateKey,
+        helper::{get_intermediate_node, get_tree_element},
+        parameters::LmsAlgorithm,
+    },
+    util::helper::try_read_and_advance,
+    Error,
+};
+
+/**
+ * Incremental computation of a node of an LMS tree, one leaf per step.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct TreeHash {
+    /// Height of the node above the leafs.
+    height: usize,
+    first_leaf: usize,
+    next_leaf: usize,
+    /// Completed subtrees with their height, the rightmost on top.
+    stack: ArrayVec<[(usize, Node); MAX_TREE_HEIGHT + 1]>,
+}
+
+impl TreeHash {
+    /**
+     * Computes the node at `height`, whose leftmost leaf is `first_leaf`.
+     */
+    pub fn new(height: usize, first_leaf: usize) -> Self {
+        Self {
+            height,
+            first_leaf,
+            next_leaf: first_leaf,
+            stack: ArrayVec::new(),
+        }
+    }
+
+    /**
+     * Instance without any node to compute.
+     */
+    fn finished(height: usize, first_leaf: usize) -> Self {
+        Self {
+            height,
+            first_leaf,
+            next_leaf: first_leaf + (1 << height),
+            stack: ArrayVec::new(),
+        }
+    }
+
+    pub fn remaining_leafs(&self) -> usize {
+        self.first_leaf + (1 << self.height) - self.next_leaf
+    }
+
+    /**
+     * Computes the next leaf and merges the completed subtrees. Every computed node is passed to
+     * `on_node` with its height and index.
+     */
+    pub fn step<H: HashChain>(
+        &mut self,
+        private_key: &LmsPrivateKey<H>,
+        on_node: &mut impl FnMut(usize, usize, &Node),
+    ) {
+        let leaf_index = private_key.lms_parameter.number_of_lm_ots_keys() + self.next_leaf;
+        self.next_leaf += 1;
+
+        let node = get_tree_element(leaf_index, private_key, &mut None);
+        on_node(0, leaf_index, &node);
+        self.stack.push((0, node));
+
+        while self.stack.len() >= 2
+            && self.stack[self.stack.len() - 1].0 == self.stack[self.stack.len() - 2].0
+        {
+            let (height, right) = self.stack.pop().unwrap();
+            let (_, left) = self.stack.pop().unwrap();
+            let index = leaf_index >> (height + 1);
+            let node = get_intermediate_node(index, private_key, &left, &right);
+            on_node(height + 1, index, &node);
+            self.stack.push((height + 1, node));
+        }
+    }
+
+    /**
+     * Returns the node, once all leafs are processed.
+     */
+    pub fn node(&self) -> Option<Node> {
+        match self.stack.as_slice() {
+            [(_, node)] if self.remaining_leafs() == 0 => Some(*node),
+            _ => None,
+        }
+    }
+
+    /// Height of the lowest subtree on the stack, `None` if there is nothing left to compute.
+    fn lowest_height(&self) -> Option<usize> {
+        if self.remaining_leafs() == 0 {
+            return None;
+        }
+        Some(self.stack.last().map_or(self.height, |(height, _)| *height))
+    }
+}
+
+/**
+ * State of the traversal of an LMS tree, which yields the authentication paths of consecutive
+ * leafs.
+ *
+ * Follows the logarithmic traversal of Szydlo, "Merkle Tree Traversal in Log Space and Time"
+ * (EUROCRYPT 2004). For every height `k`, a [`TreeHash`] instance computes the node of the
+ * authentication path that is needed next at `k`. Moving on to the next leaf computes at most
+ * `2 * h - 1` leafs, where `h` is the tree height. The setup for the first leaf processes the
+ * whole tree once.
+ *
+ * Layout of the serialized state: `u32str(lms type) || u32str(lmots type) || I || u32str(leaf) ||
+ * authentication path || (u32str(first leaf) || u32str(next leaf) || u32str(stack length) ||
+ * (u32str(height) || node)*)* || HMAC`. The HMAC is keyed with a key derived from the seed of the
+ * tree.
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct MerkleTraversal<H: HashChain> {
+    lms_tree_identifier: LmsTreeIdentifier,
+    lms_type: u32,
+    lmots_type: u32,
+    tree_height: usize,
+    leaf: u32,
+    authentication_path: ArrayVec<[Node; MAX_TREE_HEIGHT]>,
+    tree_hash: ArrayVec<[TreeHash; MAX_TREE_HEIGHT]>,
+    /// HMAC of a loaded state, which is checked before the state is used.
+    hmac: Option<Node>,
+    phantom_data: core::marker::PhantomData<H>,
+}
+
+impl<H: HashChain> MerkleTraversal<H> {
+    /**
+     * Sets up the traversal at the current leaf of `private_key`, which processes the whole tree.
+     */
+    pub fn new(private_key: &LmsPrivateKey<H>) -> Self {
+        let mut traversal = Self::empty(private_key);
+        let mut tree_hash = TreeHash::new(traversal.tree_height, 0);
+        while tree_hash.remaining_leafs() > 0 {
+            tree_hash.step(private_key, &mut |height, index, node| {
+                traversal.setup_node(height, index, node)
+            });
+        }
+        traversal
+    }
+
+    /**
+     * Traversal at the current leaf of `private_key` without any nodes. The nodes are filled by
+     * passing all nodes of the tree to [`MerkleTraversal::setup_node`].
+     */
+    pub fn empty(private_key: &LmsPrivateKey<H>) -> Self {
+        let tree_height = private_key.lms_parameter.get_tree_height() as usize;
+        let leafs = private_key.lms_parameter.number_of_lm_ots_keys();
+        let leaf = private_key.used_leafs_index as usize;
+
+        let mut authentication_path = ArrayVec::new();
+        authentication_path.resize(tree_height, Node::default());
+
+        // At height `k`, the node after the current one is the sibling of the next block of leafs
+        let tree_hash = (0..tree_height)
+            .map(|height| {
+                let next_block = (leaf >> height) + 1;
+                TreeHash::finished(height, ((next_block ^ 1) << height).min(leafs))
+            })
+            .collect();
+
+        Self {
+            lms_tree_identifier: private_key.lms_tree_identifier,
+            lms_type: private_key.lms_parameter.get_type_id(),
+            lmots_type: private_key.lmots_parameter.get_type_id(),
+            tree_height,
+            leaf: private_key.used_leafs_index,
+            authentication_path,
+            tree_hash,
+            hmac: None,
+            phantom_data: core::marker::PhantomData,
+        }
+    }
+
+    /**
+     * Stores the node at `index` and `height`, if the traversal needs it.
+     */
+    pub fn setup_node(&mut self, height: usize, index: usize, node: &Node) {
+        if height >= self.tree_height {
+            return;
+        }
+
+        let leaf_index = (1usize << self.tree_height) + self.leaf as usize;
+        if (leaf_index >> height) ^ 1 == index {
+            self.authentication_path[height] = *node;
+        }
+
+        let tree_hash = &mut self.tree_hash[height];
+        let first_leaf = tree_hash.first_leaf;
+        if first_leaf < 1 << self.tree_height
+            && ((1usize << self.tree_height) + first_leaf) >> height == index
+        {
+            tree_hash.stack.clear();
+            tree_hash.stack.push((height, *node));
+        }
+    }
+
+    /**
+     * Returns whether the traversal is at `leaf` of the tree of `private_key`.
+     */
+    pub fn is_at(&self, private_key: &LmsPrivateKey<H>, leaf: u32) -> bool {
+        self.lms_tree_identifier == private_key.lms_tree_identifier
+            && self.lms_type == private_key.lms_parameter.get_type_id()
+            && self.lmots_type == private_key.lmots_parameter.get_type_id()
+            && self.leaf == leaf
+    }
+
+    /**
+     * Returns the traversal of `traversal` if it is at the current leaf of `private_key`, and sets
+     * up a new one otherwise. Fails with [`Error::InvalidTreeTraversal`] if the HMAC of a loaded
+     * traversal does not match.
+     */
+    pub fn prepare<'a>(
+        traversal: &'a mut Option<Self>,
+        private_key: &LmsPrivateKey<H>,
+    ) -> Result<&'a mut Self, Error> {
+        match traversal {
+            Some(current) if current.is_at(private_key, private_key.used_leafs_index) => {
+                if let Some(hmac) = current.hmac.take() {
+                    if !bool::from(current.compute_hmac(private_key).ct_eq(&hmac)) {
+                        *traversal = None;
+                        return Err(Error::InvalidTreeTraversal);
+                    }
+                }
+            }
+            _ => *traversal = Some(Self::new(private_key)),
+        }
+
+        Ok(traversal.as_mut().unwrap())
+    }
+
+    pub fn authentication_path(&self) -> &ArrayVec<[Node; MAX_TREE_HEIGHT]> {
+        &self.authentication_path
+    }
+
+    /**
+     * Moves on to the next leaf of `private_key`, whose current leaf is the one of the traversal.
+     */
+    pub fn next(&mut self, private_key: &LmsPrivateKey<H>) {
+        let leafs = 1usize << self.tree_height;
+        let next_leaf = self.leaf as usize + 1;
+        self.leaf += 1;
+        if next_leaf >= leafs {
+            return;
+        }
+
+        for height in 0..self.tree_height {
+            if next_leaf % (1 << height) != 0 {
+                break;
+            }
+
+            let index = ((leafs + next_leaf) >> height) ^ 1;
+            self.authentication_path[height] = self.tree_hash[height]
+                .node()
+                // Only a setup for another leaf misses the node
+                .unwrap_or_else(|| get_tree_element(index, private_key, &mut None));
+
+            let first_leaf = (next_leaf + (1 << height)) ^ (1 << height);
+            self.tree_hash[height] = if first_leaf < leafs {
+                TreeHash::new(height, first_leaf)
+            } else {
+                TreeHash::finished(height, leafs)
+            };
+        }
+
+        for _ in 0..(2 * self.tree_height).saturating_sub(1) {
+            let focus = self
+                .tree_hash
+                .iter()
+                .enumerate()
+                .filter_map(|(height, tree_hash)| Some((tree_hash.lowest_height()?, height)))
+                .min();
+            match focus {
+                Some((_, height)) => self.tree_hash[height].step(private_key, &mut |_, _, _| ()),
+                None => break,
+            }
+        }
+    }
+
+    /**
+     * Length of the serialized traversal.
+     */
+    pub fn binary_len(&self) -> usize {
+        let mut len = 0;
+        self.write(&mut |data| len += data.len());
+        len + H::OUTPUT_SIZE as usize
+    }
+
+    /**
+     * Serializes the traversal of `private_key` into `data` and returns its length.
+     */
+    pub fn to_binary_representation(
+        &self,
+        private_key: &LmsPrivateKey<H>,
+        data: &mut [u8],
+    ) -> Result<usize, Error> {
+        let len = self.binary_len();
+        if data.len() < len {
+            return Err(Error::InvalidParameters);
+        }
+
+        let mut index = 0;
+        self.write(&mut |value| {
+            data[index..index + value.len()].copy_from_slice(value);
+            index += value.len();
+        });
+        data[index..len].copy_from_slice(self.compute_hmac(private_key).as_slice());
+
+        Ok(len)
+    }
+
+    /**
+     * Parses a serialized traversal. Its HMAC is checked by [`MerkleTraversal::prepare`].
+     */
+    pub fn from_binary_representation(data: &[u8]) -> Result<Self, Error> {
+        let error = Error::InvalidTreeTraversal;
+        let mut index = 0;
+        let read_u32 = |index: &mut usize| -> Result<u32, Error> {
+            Ok(u32::from_be_bytes(
+                try_read_and_advance(data, 4, index, error)?
+                    .try_into()
+                    .unwrap(),
+            ))
+        };
+
+        let lms_type = read_u32(&mut index)?;
+        let lmots_type = read_u32(&mut index)?;
+        let lms_parameter = LmsAlgorithm::get_from_type::<H>(lms_type).ok_or(error)?;
+        LmotsAlgorithm::get_from_type::<H>(lmots_type).ok_or(error)?;
+        let tree_height = lms_parameter.get_tree_height() as usize;
+        let leafs = 1usize << tree_height;
+        let size_hash = H::OUTPUT_SIZE as usize;
+
+        let mut lms_tree_identifier = LmsTreeIdentifier::default();
+        lms_tree_identifier.copy_from_slice(try_read_and_advance(data, ILEN, &mut index, error)?);
+        let leaf = read_u32(&mut index)?;
+        if leaf as usize >= leafs {
+            return Err(error);
+        }
+
+        let read_node = |index: &mut usize| -> Result<Node, Error> {
+            Ok(try_read_and_advance(data, size_hash, index, error)?
+                .iter()
+                .copied()
+                .collect())
+        };
+        let mut authentication_path = ArrayVec::new();
+        for _ in 0..tree_height {
+            authentication_path.push(read_node(&mut index)?);
+        }
+
+        let mut tree_hash = ArrayVec::new();
+        for height in 0..tree_height {
+            let first_leaf = read_u32(&mut index)? as usize;
+            let next_leaf = read_u32(&mut index)? as usize;
+            let stack_len = read_u32(&mut index)? as usize;
+            if first_leaf > leafs
+                || next_leaf < first_leaf
+                || next_leaf > first_leaf + (1 << height)
+                || stack_len > height + 1
+            {
+                return Err(error);
+            }
+
+            let mut instance = TreeHash {
+                height,
+                first_leaf,
+                next_leaf,
+                stack: ArrayVec::new(),
+            };
+            for _ in 0..stack_len {
+                let node_height = read_u32(&mut index)? as usize;
+                if node_height > height {
+                    return Err(error);
+                }
+                instance.stack.push((node_height, read_node(&mut index)?));
+            }
+            tree_hash.push(instance);
+        }
+
+        let hmac = read_node(&mut index)?;
+        if index != data.len() {
+            return Err(error);
+        }
+
+        Ok(Self {
+            lms_tree_identifier,
+            lms_type,
+            lmots_type,
+            tree_height,
+            leaf,
+            authentication_path,
+            tree_hash,
+            hmac: Some(hmac),
+            phantom_data: core::marker::PhantomData,
+        })
+    }
+
+    fn write(&self, sink: &mut impl FnMut(&[u8])) {
+        sink(&self.lms_type.to_be_bytes());
+        sink(&self.lmots_type.to_be_bytes());
+        sink(&self.lms_tree_identifier);
+        sink(&self.leaf.to_be_bytes());
+        for node in self.authentication_path.iter() {
+            sink(node.as_slice());
+        }
+        for tree_hash in self.tree_hash.iter() {
+            sink(&(tree_hash.first_leaf as u32).to_be_bytes());
+            sink(&(tree_hash.next_leaf as u32).to_be_bytes());
+            sink(&(tree_hash.stack.len() as u32).to_be_bytes());
+            for (height, node) in tree_hash.stack.iter() {
+                sink(&(*height as u32).to_be_bytes());
+                sink(node.as_slice());
+            }
+        }
+    }
+
+    fn compute_hmac(&self, private_key: &LmsPrivateKey<H>) -> Node {
+        let key = compute_seed_derive::<H>(private_key.seed.as_slice(), D_TREE_TRAVERSAL);
+        let mut hasher = compute_hmac_ipad::<H>(&key);
+        self.write(&mut |data| hasher.update(data));
+        compute_hmac_opad::<H>(&mut hasher, &key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lms::helper::get_authentication_path, util::helper::test_helper::gen_random_seed,
+        Sha256_256,
+    };
+
+    type H = Sha256_256;
+
+    fn private_key(used_leafs_index: u32) -> LmsPrivateKey<H> {
+        LmsPrivateKey::new(
+            gen_random_seed::<H>(),
+            [3u8; ILEN],
+            used_leafs_index,
+            LmotsAlgorithm::LmotsW2.construct_parameter().unwrap(),
+            LmsAlgorithm::LmsH5.construct_parameter().unwrap(),
+        )
+    }
+
+    #[test]
+    fn consecutive_authentication_paths() {
+        for first_leaf in [0, 13] {
+            let mut private_key = private_key(first_leaf);
+            let mut traversal = MerkleTraversal::new(&private_key);
+
+            for leaf in first_leaf..32 {
+                assert!(traversal.is_at(&private_key, leaf));
+                assert_eq!(
+                    traversal.authentication_path(),
+                    &get_authentication_path(leaf, &private_key, &mut None)
+                );
+
+                // The nodes of the next leaf are computed in time
+                for height in 0..5 {
+                    if (leaf + 1) % (1 << height) == 0 && leaf + 1 < 32 {
+                        assert!(traversal.tree_hash[height].node().is_some());
+                    }
+                }
+
+                traversal.next(&private_key);
+                private_key.used_leafs_index += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn save_and_load_tree_traversal() {
+        let mut private_key = private_key(0);
+        let mut traversal = MerkleTraversal::new(&private_key);
+        for _ in 0..7 {
+            traversal.next(&private_key);
+            private_key.used_leafs_index += 1;
+        }
+
+        let mut data = [0u8; 4_096];
+        let len = traversal
+            .to_binary_representation(&private_key, &mut data)
+            .unwrap();
+        assert_eq!(len, traversal.binary_len());
+
+        let mut loaded = Some(MerkleTraversal::from_binary_representation(&data[..len]).unwrap());
+        assert_eq!(
+            MerkleTraversal::prepare(&mut loaded, &private_key).map(|loaded| loaded.clone()),
+            Ok(traversal)
+        );
+
+        data[len - 40] ^= 1;
+        let mut tampered = Some(MerkleTraversal::from_binary_representation(&data[..len]).unwrap());
+        assert_eq!(
+            MerkleTraversal::prepare(&mut tampered, &private_key).map(|_| ()),
+            Err(Error::InvalidTreeTraversal)
+        );
+        assert!(MerkleTraversal::<H>::from_binary_representation(&data[..len - 1]).is_err());
+    }
+}
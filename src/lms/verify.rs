@@ -4,6 +4,7 @@ use crate::constants::{D_INTR, D_LEAF, MAX_HASH_SIZE};
 use crate::hasher::HashChain;
 use crate::lm_ots;
 use crate::util::helper::is_odd;
+use crate::Error;
 
 use super::definitions::InMemoryLmsPublicKey;
 use super::signing::InMemoryLmsSignature;
@@ -12,11 +13,11 @@ pub fn verify<'a, H: HashChain>(
     signature: &InMemoryLmsSignature<'a, H>,
     public_key: &InMemoryLmsPublicKey<'a, H>,
     message: &[u8],
-) -> Result<(), ()> {
+) -> Result<(), Error> {
     if signature.lmots_signature.lmots_parameter != public_key.lmots_parameter
         || signature.lms_parameter != public_key.lms_parameter
     {
-        return Err(());
+        return Err(Error::VerificationFailed { level: 0 });
     }
 
     let public_key_canditate = generate_public_key_candidate(signature, public_key, message)?;
@@ -24,7 +25,7 @@ pub fn verify<'a, H: HashChain>(
     if public_key_canditate.as_slice() == public_key.key {
         Ok(())
     } else {
-        Err(())
+        Err(Error::VerificationFailed { level: 0 })
     }
 }
 
@@ -32,12 +33,12 @@ fn generate_public_key_candidate<'a, H: HashChain>(
     signature: &InMemoryLmsSignature<'a, H>,
     public_key: &InMemoryLmsPublicKey<'a, H>,
     message: &[u8],
-) -> Result<ArrayVec<[u8; MAX_HASH_SIZE]>, ()> {
+) -> Result<ArrayVec<[u8; MAX_HASH_SIZE]>, Error> {
     let leafs = signature.lms_parameter.number_of_lm_ots_keys() as u32;
 
     let curr = signature.lms_leaf_identifier;
     if curr >= leafs {
-        return Err(());
+        return Err(Error::VerificationFailed { level: 0 });
     }
 
     let ots_public_key_canditate = lm_ots::verify::generate_public_key_candidate(
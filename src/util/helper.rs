@@ -12,6 +12,21 @@ pub fn read_and_advance<'a>(src: &'a [u8], length: usize, index: &mut usize) ->
     result
 }
 
+/**
+ * Like [`read_and_advance`], but returns `error` instead of panicking if `src` is too short.
+ */
+pub fn try_read_and_advance<'a, E: Copy>(
+    src: &'a [u8],
+    length: usize,
+    index: &mut usize,
+    error: E,
+) -> Result<&'a [u8], E> {
+    let end = index.checked_add(length).ok_or(error)?;
+    let result = src.get(*index..end).ok_or(error)?;
+    *index = end;
+    Ok(result)
+}
+
 #[cfg(test)]
 pub mod test_helper {
     use crate::{HashChain, Seed};
@@ -5,7 +5,7 @@ use std::{
     process::Command,
 };
 
-use hbs_lms::{HssParameter, LmotsAlgorithm, LmsAlgorithm, Seed, Sha256_256};
+use hbs_lms::{HssParameter, LmotsAlgorithm, LmsAlgorithm, Seed, Sha256_256, SigningKey};
 use tempfile::TempDir;
 
 const MESSAGE_FILE_NAME: &str = "message.txt";
@@ -170,20 +170,11 @@ fn own_signing(
 ) {
     let aux_slice: &mut &mut [u8] = &mut aux_data;
 
-    let private_key_before = private_key.to_vec();
+    let mut signing_key = SigningKey::<Sha256_256>::from_bytes(private_key).unwrap();
 
-    let mut update_private_key = |new_key: &[u8]| {
-        private_key.copy_from_slice(new_key);
-        Ok(())
-    };
-
-    let result = hbs_lms::sign::<Sha256_256>(
-        message_data,
-        &private_key_before,
-        &mut update_private_key,
-        Some(aux_slice),
-    )
-    .expect("Signing should succed.");
+    let result = hbs_lms::sign::<Sha256_256>(message_data, &mut signing_key, Some(aux_slice))
+        .expect("Signing should succed.");
+    private_key.copy_from_slice(signing_key.as_slice());
     save_file(
         temp_path.path().join(SIGNATURE_FILE_NAME).to_str().unwrap(),
         result.as_ref(),
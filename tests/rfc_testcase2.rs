@@ -1,10 +1,121 @@
-use hbs_lms::Sha256_256;
+use hbs_lms::{
+    lms, signature::Verifier, HssParameter, LmotsAlgorithm, LmsAlgorithm, Seed, Sha256_256,
+    SigningKey,
+};
 
 #[test]
 fn test() {
     assert!(hbs_lms::verify::<Sha256_256>(MESSAGE, SIGNATURE, PUBLIC_KEY).is_ok());
 }
 
+#[test]
+fn keygen_from_private_key() {
+    let parameters = [
+        HssParameter::new(LmotsAlgorithm::LmotsW4, LmsAlgorithm::LmsH10),
+        HssParameter::new(LmotsAlgorithm::LmotsW8, LmsAlgorithm::LmsH5),
+    ];
+    let mut seed = Seed::default();
+    seed.as_mut_slice().copy_from_slice(&TOP_LEVEL_SEED);
+
+    let (_, verifying_key) = hbs_lms::keygen_with_lms_tree_identifier::<Sha256_256>(
+        &parameters,
+        &seed,
+        &TOP_LEVEL_I,
+        None,
+    )
+    .unwrap();
+    assert_eq!(verifying_key.as_slice(), PUBLIC_KEY);
+}
+
+#[test]
+fn keygen_from_child_private_key() {
+    let parameters = [HssParameter::new(
+        LmotsAlgorithm::LmotsW8,
+        LmsAlgorithm::LmsH5,
+    )];
+    let mut seed = Seed::default();
+    seed.as_mut_slice().copy_from_slice(&CHILD_SEED);
+
+    let (mut signing_key, verifying_key) =
+        hbs_lms::keygen_with_lms_tree_identifier::<Sha256_256>(&parameters, &seed, &CHILD_I, None)
+            .unwrap();
+
+    // The signed public key follows the LMS signature of the top-level tree in the signature
+    let child_public_key = &SIGNATURE[2512..2568];
+    assert_eq!(&verifying_key.as_slice()[..4], &[0, 0, 0, 1]);
+    assert_eq!(&verifying_key.as_slice()[4..], child_public_key);
+
+    let signature = hbs_lms::sign::<Sha256_256>(MESSAGE, &mut signing_key, None).unwrap();
+    assert!(
+        hbs_lms::verify::<Sha256_256>(MESSAGE, signature.as_ref(), verifying_key.as_slice())
+            .is_ok()
+    );
+}
+
+#[test]
+fn sign_from_private_key() {
+    let parameters = [
+        HssParameter::new(LmotsAlgorithm::LmotsW4, LmsAlgorithm::LmsH10),
+        HssParameter::new(LmotsAlgorithm::LmotsW8, LmsAlgorithm::LmsH5),
+    ];
+    let mut seed = Seed::default();
+    seed.as_mut_slice().copy_from_slice(&TOP_LEVEL_SEED);
+
+    let (signing_key, _) = hbs_lms::keygen_with_lms_tree_identifier::<Sha256_256>(
+        &parameters,
+        &seed,
+        &TOP_LEVEL_I,
+        None,
+    )
+    .unwrap();
+
+    // The signature uses leaf 3 of the top-level tree and leaf 4 of the child tree. The leaf
+    // counter is stored in the first bytes of the private key.
+    let mut private_key = signing_key.as_slice().to_vec();
+    private_key[..8].copy_from_slice(&(3u64 * 32 + 4).to_be_bytes());
+    let mut signing_key = SigningKey::<Sha256_256>::from_bytes(&private_key).unwrap();
+
+    let signature = hbs_lms::sign::<Sha256_256>(MESSAGE, &mut signing_key, None).unwrap();
+    assert_eq!(signature.as_ref(), SIGNATURE);
+}
+
+#[test]
+fn lms_keygen_from_child_private_key() {
+    let parameter = HssParameter::new(LmotsAlgorithm::LmotsW8, LmsAlgorithm::LmsH5);
+    let mut seed = Seed::default();
+    seed.as_mut_slice().copy_from_slice(&CHILD_SEED);
+
+    let (mut signing_key, verifying_key) =
+        lms::keygen_with_lms_tree_identifier::<Sha256_256>(parameter, &seed, &CHILD_I, None)
+            .unwrap();
+    assert_eq!(verifying_key.as_slice(), &SIGNATURE[2512..2568]);
+
+    // The final LMS signature in the signature was generated by the child tree
+    let child_signature = &SIGNATURE[2568..];
+    assert!(lms::verify::<Sha256_256>(MESSAGE, child_signature, verifying_key.as_slice()).is_ok());
+
+    let signature = lms::sign::<Sha256_256>(MESSAGE, &mut signing_key, None).unwrap();
+    assert!(verifying_key.verify(MESSAGE, &signature).is_ok());
+}
+
+static TOP_LEVEL_SEED: [u8; 32] = [
+    0x55, 0x8b, 0x89, 0x66, 0xc4, 0x8a, 0xe9, 0xcb, 0x89, 0x8b, 0x42, 0x3c, 0x83, 0x44, 0x3a, 0xae,
+    0x01, 0x4a, 0x72, 0xf1, 0xb1, 0xab, 0x5c, 0xc8, 0x5c, 0xf1, 0xd8, 0x92, 0x90, 0x3b, 0x54, 0x39,
+];
+
+static TOP_LEVEL_I: [u8; 16] = [
+    0xd0, 0x8f, 0xab, 0xd4, 0xa2, 0x09, 0x1f, 0xf0, 0xa8, 0xcb, 0x4e, 0xd8, 0x34, 0xe7, 0x45, 0x34,
+];
+
+static CHILD_SEED: [u8; 32] = [
+    0xa1, 0xc4, 0x69, 0x6e, 0x26, 0x08, 0x03, 0x5a, 0x88, 0x61, 0x00, 0xd0, 0x5c, 0xd9, 0x99, 0x45,
+    0xeb, 0x33, 0x70, 0x73, 0x18, 0x84, 0xa8, 0x23, 0x5e, 0x2f, 0xb3, 0xd4, 0xd7, 0x1f, 0x25, 0x47,
+];
+
+static CHILD_I: [u8; 16] = [
+    0x21, 0x5f, 0x83, 0xb7, 0xcc, 0xb9, 0xac, 0xbc, 0xd0, 0x8d, 0xb9, 0x7b, 0x0d, 0x04, 0xdc, 0x2b,
+];
+
 static PUBLIC_KEY: &[u8] = &[
     0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x03, 0xd0, 0x8f, 0xab, 0xd4,
     0xa2, 0x09, 0x1f, 0xf0, 0xa8, 0xcb, 0x4e, 0xd8, 0x34, 0xe7, 0x45, 0x34, 0x32, 0xa5, 0x88, 0x85,